@@ -41,11 +41,18 @@ class Entry:
     def pack(self) -> bytes: ...
 
 def normalize(text: str) -> Entry: ...
+def normalize_batch(
+    texts: typing.List[str], threads: int = 0
+) -> typing.List[typing.Tuple[int, Entry | Exception]]: ...
 def parse(
     start_token_name: str, tokens: typing.List[OpaqueToken]
 ) -> typing.Tuple[
     ParserResult, typing.List[typing.Tuple[typing.Type, typing.Callable]]
 ]: ...
+# experimental, not used by the compiler yet
+def parse_ast(
+    start_token_name: str, tokens: typing.List[OpaqueToken]
+) -> typing.Any: ...
 def preload_spec(spec_filepath: str) -> None: ...
 def save_spec(spec_json: str, dst: str) -> None: ...
 
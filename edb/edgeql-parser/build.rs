@@ -0,0 +1,30 @@
+use std::path::PathBuf;
+
+fn main() {
+    if std::env::var_os("CARGO_FEATURE_EMBEDDED_SPEC").is_none() {
+        return;
+    }
+
+    // The spec is produced from the Python grammar by `setup.py build_parsers`,
+    // which also refreshes the copy checked in next to this file, so building
+    // the crate does not need Python.
+    println!("cargo:rerun-if-env-changed=EDGEQL_GRAMMAR_SPEC");
+    let path = match std::env::var_os("EDGEQL_GRAMMAR_SPEC") {
+        Some(path) => PathBuf::from(path),
+        None => {
+            let manifest_dir = std::env::var_os("CARGO_MANIFEST_DIR").unwrap();
+            PathBuf::from(manifest_dir).join("grammar.bc")
+        }
+    };
+    let path = path.canonicalize().unwrap_or_else(|e| {
+        panic!(
+            "cannot find grammar spec at {} ({e}), regenerate it with \
+            `python setup.py build_parsers --inplace` or point \
+            EDGEQL_GRAMMAR_SPEC to it",
+            path.display()
+        )
+    });
+
+    println!("cargo:rerun-if-changed={}", path.display());
+    println!("cargo:rustc-env=EDGEQL_GRAMMAR_SPEC={}", path.display());
+}
@@ -27,14 +27,14 @@ fn impl_enum_into_python(enum_: &mut syn::ItemEnum) -> TokenStream {
     let mut cases = Vec::new();
 
     if let Some(py_enum) = find_attr(&enum_.attrs, "py_enum") {
-        let class_path = py_enum.tokens.to_string();
+        let class_path = py_enum.tokens.to_string().replace(' ', "");
         let class_path = &class_path[1..class_path.len() - 1];
 
         for Variant { name } in variants {
-            let variant = format!("{class_path}.{name}");
+            let variant = name.to_string();
 
             cases.push(quote! {
-                Self::#name => py.eval(#variant, None, None),
+                Self::#name => crate::into_python::get_enum_variant(py, #class_path, #variant),
             });
         }
     } else if find_attr(&enum_.attrs, "py_child").is_some() {
@@ -55,11 +55,11 @@ fn impl_enum_into_python(enum_: &mut syn::ItemEnum) -> TokenStream {
 
     quote! {
         impl crate::into_python::IntoPython for #name {
-            fn into_python(
+            fn into_python<'py>(
                 self,
-                py: cpython::Python,
-                parent: Option<cpython::PyDict>,
-            ) -> cpython::PyResult<cpython::PyObject> {
+                py: pyo3::Python<'py>,
+                parent: Option<&'py pyo3::types::PyDict>,
+            ) -> pyo3::PyResult<pyo3::PyObject> {
                 use crate::into_python::IntoPython;
 
                 match self { #(#cases)* }
@@ -105,12 +105,11 @@ fn impl_struct_into_python(struct_: &mut syn::ItemStruct) -> TokenStream {
 
     let mut property_assigns = Vec::new();
     for property in properties {
+        // raw identifiers (r#type) map to plain Python attribute names
+        let property_name = property.to_string();
+        let property_name = property_name.trim_start_matches("r#");
         property_assigns.push(quote! {
-            kw_args.set_item(
-                py,
-                stringify!(#property),
-                self.#property.into_python(py, None)?
-            )?;
+            kw_args.set_item(#property_name, self.#property.into_python(py, None)?)?;
         });
     }
 
@@ -136,14 +135,14 @@ fn impl_struct_into_python(struct_: &mut syn::ItemStruct) -> TokenStream {
 
     quote! {
         impl crate::into_python::IntoPython for #name {
-            fn into_python(
+            fn into_python<'py>(
                 self,
-                py: cpython::Python,
-                parent_kw_args: Option<cpython::PyDict>,
-            ) -> cpython::PyResult<cpython::PyObject> {
+                py: pyo3::Python<'py>,
+                parent_kw_args: Option<&'py pyo3::types::PyDict>,
+            ) -> pyo3::PyResult<pyo3::PyObject> {
                 use crate::into_python::IntoPython;
 
-                let kw_args = parent_kw_args.unwrap_or_else(|| cpython::PyDict::new(py));
+                let kw_args = parent_kw_args.unwrap_or_else(|| pyo3::types::PyDict::new(py));
                 #(#property_assigns)*
 
                 #init
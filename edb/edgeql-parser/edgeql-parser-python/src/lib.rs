@@ -1,7 +1,6 @@
 mod errors;
 mod hash;
 mod keywords;
-pub mod normalize;
 mod parser;
 mod position;
 mod pynormalize;
@@ -26,8 +25,10 @@ fn _edgeql_parser(py: Python, m: &PyModule) -> PyResult<()> {
 
     m.add_class::<pynormalize::Entry>()?;
     m.add_function(wrap_pyfunction!(pynormalize::normalize, m)?)?;
+    m.add_function(wrap_pyfunction!(pynormalize::normalize_batch, m)?)?;
 
     m.add_function(wrap_pyfunction!(parser::parse, m)?)?;
+    m.add_function(wrap_pyfunction!(parser::parse_ast, m)?)?;
     m.add_function(wrap_pyfunction!(parser::preload_spec, m)?)?;
     m.add_function(wrap_pyfunction!(parser::save_spec, m)?)?;
     m.add_class::<parser::CSTNode>()?;
@@ -1,11 +1,13 @@
 use once_cell::sync::OnceCell;
 
-use edgeql_parser::parser;
+use edgeql_parser::into_python::IntoPython;
+use edgeql_parser::parser::{self, lower};
+use edgeql_parser::tokenizer::Error;
 use pyo3::exceptions::{PyAssertionError, PyValueError};
 use pyo3::prelude::*;
 use pyo3::types::{PyList, PyString, PyTuple};
 
-use crate::errors::{parser_error_into_tuple, ParserResult};
+use crate::errors::{parser_error_into_tuple, ParserResult, SyntaxError};
 use crate::pynormalize::value_to_py_object;
 use crate::tokenizer::OpaqueToken;
 
@@ -40,6 +42,49 @@ pub fn parse(
     Ok((res, productions.clone()))
 }
 
+/// Parses and lowers the tokens into `edb.edgeql.ast` objects.
+///
+/// Returns a list of statements for STARTBLOCK and a single expression for
+/// STARTFRAGMENT. Raises `SyntaxError` on the first parser or lowering error.
+///
+/// Experimental: the compiler still builds the AST from the CST returned by
+/// `parse`, this is only exercised by tests comparing the two ASTs.
+#[pyfunction]
+pub fn parse_ast(
+    py: Python,
+    start_token_name: &PyString,
+    tokens: PyObject,
+) -> PyResult<PyObject> {
+    let start_token_name = start_token_name.to_string();
+
+    let (spec, _) = get_spec()?;
+
+    let tokens = downcast_tokens(py, &start_token_name, tokens)?;
+
+    let context = parser::Context::new(spec);
+    let (cst, errors) = parser::parse(&tokens, &context);
+
+    let syntax_error = |e: Error| SyntaxError::new_err(parser_error_into_tuple(py, e));
+    if let Some(error) = errors.into_iter().next() {
+        return Err(syntax_error(error));
+    }
+    let Some(cst) = cst else {
+        return Err(PyAssertionError::new_err(("parser produced no output",)));
+    };
+
+    match start_token_name.as_str() {
+        "STARTBLOCK" => lower::lower_block(cst, spec)
+            .map_err(syntax_error)?
+            .into_python(py, None),
+        "STARTFRAGMENT" => lower::lower_expr(cst, spec)
+            .map_err(syntax_error)?
+            .into_python(py, None),
+        _ => Err(PyValueError::new_err(format!(
+            "cannot lower {start_token_name}"
+        ))),
+    }
+}
+
 #[pyclass]
 pub struct CSTNode {
     #[pyo3(get)]
@@ -77,8 +122,7 @@ fn downcast_tokens(
 ) -> PyResult<Vec<parser::Terminal>> {
     let tokens: &PyList = token_list.downcast(py)?;
 
-    let mut buf = Vec::with_capacity(tokens.len() + 1);
-    buf.push(parser::Terminal::from_start_name(start_token_name));
+    let mut buf = Vec::with_capacity(tokens.len());
     for token in tokens.iter() {
         let token: &PyCell<OpaqueToken> = token.downcast()?;
         let token = token.borrow().inner.clone();
@@ -86,16 +130,11 @@ fn downcast_tokens(
         buf.push(parser::Terminal::from_token(token));
     }
 
-    // adjust the span of the starting token for nicer error message spans
-    if buf.len() >= 2 {
-        buf[0].span.start = buf[1].span.start;
-        buf[0].span.end = buf[1].span.start;
-    }
-
-    Ok(buf)
+    let start = parser::Terminal::from_start_name(start_token_name);
+    Ok(parser::with_start_token(start, buf))
 }
 
-fn get_spec() -> PyResult<&'static (parser::Spec, PyObject)> {
+pub(crate) fn get_spec() -> PyResult<&'static (parser::Spec, PyObject)> {
     if let Some(x) = PARSER_SPECS.get() {
         Ok(x)
     } else {
@@ -163,12 +202,12 @@ fn to_py_cst<'a>(cst: &'a parser::CSTNode<'a>, py: Python) -> PyResult<CSTNode>
             production: py.None(),
             terminal: py.None(),
         },
-        parser::CSTNode::Terminal(token) => CSTNode {
+        parser::CSTNode::Terminal(token) | parser::CSTNode::Missing(token) => CSTNode {
             production: py.None(),
             terminal: Terminal {
                 text: token.text.clone(),
                 value: if let Some(val) = &token.value {
-                    value_to_py_object(py, val)?
+                    value_to_py_object(py, &val.clone().into())?
                 } else {
                     py.None()
                 },
@@ -193,5 +232,6 @@ fn to_py_cst<'a>(cst: &'a parser::CSTNode<'a>, py: Python) -> PyResult<CSTNode>
             .into_py(py),
             terminal: py.None(),
         },
+        parser::CSTNode::Skipped(skipped) => to_py_cst(&skipped.node, py)?,
     })
 }
@@ -5,30 +5,71 @@ use bigdecimal::Num;
 use bytes::{BufMut, Bytes, BytesMut};
 use edgedb_protocol::codec;
 use edgedb_protocol::model::{BigInt, Decimal};
-use edgeql_parser::tokenizer::Value;
+use edgeql_parser::normalize::{encode_element, encode_variables, Encoder};
+use edgeql_parser::normalize::{normalize_with as _normalize, normalize_batch as _normalize_batch};
+use edgeql_parser::normalize::{Error, Options, Value, Variable, PackedEntry};
 use pyo3::exceptions::{PyAssertionError, PyValueError};
 use pyo3::prelude::*;
 use pyo3::types::{PyBytes, PyDict, PyFloat, PyList, PyLong, PyString};
 
 use crate::errors::SyntaxError;
-use crate::normalize::{normalize as _normalize, Error, Variable, PackedEntry};
+use crate::parser::get_spec;
 use crate::tokenizer::tokens_to_py;
 
 #[pyfunction]
 pub fn normalize(py: Python<'_>, text: &PyString) -> PyResult<Entry> {
     let text = text.to_string();
-    match _normalize(&text) {
+    match _normalize(&text, &options()?) {
         Ok(entry) => Entry::new(py, entry),
-        Err(Error::Tokenizer(msg, pos)) => {
-            Err(SyntaxError::new_err((
+        Err(e) => Err(error_to_py(py, e)),
+    }
+}
+
+/// Normalizes queries in parallel, without holding the GIL.
+///
+/// Returns a list of `(index, result)` tuples in the order of `texts`, where
+/// result is either an `Entry` or an exception (which is not raised).
+/// Queries with the same hash as a preceding query are omitted.
+#[pyfunction]
+#[pyo3(signature = (texts, threads=0))]
+pub fn normalize_batch(
+    py: Python<'_>,
+    texts: Vec<String>,
+    threads: usize,
+) -> PyResult<PyObject> {
+    let options = options()?;
+    let results = py.allow_threads(|| _normalize_batch(&texts, threads, &options));
+
+    let mut items = Vec::with_capacity(results.len());
+    for (idx, result) in results {
+        let result: PyObject = match result {
+            Ok(entry) => Entry::new(py, entry)?.into_py(py),
+            Err(e) => error_to_py(py, e).into_value(py).into_py(py),
+        };
+        items.push((idx, result));
+    }
+    Ok(PyList::new(py, items).into())
+}
+
+/// Uses the grammar to find contexts of literals, so it has to be loaded
+/// with `preload_spec` first.
+fn options() -> PyResult<Options<'static>> {
+    let (spec, _) = get_spec()?;
+    Ok(Options::new(spec))
+}
+
+fn error_to_py(py: Python<'_>, error: Error) -> PyErr {
+    match error {
+        Error::Tokenizer(msg, pos) => {
+            SyntaxError::new_err((
                 msg,
                 (pos, py.None()),
                 py.None(),
                 py.None(),
-            )))
+            ))
         }
-        Err(Error::Assertion(msg, pos)) => {
-            Err(PyAssertionError::new_err(format!("{}: {}", pos, msg)))
+        Error::Assertion(msg, pos) => {
+            PyAssertionError::new_err(format!("{}: {}", pos, msg))
         }
     }
 }
@@ -56,7 +97,7 @@ pub struct Entry {
 }
 
 impl Entry {
-    pub fn new(py: Python, entry: crate::normalize::Entry) -> PyResult<Self> {
+    pub fn new(py: Python, entry: edgeql_parser::normalize::Entry) -> PyResult<Self> {
         let blobs =
             serialize_all(py, &entry.variables).map_err(PyAssertionError::new_err)?;
         let counts: Vec<_> = entry
@@ -106,19 +147,37 @@ impl Entry {
 }
 
 pub fn serialize_extra(variables: &[Variable]) -> Result<Bytes, String> {
-    use edgedb_protocol::codec::Codec;
-    use edgedb_protocol::value::Value as P;
-
-    let mut buf = BytesMut::new();
-    buf.reserve(4 * variables.len());
-    for var in variables {
-        buf.reserve(4);
-        let pos = buf.len();
-        buf.put_u32(0); // replaced after serializing a value
-        match var.value {
+    encode_variables(&ProtocolEncoder, variables).map(Bytes::from)
+}
+
+/// Encodes values with the codecs of edgedb-protocol
+struct ProtocolEncoder;
+
+impl Encoder for ProtocolEncoder {
+    fn encode_value(&self, out: &mut Vec<u8>, value: &Value) -> Result<(), String> {
+        use edgedb_protocol::codec::Codec;
+        use edgedb_protocol::value::Value as P;
+
+        if let Value::Array(ref items) = value {
+            // one-dimensional array, same as codec::Array
+            let len = u32::try_from(items.len())
+                .map_err(|_| "array is too long".to_owned())?;
+            out.reserve(20);
+            out.put_u32(1); // ndims
+            out.put_u32(0); // reserved0
+            out.put_u32(0); // reserved1
+            out.put_u32(len);
+            out.put_u32(1); // lower
+            for item in items {
+                encode_element(self, out, item)?;
+            }
+            return Ok(());
+        }
+        let mut buf = BytesMut::new();
+        match value {
             Value::Int(v) => {
                 codec::Int64
-                    .encode(&mut buf, &P::Int64(v))
+                    .encode(&mut buf, &P::Int64(*v))
                     .map_err(|e| format!("int cannot be encoded: {}", e))?;
             }
             Value::String(ref v) => {
@@ -128,7 +187,7 @@ pub fn serialize_extra(variables: &[Variable]) -> Result<Bytes, String> {
             }
             Value::Float(ref v) => {
                 codec::Float64
-                    .encode(&mut buf, &P::Float64(v.clone()))
+                    .encode(&mut buf, &P::Float64(*v))
                     .map_err(|e| format!("float cannot be encoded: {}", e))?;
             }
             Value::BigInt(ref v) => {
@@ -152,19 +211,21 @@ pub fn serialize_extra(variables: &[Variable]) -> Result<Bytes, String> {
                     .encode(&mut buf, &P::Decimal(val))
                     .map_err(|e| format!("decimal cannot be encoded: {}", e))?;
             }
-            Value::Bytes(_) => {
-                // bytes literals should not be extracted during normalization
-                unreachable!()
+            Value::Bytes(ref v) => {
+                codec::Bytes
+                    .encode(&mut buf, &P::Bytes(Bytes::copy_from_slice(v)))
+                    .map_err(|e| format!("bytes cannot be encoded: {}", e))?;
+            }
+            Value::Bool(v) => {
+                codec::Bool
+                    .encode(&mut buf, &P::Bool(*v))
+                    .map_err(|e| format!("bool cannot be encoded: {}", e))?;
             }
+            Value::Array(_) => unreachable!("arrays are encoded above"),
         }
-        let len = buf.len() - pos - 4;
-        buf[pos..pos + 4].copy_from_slice(
-            &u32::try_from(len)
-                .map_err(|_| "element isn't too long".to_owned())?
-                .to_be_bytes(),
-        );
+        out.extend_from_slice(&buf);
+        Ok(())
     }
-    Ok(buf.freeze())
 }
 
 pub fn serialize_all<'a>(
@@ -194,5 +255,47 @@ pub fn value_to_py_object(py: Python, val: &Value) -> PyResult<PyObject> {
             .call((v.to_string(),), None)?
             .into(),
         Value::Bytes(v) => PyBytes::new(py, v).into(),
+        Value::Bool(v) => v.into_py(py),
+        Value::Array(items) => {
+            let items = items.iter()
+                .map(|v| value_to_py_object(py, v))
+                .collect::<PyResult<Vec<_>>>()?;
+            PyList::new(py, items).into()
+        }
     })
 }
+
+#[cfg(test)]
+mod test {
+    use super::serialize_extra;
+    use edgeql_parser::normalize::{Value, Variable};
+
+    #[test]
+    fn bool() {
+        let variables = [
+            Variable { value: Value::Bool(true) },
+            Variable { value: Value::Bool(false) },
+        ];
+        let bytes = serialize_extra(&variables).unwrap();
+        assert_eq!(&bytes[..], &[
+            0, 0, 0, 1, 1,
+            0, 0, 0, 1, 0,
+        ]);
+    }
+
+    #[test]
+    fn array() {
+        let value = Value::Array(vec![Value::Bool(true), Value::Bool(false)]);
+        let bytes = serialize_extra(&[Variable { value }]).unwrap();
+        assert_eq!(&bytes[..], &[
+            0, 0, 0, 30, // length
+            0, 0, 0, 1, // ndims
+            0, 0, 0, 0, // reserved0
+            0, 0, 0, 0, // reserved1
+            0, 0, 0, 2, // upper
+            0, 0, 0, 1, // lower
+            0, 0, 0, 1, 1,
+            0, 0, 0, 1, 0,
+        ]);
+    }
+}
@@ -10,20 +10,18 @@ use crate::errors::{parser_error_into_tuple, ParserResult};
 pub fn tokenize(py: Python, s: &PyString) -> PyResult<ParserResult> {
     let data = s.to_string();
 
-    let mut token_stream = Tokenizer::new(&data[..]).validated_values().with_eof();
+    let token_stream = Tokenizer::new(&data[..])
+        .with_error_recovery()
+        .validated_values()
+        .with_eof();
 
     let mut tokens: Vec<_> = Vec::new();
     let mut errors: Vec<_> = Vec::new();
 
-    for res in &mut token_stream {
+    for res in token_stream {
         match res {
             Ok(token) => tokens.push(token),
-            Err(e) => {
-                errors.push(parser_error_into_tuple(py, e));
-
-                // TODO: fix tokenizer to skip bad tokens and continue
-                break;
-            }
+            Err(e) => errors.push(parser_error_into_tuple(py, e)),
         }
     }
 
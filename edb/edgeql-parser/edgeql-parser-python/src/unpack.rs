@@ -3,7 +3,7 @@ use pyo3::prelude::*;
 use pyo3::types::PyBytes;
 use edgeql_parser::tokenizer::Token;
 
-use crate::normalize::PackedEntry;
+use edgeql_parser::normalize::PackedEntry;
 use crate::pynormalize::Entry;
 use crate::tokenizer::tokens_to_py;
 
@@ -4,7 +4,7 @@
 
 //! Abstract Syntax Tree for EdgeQL
 #![allow(non_camel_case_types)]
-#![cfg(never)] // TODO: migrate cpython-rust to pyo3
+#![allow(clippy::large_enum_variant, clippy::type_complexity)]
 
 use indexmap::IndexMap;
 
@@ -71,7 +71,6 @@ pub enum ExprKind {
     ShapeElement(ShapeElement),
     Shape(Shape),
     Query(Query),
-    ConfigOp(ConfigOp),
 }
 
 #[derive(Debug, Clone)]
@@ -128,26 +127,9 @@ pub struct ObjectRef {
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct PseudoObjectRef {
-    #[cfg_attr(feature = "python", py_child)]
-    pub kind: PseudoObjectRefKind,
-}
-
-#[derive(Debug, Clone)]
-#[cfg_attr(feature = "python", derive(IntoPython))]
-#[cfg_attr(feature = "python", py_child)]
-pub enum PseudoObjectRefKind {
-    AnyType(AnyType),
-    AnyTuple(AnyTuple),
+    pub name: String,
 }
 
-#[derive(Debug, Clone)]
-#[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct AnyType {}
-
-#[derive(Debug, Clone)]
-#[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct AnyTuple {}
-
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct Anchor {
@@ -160,35 +142,19 @@ pub struct Anchor {
 #[cfg_attr(feature = "python", derive(IntoPython))]
 #[cfg_attr(feature = "python", py_child)]
 pub enum AnchorKind {
+    IRAnchor(IRAnchor),
     SpecialAnchor(SpecialAnchor),
 }
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct SpecialAnchor {
-    #[cfg_attr(feature = "python", py_child)]
-    pub kind: Option<SpecialAnchorKind>,
-}
-
-#[derive(Debug, Clone)]
-#[cfg_attr(feature = "python", derive(IntoPython))]
-#[cfg_attr(feature = "python", py_child)]
-pub enum SpecialAnchorKind {
-    Source(Source),
-    Subject(Subject),
-}
-
-#[derive(Debug, Clone)]
-#[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct Source {
-    pub name: String,
+pub struct IRAnchor {
+    pub has_dml: bool,
 }
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct Subject {
-    pub name: String,
-}
+pub struct SpecialAnchor {}
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
@@ -238,21 +204,7 @@ pub struct BinOp {
     pub op: String,
     pub right: Box<Expr>,
     pub rebalanced: bool,
-    #[cfg_attr(feature = "python", py_child)]
-    pub kind: Option<BinOpKind>,
-}
-
-#[derive(Debug, Clone)]
-#[cfg_attr(feature = "python", derive(IntoPython))]
-#[cfg_attr(feature = "python", py_child)]
-pub enum BinOpKind {
-    SetConstructorOp(SetConstructorOp),
-}
-
-#[derive(Debug, Clone)]
-#[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct SetConstructorOp {
-    pub op: String,
+    pub set_constructor: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -282,7 +234,6 @@ pub enum FunctionCallFunc {
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct BaseConstant {
-    pub value: String,
     #[cfg_attr(feature = "python", py_child)]
     pub kind: BaseConstantKind,
 }
@@ -291,54 +242,17 @@ pub struct BaseConstant {
 #[cfg_attr(feature = "python", derive(IntoPython))]
 #[cfg_attr(feature = "python", py_child)]
 pub enum BaseConstantKind {
-    StringConstant(StringConstant),
-    BaseRealConstant(BaseRealConstant),
-    BooleanConstant(BooleanConstant),
+    Constant(Constant),
     BytesConstant(BytesConstant),
 }
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct StringConstant {}
-
-#[derive(Debug, Clone)]
-#[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct BaseRealConstant {
-    pub is_negative: bool,
-    #[cfg_attr(feature = "python", py_child)]
-    pub kind: BaseRealConstantKind,
-}
-
-#[derive(Debug, Clone)]
-#[cfg_attr(feature = "python", derive(IntoPython))]
-#[cfg_attr(feature = "python", py_child)]
-pub enum BaseRealConstantKind {
-    IntegerConstant(IntegerConstant),
-    FloatConstant(FloatConstant),
-    BigintConstant(BigintConstant),
-    DecimalConstant(DecimalConstant),
+pub struct Constant {
+    pub kind: ConstantKind,
+    pub value: String,
 }
 
-#[derive(Debug, Clone)]
-#[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct IntegerConstant {}
-
-#[derive(Debug, Clone)]
-#[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct FloatConstant {}
-
-#[derive(Debug, Clone)]
-#[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct BigintConstant {}
-
-#[derive(Debug, Clone)]
-#[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct DecimalConstant {}
-
-#[derive(Debug, Clone)]
-#[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct BooleanConstant {}
-
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct BytesConstant {
@@ -363,7 +277,7 @@ pub struct UnaryOp {
 pub struct TypeExpr {
     pub name: Option<String>,
     #[cfg_attr(feature = "python", py_child)]
-    pub kind: Option<TypeExprKind>,
+    pub kind: TypeExprKind,
 }
 
 #[derive(Debug, Clone)]
@@ -385,7 +299,7 @@ pub struct TypeOf {
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct TypeExprLiteral {
-    pub val: BaseConstant,
+    pub val: Constant,
 }
 
 #[derive(Debug, Clone)]
@@ -431,7 +345,7 @@ pub struct TypeIntersection {
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct Ptr {
-    pub ptr: ObjectRef,
+    pub name: String,
     pub direction: Option<String>,
     pub r#type: Option<String>,
 }
@@ -482,12 +396,13 @@ pub struct IfElse {
     pub condition: Box<Expr>,
     pub if_expr: Box<Expr>,
     pub else_expr: Box<Expr>,
+    pub python_style: bool,
 }
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct TupleElement {
-    pub name: ObjectRef,
+    pub name: Ptr,
     pub val: Box<Expr>,
 }
 
@@ -614,39 +529,25 @@ pub enum QueryAliases {
 #[cfg_attr(feature = "python", derive(IntoPython))]
 #[cfg_attr(feature = "python", py_child)]
 pub enum QueryKind {
-    PipelinedQuery(PipelinedQuery),
+    SelectQuery(SelectQuery),
     GroupQuery(GroupQuery),
     InsertQuery(InsertQuery),
     UpdateQuery(UpdateQuery),
+    DeleteQuery(DeleteQuery),
     ForQuery(ForQuery),
 }
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct PipelinedQuery {
-    pub implicit: bool,
+pub struct SelectQuery {
+    pub result_alias: Option<String>,
+    pub result: Box<Expr>,
     pub r#where: Option<Box<Expr>>,
     pub orderby: Option<Vec<SortExpr>>,
     pub offset: Option<Box<Expr>>,
     pub limit: Option<Box<Expr>>,
     pub rptr_passthrough: bool,
-    #[cfg_attr(feature = "python", py_child)]
-    pub kind: PipelinedQueryKind,
-}
-
-#[derive(Debug, Clone)]
-#[cfg_attr(feature = "python", derive(IntoPython))]
-#[cfg_attr(feature = "python", py_child)]
-pub enum PipelinedQueryKind {
-    SelectQuery(SelectQuery),
-    DeleteQuery(DeleteQuery),
-}
-
-#[derive(Debug, Clone)]
-#[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct SelectQuery {
-    pub result_alias: Option<String>,
-    pub result: Box<Expr>,
+    pub implicit: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -767,11 +668,18 @@ pub struct UpdateQuery {
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct DeleteQuery {
     pub subject: Box<Expr>,
+    pub r#where: Option<Box<Expr>>,
+    pub orderby: Option<Vec<SortExpr>>,
+    pub offset: Option<Box<Expr>>,
+    pub limit: Option<Box<Expr>>,
 }
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct ForQuery {
+    pub from_desugaring: bool,
+    pub has_union: bool,
+    pub optional: bool,
     pub iterator: Box<Expr>,
     pub iterator_alias: String,
     pub result_alias: Option<String>,
@@ -1039,6 +947,7 @@ pub enum AlterObjectKind {
     AlterAnnotation(AlterAnnotation),
     AlterScalarType(AlterScalarType),
     AlterProperty(AlterProperty),
+    AlterConcreteUnknownPointer(AlterConcreteUnknownPointer),
     AlterConcreteProperty(AlterConcreteProperty),
     AlterObjectType(AlterObjectType),
     AlterAlias(AlterAlias),
@@ -1213,33 +1122,48 @@ pub struct CommitMigrationRewrite {}
 pub struct CreateDatabase {
     pub template: Option<ObjectRef>,
     pub branch_type: BranchType,
+    pub flavor: String,
 }
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct AlterDatabase {}
+pub struct AlterDatabase {
+    pub force: bool,
+    pub flavor: String,
+}
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct DropDatabase {}
+pub struct DropDatabase {
+    pub force: bool,
+    pub flavor: String,
+}
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct CreateExtensionPackage {
     pub body: NestedQLBlock,
+    pub version: Constant,
 }
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct DropExtensionPackage {}
+pub struct DropExtensionPackage {
+    pub version: Constant,
+}
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct CreateExtension {}
+pub struct CreateExtension {
+    pub object_class: SchemaObjectClass,
+    pub version: Option<Constant>,
+}
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct DropExtension {}
+pub struct DropExtension {
+    pub version: Option<Constant>,
+}
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
@@ -1247,7 +1171,9 @@ pub struct CreateFuture {}
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct DropFuture {}
+pub struct DropFuture {
+    pub version: Option<Constant>,
+}
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
@@ -1325,7 +1251,7 @@ pub struct CreateConcretePointer {
     pub is_required: Option<bool>,
     pub declared_overloaded: bool,
     pub target: CreateConcretePointerTarget,
-    pub cardinality: SchemaCardinality,
+    pub cardinality: Option<SchemaCardinality>,
     pub bases: Vec<TypeExpr>,
     #[cfg_attr(feature = "python", py_child)]
     pub kind: Option<CreateConcretePointerKind>,
@@ -1353,6 +1279,10 @@ pub enum CreateConcretePointerKind {
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct CreateConcreteUnknownPointer {}
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+pub struct AlterConcreteUnknownPointer {}
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct CreateConcreteProperty {}
@@ -1468,15 +1398,26 @@ pub struct DropConstraint {}
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct CreateConcreteConstraint {
     pub delegated: bool,
+    pub args: Vec<Box<Expr>>,
+    pub subjectexpr: Option<Box<Expr>>,
+    pub except_expr: Option<Box<Expr>>,
 }
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct AlterConcreteConstraint {}
+pub struct AlterConcreteConstraint {
+    pub args: Vec<Box<Expr>>,
+    pub subjectexpr: Option<Box<Expr>>,
+    pub except_expr: Option<Box<Expr>>,
+}
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct DropConcreteConstraint {}
+pub struct DropConcreteConstraint {
+    pub args: Vec<Box<Expr>>,
+    pub subjectexpr: Option<Box<Expr>>,
+    pub except_expr: Option<Box<Expr>>,
+}
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
@@ -1512,15 +1453,30 @@ pub struct DropIndex {}
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct CreateConcreteIndex {}
+pub struct CreateConcreteIndex {
+    pub kwargs: IndexMap<String, Box<Expr>>,
+    pub expr: Box<Expr>,
+    pub except_expr: Option<Box<Expr>>,
+    pub deferred: Option<bool>,
+}
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct AlterConcreteIndex {}
+pub struct AlterConcreteIndex {
+    pub kwargs: IndexMap<String, Box<Expr>>,
+    pub expr: Box<Expr>,
+    pub except_expr: Option<Box<Expr>>,
+    pub deferred: Option<bool>,
+}
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct DropConcreteIndex {}
+pub struct DropConcreteIndex {
+    pub kwargs: IndexMap<String, Box<Expr>>,
+    pub expr: Box<Expr>,
+    pub except_expr: Option<Box<Expr>>,
+    pub deferred: Option<bool>,
+}
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
@@ -1569,6 +1525,7 @@ pub struct CreateTrigger {
     pub kinds: Vec<TriggerKind>,
     pub scope: TriggerScope,
     pub expr: Box<Expr>,
+    pub condition: Option<Box<Expr>>,
 }
 
 #[derive(Debug, Clone)]
@@ -1583,15 +1540,20 @@ pub struct DropTrigger {}
 #[cfg_attr(feature = "python", derive(IntoPython))]
 pub struct CreateRewrite {
     pub expr: Box<Expr>,
+    pub kinds: Vec<RewriteKind>,
 }
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct AlterRewrite {}
+pub struct AlterRewrite {
+    pub kinds: Vec<RewriteKind>,
+}
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct DropRewrite {}
+pub struct DropRewrite {
+    pub kinds: Vec<RewriteKind>,
+}
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
@@ -1610,6 +1572,7 @@ pub struct CreateFunction {
     pub code: FunctionCode,
     pub nativecode: Option<Box<Expr>>,
     pub returning_typemod: TypeModifier,
+    pub params: Vec<FuncParam>,
 }
 
 #[derive(Debug, Clone)]
@@ -1617,11 +1580,14 @@ pub struct CreateFunction {
 pub struct AlterFunction {
     pub code: FunctionCode,
     pub nativecode: Option<Box<Expr>>,
+    pub params: Vec<FuncParam>,
 }
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct DropFunction {}
+pub struct DropFunction {
+    pub params: Vec<FuncParam>,
+}
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
@@ -1639,15 +1605,23 @@ pub struct CreateOperator {
     pub returning: TypeExpr,
     pub returning_typemod: TypeModifier,
     pub code: OperatorCode,
+    pub kind: OperatorKind,
+    pub params: Vec<FuncParam>,
 }
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct AlterOperator {}
+pub struct AlterOperator {
+    pub kind: OperatorKind,
+    pub params: Vec<FuncParam>,
+}
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct DropOperator {}
+pub struct DropOperator {
+    pub kind: OperatorKind,
+    pub params: Vec<FuncParam>,
+}
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
@@ -1665,15 +1639,23 @@ pub struct CreateCast {
     pub code: CastCode,
     pub allow_implicit: bool,
     pub allow_assignment: bool,
+    pub from_type: TypeExpr,
+    pub to_type: TypeExpr,
 }
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct AlterCast {}
+pub struct AlterCast {
+    pub from_type: TypeExpr,
+    pub to_type: TypeExpr,
+}
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
-pub struct DropCast {}
+pub struct DropCast {
+    pub from_type: TypeExpr,
+    pub to_type: TypeExpr,
+}
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
@@ -1803,6 +1785,18 @@ pub enum DescribeGlobal {
     Roles,
 }
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+#[cfg_attr(feature = "python", py_enum(qlast.ConstantKind))]
+pub enum ConstantKind {
+    STRING,
+    BOOLEAN,
+    INTEGER,
+    FLOAT,
+    BIGINT,
+    DECIMAL,
+}
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 #[cfg_attr(feature = "python", py_enum(qlast.ShapeOp))]
@@ -1823,6 +1817,15 @@ pub enum ShapeOrigin {
     MATERIALIZATION,
 }
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+#[cfg_attr(feature = "python", py_enum(qlast.BranchType))]
+pub enum BranchType {
+    EMPTY,
+    SCHEMA,
+    DATA,
+}
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 #[cfg_attr(feature = "python", py_enum(qlast.Language))]
@@ -1922,6 +1925,15 @@ pub enum Multiplicity {
     UNKNOWN,
 }
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+#[cfg_attr(feature = "python", py_enum(qltypes.IndexDeferrability))]
+pub enum IndexDeferrability {
+    Prohibited,
+    Permitted,
+    Required,
+}
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
 #[cfg_attr(feature = "python", py_enum(qltypes.AccessPolicyAction))]
@@ -1992,6 +2004,7 @@ pub enum SchemaObjectClass {
     ALIAS,
     ANNOTATION,
     ARRAY_TYPE,
+    BRANCH,
     CAST,
     CONSTRAINT,
     DATABASE,
@@ -2004,6 +2017,7 @@ pub enum SchemaObjectClass {
     LINK,
     MIGRATION,
     MODULE,
+    MULTIRANGE_TYPE,
     OPERATOR,
     PARAMETER,
     PROPERTY,
@@ -2048,9 +2062,9 @@ pub enum ConfigScope {
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", derive(IntoPython))]
-#[cfg_attr(feature = "python", py_enum(qlast.BranchType))]
-pub enum BranchType {
-    EMPTY,
-    SCHEMA,
-    DATA,
-}
\ No newline at end of file
+#[cfg_attr(feature = "python", py_enum(qltypes.TypeTag))]
+pub enum TypeTag {
+    SCALAR,
+    TUPLE,
+    ARRAY,
+}
@@ -0,0 +1,119 @@
+//! Machine-readable parts of [Error]: error codes, secondary labels and
+//! suggested edits.
+//!
+//! [Error]: crate::tokenizer::Error
+
+use crate::position::Span;
+
+/// Stable identifier of a kind of error.
+///
+/// Codes do not change between releases, so tools can match on them instead
+/// of on error messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ErrorCode {
+    /// Input could not be split into tokens.
+    InvalidToken,
+    /// String, quoted name or comment without the closing delimiter.
+    UnterminatedLiteral,
+    /// Token was recognized, but its value is invalid (bad escape, number
+    /// out of range, ...).
+    InvalidLiteral,
+
+    /// Parser did not expect the token and skipped it.
+    UnexpectedToken,
+    /// Parser expected a token that is not there.
+    MissingToken,
+    /// Reserved keyword used as an identifier.
+    ReservedKeyword,
+    /// Statement or complex expression must be wrapped in parentheses.
+    MissingParentheses,
+    /// Keyword from another query language.
+    UnsupportedKeyword,
+}
+
+impl ErrorCode {
+    pub fn as_str(&self) -> &'static str {
+        use ErrorCode::*;
+
+        match self {
+            InvalidToken => "E0001",
+            UnterminatedLiteral => "E0002",
+            InvalidLiteral => "E0003",
+            UnexpectedToken => "E0101",
+            MissingToken => "E0102",
+            ReservedKeyword => "E0103",
+            MissingParentheses => "E0104",
+            UnsupportedKeyword => "E0105",
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Secondary span of an error, pointing to a related part of the source.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+}
+
+/// Edit of the source that fixes the error and can be applied without
+/// further input from the user.
+///
+/// Empty span means insertion, empty replacement means removal.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Suggestion {
+    /// Short description of the edit, i.e. "insert ')'".
+    pub message: String,
+    pub span: Span,
+    pub replacement: String,
+}
+
+impl Suggestion {
+    pub fn insert(offset: u64, text: impl Into<String>) -> Self {
+        let text = text.into();
+        Suggestion {
+            message: format!("insert '{text}'"),
+            span: Span {
+                start: offset,
+                end: offset,
+            },
+            replacement: text,
+        }
+    }
+
+    pub fn replace(span: Span, text: impl Into<String>) -> Self {
+        let text = text.into();
+        Suggestion {
+            message: format!("replace with '{text}'"),
+            span,
+            replacement: text,
+        }
+    }
+
+    pub fn remove(span: Span) -> Self {
+        Suggestion {
+            message: "remove".to_string(),
+            span,
+            replacement: String::new(),
+        }
+    }
+
+    /// Applies the edit to the source text.
+    pub fn apply(&self, source: &str) -> String {
+        let start = self.span.start as usize;
+        let end = self.span.end as usize;
+        let mut res = String::with_capacity(source.len() + self.replacement.len());
+        res.push_str(&source[..start]);
+        res.push_str(&self.replacement);
+        res.push_str(&source[end..]);
+        res
+    }
+}
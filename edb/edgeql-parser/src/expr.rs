@@ -1,4 +1,4 @@
-use crate::position::{Pos, InflatedPos};
+use crate::position::{Pos, InflatedPos, Span};
 use crate::tokenizer::{Kind, self};
 
 /// Error of expression checking
@@ -6,28 +6,81 @@ use crate::tokenizer::{Kind, self};
 /// See [check][].
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
-    #[error("{}: tokenizer error: {}", _1, _0)]
+    #[error("{}: {}", _1, self.message())]
     Tokenizer(String, Pos),
-    #[error(
-        "{}: closing bracket mismatch, opened {:?} at {}, encountered {:?}",
-        closing_pos, opened, opened_pos, encountered)]
+    #[error("{}: {}", closing_pos, self.message())]
     BracketMismatch {
         opened: &'static str,
         encountered: &'static str,
         opened_pos: Pos,
         closing_pos: Pos,
     },
-    #[error("{}: extra closing bracket {:?}", _1, _0)]
+    #[error("{}: {}", _1, self.message())]
     ExtraBracket(&'static str, Pos),
-    #[error("{}: bracket {:?} has never been closed", _1, _0)]
+    #[error("{}: {}", _1, self.message())]
     MissingBracket(&'static str, Pos),
-    #[error("{}: token {:?} is not allowed in expression \
-             (try parenthesize the expression)", _1, _0)]
+    #[error("{}: {}", _1, self.message())]
     UnexpectedToken(String, Pos),
-    #[error("expression is empty")]
+    #[error("{}", self.message())]
     Empty,
 }
 
+impl Error {
+    /// Message without the position, which `Display` prepends
+    pub fn message(&self) -> String {
+        use Error::*;
+
+        match self {
+            Tokenizer(error, _) => format!("tokenizer error: {}", error),
+            BracketMismatch { opened, encountered, opened_pos, .. } => {
+                format!("closing bracket mismatch, opened {:?} at {}, \
+                         encountered {:?}", opened, opened_pos, encountered)
+            }
+            ExtraBracket(bracket, _) => {
+                format!("extra closing bracket {:?}", bracket)
+            }
+            MissingBracket(bracket, _) => {
+                format!("bracket {:?} has never been closed", bracket)
+            }
+            UnexpectedToken(text, _) => {
+                format!("token {:?} is not allowed in expression \
+                         (try parenthesize the expression)", text)
+            }
+            Empty => "expression is empty".to_string(),
+        }
+    }
+}
+
+impl From<&Error> for tokenizer::Error {
+    /// Converts into an error with a span, which can be rendered with
+    /// [crate::render::Renderer].
+    fn from(error: &Error) -> tokenizer::Error {
+        use Error::*;
+
+        let span = |pos: &Pos, len: usize| Span {
+            start: pos.offset,
+            end: pos.offset + len as u64,
+        };
+        let message = error.message();
+        match error {
+            Tokenizer(_, pos) => tokenizer::Error::new(message)
+                .with_span(span(pos, 0)),
+            BracketMismatch { opened, encountered, opened_pos, closing_pos } => {
+                tokenizer::Error::new(message)
+                .with_span(span(closing_pos, encountered.len()))
+                .with_label(span(opened_pos, opened.len()), "opened here")
+            }
+            ExtraBracket(bracket, pos) | MissingBracket(bracket, pos) => {
+                tokenizer::Error::new(message)
+                .with_span(span(pos, bracket.len()))
+            }
+            UnexpectedToken(text, pos) => tokenizer::Error::new(message)
+                .with_span(span(pos, text.len())),
+            Empty => tokenizer::Error::new(message),
+        }
+    }
+}
+
 fn bracket_str(tok: Kind) -> &'static str {
     use crate::tokenizer::Kind::*;
 
@@ -0,0 +1,805 @@
+//! Formatter of EdgeQL queries and SDL.
+//!
+//! The source is parsed, as a block of statements or as an SDL document, and
+//! laid out by the structure of its CST: clauses of statements (`FILTER`,
+//! `ORDER BY`, `LIMIT`, ...) start new lines, elements of shapes and lists
+//! are placed on their own, indented lines and each command in a body of a
+//! DDL command or an SDL declaration is always on its own line. Statements,
+//! shapes and lists are kept on a single line if they fit into the configured
+//! width. Comments are preserved.
+//!
+//! Tokens of inlined productions (braces of shapes, parentheses, `FILTER`)
+//! are not in the CST, so they are put back into it: a pair of brackets
+//! around the highest node that spans what is between them, other tokens
+//! between children of the smallest node that spans them.
+//!
+//! Keywords have their case changed, unless they were parsed as names.
+
+use std::collections::HashSet;
+
+use unicode_width::UnicodeWidthStr;
+
+use crate::parser::{CSTNode, Context, Spec};
+use crate::rewrite::{change_case, find_names};
+use crate::tokenizer::{Error, Kind, Tokenizer};
+
+#[derive(Clone)]
+pub struct Options<'a> {
+    /// Maximum width of a line, which is exceeded only when a single token
+    /// does not fit.
+    pub width: usize,
+    /// Number of spaces used for each level of indentation.
+    pub indent: usize,
+    pub keyword_case: KeywordCase,
+    /// Grammar used to parse the source
+    pub spec: &'a Spec,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeywordCase {
+    Upper,
+    Lower,
+    Preserve,
+}
+
+impl<'a> Options<'a> {
+    /// Options with lines of 80 characters, indented by 4 spaces, and
+    /// upper-case keywords
+    pub fn new(spec: &'a Spec) -> Options<'a> {
+        Options {
+            width: 80,
+            indent: 4,
+            keyword_case: KeywordCase::Upper,
+            spec,
+        }
+    }
+}
+
+/// Formats the source. Fails if it cannot be tokenized or parsed, neither as
+/// a block of statements nor as an SDL document.
+pub fn format(source: &str, options: &Options) -> Result<String, Error> {
+    let tokens = tokenize(source)?;
+    let ctx = Context::new(options.spec);
+    let cst = parse(&ctx, source)?;
+
+    let closing = match_brackets(&tokens);
+    let tree = build_tree(cst, &tokens, &closing, options.spec);
+    let mut builder = Builder {
+        tokens: &tokens,
+        closing: &closing,
+        names: find_names(cst, options.spec),
+        keyword_case: options.keyword_case,
+        next: 0,
+        comments_done: 0,
+        separated: true,
+        spacing: Spacing::default(),
+    };
+    let doc = builder.document(tree.as_ref());
+
+    let mut out = print(&doc, options);
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+/// Parses the source as a block of statements or as an SDL document. When
+/// both fail, the error which is further in the source is returned.
+fn parse<'c>(ctx: &'c Context, source: &str) -> Result<&'c CSTNode<'c>, Error> {
+    let block = ctx.parse_block(source);
+    if let (Some(cst), false) = (block.cst, block.has_errors()) {
+        return Ok(cst);
+    }
+    let sdl = ctx.parse_sdl_document(source);
+    if let (Some(cst), false) = (sdl.cst, sdl.has_errors()) {
+        return Ok(cst);
+    }
+
+    let error = match (block.errors().next(), sdl.errors().next()) {
+        (Some(block), Some(sdl)) if sdl.span.start > block.span.start => sdl,
+        (Some(block), _) => block,
+        (None, Some(sdl)) => sdl,
+        (None, None) => return Err(Error::new("cannot parse the source")),
+    };
+    Err(error.clone())
+}
+
+/// A significant token with the whitespace and comments in front of it.
+#[derive(Debug)]
+struct Tok<'a> {
+    kind: Kind,
+    text: &'a str,
+    start: u64,
+    newlines_before: usize,
+    comments: Vec<Comment<'a>>,
+}
+
+#[derive(Debug)]
+struct Comment<'a> {
+    text: &'a str,
+    newlines_before: usize,
+}
+
+/// Significant tokens, followed by an end of file, which holds the comments
+/// at the end of the source.
+fn tokenize(source: &str) -> Result<Vec<Tok<'_>>, Error> {
+    let mut tokens = Vec::new();
+    let mut comments = Vec::new();
+    let mut newlines = 0;
+    for token in Tokenizer::new(source).with_trivia() {
+        let token = token?;
+        let text = &source[token.span.start as usize..token.span.end as usize];
+        match token.kind {
+            Kind::Whitespace => {
+                newlines += text.matches('\n').count();
+                continue;
+            }
+            Kind::Comment => comments.push(Comment {
+                text: text.trim_end(),
+                newlines_before: newlines,
+            }),
+            kind => {
+                // separators go before comments that precede them,
+                // so that they are not commented out
+                let comments = match kind {
+                    Kind::Comma | Kind::Semicolon => Vec::new(),
+                    _ => std::mem::take(&mut comments),
+                };
+                tokens.push(Tok {
+                    kind,
+                    text,
+                    start: token.span.start,
+                    newlines_before: newlines,
+                    comments,
+                });
+            }
+        }
+        newlines = 0;
+    }
+    tokens.push(Tok {
+        kind: Kind::EOF,
+        text: "",
+        start: source.len() as u64,
+        newlines_before: newlines,
+        comments,
+    });
+    Ok(tokens)
+}
+
+/// Index of the matching closing bracket of each opening bracket. The source
+/// was parsed, so the brackets are balanced.
+fn match_brackets(tokens: &[Tok]) -> Vec<Option<usize>> {
+    let mut closing = vec![None; tokens.len()];
+    let mut open = Vec::new();
+    for (i, tok) in tokens.iter().enumerate() {
+        match tok.kind {
+            Kind::OpenParen | Kind::OpenBracket | Kind::OpenBrace => open.push(i),
+            Kind::CloseParen | Kind::CloseBracket | Kind::CloseBrace => {
+                if let Some(open) = open.pop() {
+                    closing[open] = Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    closing
+}
+
+/// The CST, with tokens of inlined productions put back, and empty nodes
+/// removed.
+#[derive(Debug)]
+enum Tree<'s> {
+    /// Index of the token
+    Token(usize),
+    /// Children of a production, by the name of its non-terminal. Brackets
+    /// that were put back around a node make a node without a name.
+    Node(&'s str, Vec<Tree<'s>>),
+}
+
+impl Tree<'_> {
+    fn first(&self) -> usize {
+        match self {
+            Tree::Token(i) => *i,
+            Tree::Node(_, children) => children[0].first(),
+        }
+    }
+
+    fn last(&self) -> usize {
+        match self {
+            Tree::Token(i) => *i,
+            Tree::Node(_, children) => children[children.len() - 1].last(),
+        }
+    }
+
+    fn non_term(&self) -> Option<&str> {
+        match self {
+            Tree::Token(_) => None,
+            Tree::Node(non_term, _) => Some(non_term),
+        }
+    }
+
+    fn mark_tokens(&self, placed: &mut [bool]) {
+        match self {
+            Tree::Token(i) => placed[*i] = true,
+            Tree::Node(_, children) => children.iter().for_each(|c| c.mark_tokens(placed)),
+        }
+    }
+}
+
+fn build_tree<'s>(
+    cst: &CSTNode,
+    tokens: &[Tok],
+    closing: &[Option<usize>],
+    spec: &'s Spec,
+) -> Option<Tree<'s>> {
+    let mut tree = from_cst(cst, tokens, spec)?;
+
+    // the end of file is not a part of the tree
+    let mut placed = vec![false; tokens.len() - 1];
+    tree.mark_tokens(&mut placed);
+
+    // pairs of brackets, the inner ones first
+    let mut pairs: Vec<_> = (closing.iter().enumerate())
+        .filter_map(|(open, close)| Some((open, (*close)?)))
+        .filter(|(open, close)| !placed[*open] && !placed[*close])
+        .collect();
+    pairs.sort_by_key(|(_, close)| *close);
+    for (open, close) in pairs {
+        let mut inner = (open + 1..close).filter(|i| placed[*i]);
+        let Some(first) = inner.next() else {
+            continue;
+        };
+        let last = inner.next_back().unwrap_or(first);
+        if wrap(&mut tree, (first, last), (open, close)) {
+            placed[open] = true;
+            placed[close] = true;
+        }
+    }
+
+    for (i, _) in placed.iter().enumerate().filter(|(_, placed)| !**placed) {
+        insert(&mut tree, i);
+    }
+    Some(tree)
+}
+
+fn from_cst<'s>(node: &CSTNode, tokens: &[Tok], spec: &'s Spec) -> Option<Tree<'s>> {
+    match node {
+        CSTNode::Terminal(terminal) => {
+            let i = (tokens.binary_search_by_key(&terminal.span.start, |t| t.start)).ok()?;
+            let is_token = terminal.kind != Kind::EOF && tokens[i].kind == terminal.kind;
+            is_token.then_some(Tree::Token(i))
+        }
+        CSTNode::Production(production) => {
+            let children: Vec<_> = (production.args.iter())
+                .filter_map(|c| from_cst(c, tokens, spec))
+                .collect();
+            if children.is_empty() {
+                return None;
+            }
+            let (non_term, _) = spec.production_name(production.id);
+            Some(Tree::Node(non_term, children))
+        }
+        _ => None,
+    }
+}
+
+/// Puts the brackets around the highest node below the root, which spans
+/// tokens from `first` to `last`. Fails if there is no such node.
+fn wrap(tree: &mut Tree, (first, last): (usize, usize), (open, close): (usize, usize)) -> bool {
+    let Tree::Node(_, children) = tree else {
+        return false;
+    };
+    let child = (children.iter_mut()).find(|c| c.first() <= first && last <= c.last());
+    let Some(child) = child else {
+        return false;
+    };
+    if child.first() != first || child.last() != last {
+        return wrap(child, (first, last), (open, close));
+    }
+    let node = std::mem::replace(child, Tree::Token(open));
+    *child = Tree::Node("", vec![Tree::Token(open), node, Tree::Token(close)]);
+    true
+}
+
+/// Puts the token between children of the smallest node that spans it.
+fn insert(tree: &mut Tree, token: usize) {
+    let Tree::Node(_, children) = tree else {
+        unreachable!("root of the tree is a node");
+    };
+    let pos = children.partition_point(|c| c.last() < token);
+    match children.get_mut(pos) {
+        Some(child) if child.first() < token => insert(child, token),
+        _ => children.insert(pos, Tree::Token(token)),
+    }
+}
+
+/// Document, which is laid out by the [print]er.
+#[derive(Debug, Clone)]
+enum Doc {
+    Text(String),
+    /// Space, or a line break when the enclosing group does not fit.
+    Line,
+    /// Nothing, or a line break when the enclosing group does not fit.
+    SoftLine,
+    HardLine,
+    /// Forces enclosing groups to break, without printing anything.
+    BreakParent,
+    Concat(Vec<Doc>),
+    Nest(Box<Doc>),
+    Group {
+        doc: Box<Doc>,
+        forced: bool,
+    },
+}
+
+impl Doc {
+    fn group(docs: Vec<Doc>) -> Doc {
+        let forced = docs.iter().any(Doc::forces_break);
+        Doc::Group {
+            doc: Box::new(Doc::Concat(docs)),
+            forced,
+        }
+    }
+
+    fn forces_break(&self) -> bool {
+        match self {
+            Doc::HardLine | Doc::BreakParent => true,
+            Doc::Text(_) | Doc::Line | Doc::SoftLine => false,
+            Doc::Concat(docs) => docs.iter().any(Doc::forces_break),
+            Doc::Nest(doc) => doc.forces_break(),
+            Doc::Group { forced, .. } => *forced,
+        }
+    }
+}
+
+/// Statements, which are laid out in a group with a line for each clause.
+const STATEMENTS: &[&str] = &[
+    "SimpleSelect",
+    "SimpleInsert",
+    "SimpleUpdate",
+    "SimpleDelete",
+    "SimpleFor",
+    "SimpleGroup",
+    "InternalGroup",
+];
+
+/// Nodes, which have clauses, but are laid out within the enclosing group.
+const CLAUSE_NODES: &[&str] = &["SelectLimit", "UnlessConflictSpecifier", "ShapeElement"];
+
+/// Keywords that start a clause.
+const CLAUSES: &[&str] = &[
+    "filter", "order", "offset", "limit", "set", "union", "using", "by", "unless", "else",
+];
+
+struct Builder<'a> {
+    tokens: &'a [Tok<'a>],
+    closing: &'a [Option<usize>],
+    /// Start offsets of keywords which were parsed as names
+    names: HashSet<u64>,
+    keyword_case: KeywordCase,
+    /// Index of the next token, since the tree is laid out in order.
+    next: usize,
+    /// Number of comments of the next token, which were already laid out.
+    comments_done: usize,
+    /// A line (which may be a space) was laid out after the last token.
+    separated: bool,
+    spacing: Spacing,
+}
+
+impl Builder<'_> {
+    /// Statements or SDL declarations, followed by the comments at the end.
+    fn document(&mut self, tree: Option<&Tree>) -> Doc {
+        let mut docs = Vec::new();
+        if let Some(Tree::Node(_, children)) = tree {
+            docs.push(self.items(children, Doc::HardLine));
+        }
+        docs.push(self.comments());
+        Doc::Concat(docs)
+    }
+
+    fn tree(&mut self, tree: &Tree) -> Doc {
+        let Tree::Node(non_term, children) = tree else {
+            return self.token(tree.first());
+        };
+        if is_list(non_term) && self.has_commas(children) {
+            let items = self.items(children, Doc::Line);
+            return Doc::group(vec![Doc::Nest(Box::new(items))]);
+        }
+        let with_block = children.iter().any(|c| c.non_term() == Some("WithBlock"));
+        if STATEMENTS.contains(non_term) || with_block {
+            return Doc::group(self.children(non_term, children, true));
+        }
+        let has_clauses = CLAUSE_NODES.contains(non_term);
+        Doc::Concat(self.children(non_term, children, has_clauses))
+    }
+
+    /// Children of a node, with pairs of brackets among them laid out as
+    /// a [bracket](Self::bracket).
+    fn children(&mut self, non_term: &str, children: &[Tree], has_clauses: bool) -> Vec<Doc> {
+        let mut docs = Vec::new();
+        let mut i = 0;
+        while i < children.len() {
+            if has_clauses && i > 0 && self.starts_clause(&children[i - 1], &children[i]) {
+                docs.push(self.line(Doc::Line));
+            }
+            let close = match &children[i] {
+                Tree::Token(open) => self.closing[*open].and_then(|close| {
+                    (children[i..].iter()).position(|c| matches!(c, Tree::Token(t) if *t == close))
+                }),
+                Tree::Node(..) => None,
+            };
+            match close {
+                Some(len) => {
+                    docs.push(self.bracket(non_term, &children[i..=i + len]));
+                    i += len + 1;
+                }
+                None => {
+                    docs.push(self.tree(&children[i]));
+                    i += 1;
+                }
+            }
+        }
+        docs
+    }
+
+    /// Checks if the child starts a clause: a statement after a `WITH` block
+    /// or a keyword of [CLAUSES], which does not follow another keyword
+    /// (`ORDER BY`).
+    fn starts_clause(&self, prev: &Tree, child: &Tree) -> bool {
+        if prev.non_term() == Some("WithBlock") {
+            return true;
+        }
+        let first = &self.tokens[child.first()];
+        let is_clause = CLAUSES.iter().any(|kw| first.text.eq_ignore_ascii_case(kw));
+        is_clause && self.is_keyword(first) && !self.is_keyword(&self.tokens[prev.last()])
+    }
+
+    /// Brackets, with the trees between them. Shapes and blocks are padded
+    /// with spaces and blocks always have each element on its own line.
+    fn bracket(&mut self, non_term: &str, trees: &[Tree]) -> Doc {
+        let (open, close) = (trees[0].first(), trees[trees.len() - 1].first());
+        let contents = &trees[1..trees.len() - 1];
+
+        let open_kind = self.tokens[open].kind;
+        if open_kind == Kind::OpenBracket && non_term == "IndirectionEl" {
+            // index or slice
+            let in_index = std::mem::replace(&mut self.spacing.in_index, true);
+            let mut docs = vec![self.token(open)];
+            docs.extend(self.children(non_term, contents, false));
+            self.spacing.in_index = in_index;
+            docs.push(self.token(close));
+            return Doc::Concat(docs);
+        }
+        if contents.is_empty() {
+            return Doc::Concat(vec![self.token(open), self.token(close)]);
+        }
+
+        let (line, separator) = match open_kind {
+            Kind::OpenBrace if non_term == "Set" => (Doc::SoftLine, Doc::Line),
+            Kind::OpenBrace if is_block(contents) => (Doc::HardLine, Doc::HardLine),
+            Kind::OpenBrace => (Doc::Line, Doc::Line),
+            _ => (Doc::SoftLine, Doc::Line),
+        };
+        let open = self.token(open);
+        let inner = vec![self.line(line.clone()), self.items(contents, separator)];
+        let end = self.line(line);
+        let close = self.token(close);
+        Doc::group(vec![
+            open,
+            Doc::Nest(Box::new(Doc::Concat(inner))),
+            end,
+            close,
+        ])
+    }
+
+    /// Elements of lists (which are flattened) split by the separator line.
+    /// Blank lines between elements are preserved.
+    fn items(&mut self, trees: &[Tree], separator: Doc) -> Doc {
+        let mut elements = Vec::new();
+        flatten(trees, &mut elements);
+
+        let mut docs = Vec::new();
+        let mut first = true;
+        for tree in elements {
+            if let Tree::Token(i) = tree {
+                if matches!(self.tokens[*i].kind, Kind::Comma | Kind::Semicolon) {
+                    docs.push(self.token(*i));
+                    continue;
+                }
+            }
+            if !first {
+                docs.push(self.line(separator.clone()));
+                if self.blank_line_before() {
+                    docs.push(Doc::HardLine);
+                }
+            }
+            first = false;
+            // comments in front of an element do not break its group
+            docs.push(self.comments());
+            docs.push(self.tree(tree));
+        }
+        Doc::Concat(docs)
+    }
+
+    fn has_commas(&self, trees: &[Tree]) -> bool {
+        let mut elements = Vec::new();
+        flatten(trees, &mut elements);
+        (elements.iter())
+            .any(|t| matches!(t, Tree::Token(i) if self.tokens[*i].kind == Kind::Comma))
+    }
+
+    fn blank_line_before(&self) -> bool {
+        let tok = &self.tokens[self.next];
+        let newlines = match tok.comments.get(self.comments_done) {
+            Some(comment) => comment.newlines_before,
+            None => tok.newlines_before,
+        };
+        newlines > 1
+    }
+
+    /// A line, in front of which stays a comment that was on the same line
+    /// as the previous token.
+    fn line(&mut self, line: Doc) -> Doc {
+        let mut docs = Vec::new();
+        let tok = &self.tokens[self.next];
+        if let Some(comment) = tok.comments.get(self.comments_done) {
+            if comment.newlines_before == 0 && !self.separated {
+                docs.push(Doc::Text(format!(" {}", comment.text)));
+                docs.push(Doc::BreakParent);
+                self.comments_done += 1;
+            }
+        }
+        docs.push(line);
+        self.separated = true;
+        Doc::Concat(docs)
+    }
+
+    /// Comments in front of the next token that were not laid out yet. Each
+    /// is on its own line, unless it was on the line of the previous token.
+    fn comments(&mut self) -> Doc {
+        let tok = &self.tokens[self.next];
+        let comments = &tok.comments[self.comments_done..];
+        if comments.is_empty() {
+            return Doc::Concat(Vec::new());
+        }
+        self.comments_done = tok.comments.len();
+
+        let mut docs = Vec::new();
+        for comment in comments {
+            if !self.separated {
+                docs.push(match comment.newlines_before {
+                    0 => Doc::Text(" ".to_string()),
+                    _ => Doc::HardLine,
+                });
+            }
+            docs.push(Doc::Text(comment.text.to_string()));
+            docs.push(Doc::BreakParent);
+            self.separated = false;
+        }
+        docs.push(Doc::HardLine);
+        self.separated = true;
+        Doc::Concat(docs)
+    }
+
+    fn token(&mut self, index: usize) -> Doc {
+        debug_assert_eq!(index, self.next, "tokens are laid out in order");
+        let mut docs = vec![self.comments()];
+
+        let tok = &self.tokens[index];
+        if !self.separated && self.spacing.space_before(tok.kind) {
+            docs.push(Doc::Text(" ".to_string()));
+        }
+        let text = match self.is_keyword(tok) {
+            true => change_case(tok.text, self.keyword_case),
+            false => tok.text.to_string(),
+        };
+        docs.push(Doc::Text(text));
+        self.spacing.after(tok.kind);
+
+        self.separated = false;
+        self.next = index + 1;
+        self.comments_done = 0;
+        Doc::Concat(docs)
+    }
+
+    /// Checks if the token is a keyword, which was not parsed as a name.
+    fn is_keyword(&self, tok: &Tok) -> bool {
+        matches!(tok.kind, Kind::Keyword(_)) && !self.names.contains(&tok.start)
+    }
+}
+
+/// Lists are named by their elements (`ExprList`), with a left-recursive
+/// `Inner` non-terminal, or are blocks of statements.
+fn is_list(non_term: &str) -> bool {
+    non_term.ends_with("List")
+        || non_term.ends_with("ListInner")
+        || non_term.ends_with("Statements")
+        || non_term.ends_with("Semicolons")
+        || non_term == "StatementBlock"
+}
+
+/// Elements of the trees, with lists replaced by their elements and
+/// separators.
+fn flatten<'t, 's>(trees: &'t [Tree<'s>], elements: &mut Vec<&'t Tree<'s>>) {
+    for tree in trees {
+        match tree {
+            Tree::Node(non_term, children) if is_list(non_term) => flatten(children, elements),
+            _ => elements.push(tree),
+        }
+    }
+}
+
+/// Blocks contain DDL commands, SDL declarations or statements.
+fn is_block(contents: &[Tree]) -> bool {
+    contents.iter().any(|tree| match tree {
+        Tree::Token(_) => false,
+        Tree::Node(non_term, _) => {
+            non_term.contains("Command")
+                || non_term.contains("SDL")
+                || non_term.contains("Statement")
+        }
+    })
+}
+
+/// Decides whether there should be a space between two tokens.
+#[derive(Default)]
+struct Spacing {
+    prev: Option<Kind>,
+    /// Depth of type casts `<...>`
+    cast_depth: usize,
+    /// Within array indexing or slicing
+    in_index: bool,
+    /// Previous token was an unary operator.
+    after_unary: bool,
+    /// Previous token closed a type cast.
+    after_cast: bool,
+}
+
+impl Spacing {
+    fn space_before(&mut self, next: Kind) -> bool {
+        use Kind::*;
+
+        let Some(prev) = self.prev else {
+            return false;
+        };
+        if self.after_unary || self.after_cast {
+            return false;
+        }
+        if matches!(
+            prev,
+            OpenParen | OpenBracket | Dot | BackwardLink | At | Namespace
+        ) {
+            return false;
+        }
+        if self.in_index && (prev == Colon || next == Colon) {
+            return false;
+        }
+        match next {
+            Comma | Semicolon | CloseParen | CloseBracket | CloseBrace | Namespace | Colon => false,
+            Dot | BackwardLink | OpenParen | OpenBracket => !is_operand(prev),
+            Greater if self.cast_depth > 0 => false,
+            Less if self.cast_depth > 0 => false,
+            _ => true,
+        }
+    }
+
+    fn after(&mut self, kind: Kind) {
+        use Kind::*;
+
+        let prev_is_operand = self.prev.map_or(false, is_operand);
+        self.after_unary = false;
+        self.after_cast = false;
+        match kind {
+            Add | Sub if !prev_is_operand => self.after_unary = true,
+            Less if self.cast_depth > 0 || !prev_is_operand => {
+                self.cast_depth += 1;
+                self.after_unary = true;
+            }
+            Greater if self.cast_depth > 0 => {
+                self.cast_depth -= 1;
+                self.after_cast = self.cast_depth == 0;
+                self.after_unary = self.cast_depth > 0;
+            }
+            _ => {}
+        }
+        self.prev = Some(kind);
+    }
+}
+
+fn is_operand(kind: Kind) -> bool {
+    use Kind::*;
+
+    matches!(
+        kind,
+        Keyword(crate::keywords::Keyword("true" | "false"))
+            | Ident
+            | BacktickName
+            | Parameter
+            | Str
+            | BinStr
+            | IntConst
+            | FloatConst
+            | BigIntConst
+            | DecimalConst
+            | Substitution
+            | CloseParen
+            | CloseBracket
+            | CloseBrace
+    )
+}
+
+/// Lays out the document, breaking groups that do not fit into the width.
+fn print(doc: &Doc, options: &Options) -> String {
+    #[derive(Clone, Copy, PartialEq)]
+    enum Mode {
+        Flat,
+        Break,
+    }
+
+    let mut out = String::new();
+    let mut column = 0;
+    let mut stack = vec![(0, Mode::Break, doc)];
+    while let Some((indent, mode, doc)) = stack.pop() {
+        match doc {
+            Doc::Text(text) => {
+                out.push_str(text);
+                column += text.width();
+            }
+            Doc::Line if mode == Mode::Flat => {
+                out.push(' ');
+                column += 1;
+            }
+            Doc::SoftLine if mode == Mode::Flat => {}
+            Doc::Line | Doc::SoftLine | Doc::HardLine => {
+                let trimmed = out.trim_end_matches(' ').len();
+                out.truncate(trimmed);
+                out.push('\n');
+                out.extend(std::iter::repeat(' ').take(indent));
+                column = indent;
+            }
+            Doc::BreakParent => {}
+            Doc::Concat(docs) => {
+                stack.extend(docs.iter().rev().map(|d| (indent, mode, d)));
+            }
+            Doc::Nest(doc) => stack.push((indent + options.indent, mode, doc)),
+            Doc::Group { doc, forced } => {
+                let fits = !forced && fits(doc, options.width.saturating_sub(column));
+                let mode = if mode == Mode::Flat || fits {
+                    Mode::Flat
+                } else {
+                    Mode::Break
+                };
+                stack.push((indent, mode, doc));
+            }
+        }
+    }
+    out.trim_end().to_string()
+}
+
+/// Checks if the document fits into the width when laid out on a single
+/// line.
+fn fits(doc: &Doc, width: usize) -> bool {
+    fn measure(doc: &Doc, remaining: &mut usize) -> bool {
+        match doc {
+            Doc::Text(text) => {
+                let width = text.width();
+                if width > *remaining {
+                    return false;
+                }
+                *remaining -= width;
+                true
+            }
+            Doc::Line if *remaining > 0 => {
+                *remaining -= 1;
+                true
+            }
+            Doc::Line => false,
+            Doc::SoftLine | Doc::BreakParent => true,
+            Doc::HardLine => false,
+            Doc::Concat(docs) => docs.iter().all(|d| measure(d, remaining)),
+            Doc::Nest(doc) | Doc::Group { doc, .. } => measure(doc, remaining),
+        }
+    }
+    let mut remaining = width;
+    measure(doc, &mut remaining)
+}
@@ -1,14 +1,13 @@
-#![cfg(never)] // TODO: migrate cpython-rust to pyo3
 use indexmap::IndexMap;
 
-use cpython::{
-    PyBytes, PyDict, PyList, PyObject, PyResult, PyTuple, Python, PythonObject, ToPyObject,
-};
+use pyo3::prelude::*;
+use pyo3::sync::GILOnceCell;
+use pyo3::types::{PyBytes, PyDict, PyList, PyModule, PyTuple};
 
 /// Convert into a Python object.
 ///
 /// Primitives (i64, String, Option, Vec) have this trait implemented with
-/// calls to [cpython].
+/// calls to [pyo3].
 ///
 /// Structs have this trait derived to collect all their properties into a
 /// [PyDict] and call constructor of the AST node.
@@ -18,45 +17,49 @@ use cpython::{
 ///
 /// See [edgeql_parser_derive] crate.
 pub trait IntoPython: Sized {
-    fn into_python(self, py: Python<'_>, parent_kw_args: Option<PyDict>) -> PyResult<PyObject>;
+    fn into_python<'py>(
+        self,
+        py: Python<'py>,
+        parent_kw_args: Option<&'py PyDict>,
+    ) -> PyResult<PyObject>;
 }
 
 impl IntoPython for String {
-    fn into_python(self, py: Python<'_>, _: Option<PyDict>) -> PyResult<PyObject> {
-        Ok(self.to_py_object(py).into_object())
+    fn into_python<'py>(self, py: Python<'py>, _: Option<&'py PyDict>) -> PyResult<PyObject> {
+        Ok(self.into_py(py))
     }
 }
 
 impl IntoPython for i64 {
-    fn into_python(self, py: Python<'_>, _: Option<PyDict>) -> PyResult<PyObject> {
-        Ok(self.to_py_object(py).into_object())
+    fn into_python<'py>(self, py: Python<'py>, _: Option<&'py PyDict>) -> PyResult<PyObject> {
+        Ok(self.into_py(py))
     }
 }
 
 impl IntoPython for f64 {
-    fn into_python(self, py: Python<'_>, _: Option<PyDict>) -> PyResult<PyObject> {
-        Ok(self.to_py_object(py).into_object())
+    fn into_python<'py>(self, py: Python<'py>, _: Option<&'py PyDict>) -> PyResult<PyObject> {
+        Ok(self.into_py(py))
     }
 }
 
 impl IntoPython for bool {
-    fn into_python(self, py: Python<'_>, _: Option<PyDict>) -> PyResult<PyObject> {
-        Ok(if self { py.True() } else { py.False() }.into_object())
+    fn into_python<'py>(self, py: Python<'py>, _: Option<&'py PyDict>) -> PyResult<PyObject> {
+        Ok(self.into_py(py))
     }
 }
 
 impl<T: IntoPython> IntoPython for Vec<T> {
-    fn into_python(self, py: Python<'_>, _: Option<PyDict>) -> PyResult<PyObject> {
+    fn into_python<'py>(self, py: Python<'py>, _: Option<&'py PyDict>) -> PyResult<PyObject> {
         let mut elements = Vec::new();
         for x in self {
             elements.push(x.into_python(py, None)?);
         }
-        Ok(PyList::new(py, elements.as_slice()).into_object())
+        Ok(PyList::new(py, elements).into())
     }
 }
 
 impl<T: IntoPython> IntoPython for Option<T> {
-    fn into_python(self, py: Python<'_>, _: Option<PyDict>) -> PyResult<PyObject> {
+    fn into_python<'py>(self, py: Python<'py>, _: Option<&'py PyDict>) -> PyResult<PyObject> {
         if let Some(value) = self {
             value.into_python(py, None)
         } else {
@@ -66,52 +69,75 @@ impl<T: IntoPython> IntoPython for Option<T> {
 }
 
 impl<T: IntoPython> IntoPython for Box<T> {
-    fn into_python(self, py: Python<'_>, _: Option<PyDict>) -> PyResult<PyObject> {
+    fn into_python<'py>(self, py: Python<'py>, _: Option<&'py PyDict>) -> PyResult<PyObject> {
         (*self).into_python(py, None)
     }
 }
 
 impl<T1: IntoPython, T2: IntoPython> IntoPython for (T1, T2) {
-    fn into_python(self, py: Python<'_>, _: Option<PyDict>) -> PyResult<PyObject> {
-        let mut elements = Vec::new();
-        elements.push(self.0.into_python(py, None)?);
-        elements.push(self.1.into_python(py, None)?);
-        Ok(PyTuple::new(py, elements.as_slice()).into_object())
+    fn into_python<'py>(self, py: Python<'py>, _: Option<&'py PyDict>) -> PyResult<PyObject> {
+        let elements = [self.0.into_python(py, None)?, self.1.into_python(py, None)?];
+        Ok(PyTuple::new(py, elements).into())
     }
 }
 
 impl<K: IntoPython, V: IntoPython> IntoPython for IndexMap<K, V> {
-    fn into_python(self, py: Python<'_>, _: Option<PyDict>) -> PyResult<PyObject> {
+    fn into_python<'py>(self, py: Python<'py>, _: Option<&'py PyDict>) -> PyResult<PyObject> {
         let dict = PyDict::new(py);
         for (key, value) in self {
             let key = key.into_python(py, None)?;
             let value = value.into_python(py, None)?;
-            dict.set_item(py, key, value)?;
+            dict.set_item(key, value)?;
         }
-        Ok(dict.into_object())
+        Ok(dict.into())
     }
 }
 
 impl IntoPython for Vec<u8> {
-    fn into_python(self, py: Python<'_>, _: Option<PyDict>) -> PyResult<PyObject> {
-        Ok(PyBytes::new(py, self.as_slice()).into_object())
+    fn into_python<'py>(self, py: Python<'py>, _: Option<&'py PyDict>) -> PyResult<PyObject> {
+        Ok(PyBytes::new(py, self.as_slice()).into())
     }
 }
 
 impl IntoPython for () {
-    fn into_python(self, py: Python<'_>, _: Option<PyDict>) -> PyResult<PyObject> {
-        Ok(py.None().into_object())
+    fn into_python<'py>(self, py: Python<'py>, _: Option<&'py PyDict>) -> PyResult<PyObject> {
+        Ok(py.None())
     }
 }
 
+static QLAST: GILOnceCell<Py<PyModule>> = GILOnceCell::new();
+static QLTYPES: GILOnceCell<Py<PyModule>> = GILOnceCell::new();
+
+/// Resolves module alias, as used in generated `ast.rs`.
+fn get_module<'py>(py: Python<'py>, alias: &str) -> PyResult<&'py PyModule> {
+    let (cell, name) = match alias {
+        "qlast" => (&QLAST, "edb.edgeql.ast"),
+        "qltypes" => (&QLTYPES, "edb.edgeql.qltypes"),
+        _ => {
+            let msg = format!("unknown AST module: {alias}");
+            return Err(pyo3::exceptions::PyValueError::new_err(msg));
+        }
+    };
+    let module = cell.get_or_try_init(py, || PyModule::import(py, name).map(Into::into))?;
+    Ok(module.as_ref(py))
+}
+
 pub fn init_ast_class(
     py: Python,
     class_name: &'static str,
-    kw_args: PyDict,
-) -> Result<PyObject, cpython::PyErr> {
-    let locals = PyDict::new(py);
-    locals.set_item(py, "kw_args", kw_args)?;
+    kw_args: &PyDict,
+) -> PyResult<PyObject> {
+    let class = get_module(py, "qlast")?.getattr(class_name)?;
+    Ok(class.call((), Some(kw_args))?.into())
+}
 
-    let code = format!("qlast.{class_name}(**kw_args)");
-    py.eval(&code, None, Some(&locals))
+/// Looks up a variant of a Python enum, such as `qlast.SortOrder.Asc`.
+pub fn get_enum_variant(
+    py: Python,
+    class_path: &'static str,
+    variant: &'static str,
+) -> PyResult<PyObject> {
+    let (module, class_name) = class_path.split_once('.').unwrap_or(("qlast", class_path));
+    let class = get_module(py, module)?.getattr(class_name)?;
+    Ok(class.getattr(variant)?.into())
 }
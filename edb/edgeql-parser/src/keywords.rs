@@ -236,6 +236,140 @@ impl Keyword {
     pub fn is_reserved(&self) -> bool {
         FUTURE_RESERVED_KEYWORDS.contains(self.0) || CURRENT_RESERVED_KEYWORDS.contains(self.0)
     }
+
+    /// Same as [Keyword::is_reserved], but in the specified version.
+    pub fn is_reserved_at(&self, version: Version) -> bool {
+        matches!(
+            class_at(self.0, version),
+            Some(KeywordClass::FutureReserved | KeywordClass::Reserved)
+        )
+    }
+}
+
+/// Version of the language, which is the major and minor version of the
+/// server that introduced it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Version {
+    pub major: u16,
+    pub minor: u16,
+}
+
+impl Version {
+    pub const fn new(major: u16, minor: u16) -> Version {
+        Version { major, minor }
+    }
+}
+
+impl std::fmt::Display for Version {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// Which of the keyword sets contains the keyword.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeywordClass {
+    Unreserved,
+    PartialReserved,
+    FutureReserved,
+    Reserved,
+    Combined,
+}
+
+/// Changes of the keyword sets: the keyword, version in which it was moved
+/// into its current set and the set it belonged to before (`None` when it
+/// was not a keyword).
+///
+/// Keywords that are not listed have been in their current set since 1.0.
+/// Entries for the same keyword go from the newest to the oldest.
+///
+/// Collected from the changelogs (`docs/changelog/*.rst`), from the
+/// sections of the release that introduced the syntax using the keyword.
+/// Changes made before 1.0 (such as the keywords unreserved in 1.0-rc5) are
+/// not listed.
+pub const KEYWORD_CHANGES: &[(&str, Version, Option<KeywordClass>)] = &[
+    // 5_x.rst: "EdgeDB branches" and `administer vacuum()`, `anyobject`
+    // first appears in the signatures of `ext::ai`
+    ("administer", Version::new(5, 0), None),
+    ("anyobject", Version::new(5, 0), None),
+    ("branch", Version::new(5, 0), None),
+    ("data", Version::new(5, 0), None),
+    // 4_x.rst: new style of `if`/`then`/`else`
+    ("then", Version::new(4, 0), None),
+    // 3_x.rst: "Triggers", "Mutation rewrites", "Query performance
+    // analysis" and "`intersect` and `except` operators"
+    ("after", Version::new(3, 0), None),
+    ("analyze", Version::new(3, 0), Some(KeywordClass::FutureReserved)),
+    ("do", Version::new(3, 0), Some(KeywordClass::FutureReserved)),
+    ("each", Version::new(3, 0), None),
+    ("except", Version::new(3, 0), Some(KeywordClass::FutureReserved)),
+    ("intersect", Version::new(3, 0), Some(KeywordClass::FutureReserved)),
+    ("trigger", Version::new(3, 0), None),
+    ("__new__", Version::new(3, 0), None),
+    ("__old__", Version::new(3, 0), None),
+    ("__specified__", Version::new(3, 0), None),
+    // 2_x.rst, 2.13: `start migration rewrite`
+    ("rewrite", Version::new(2, 13), None),
+    // 2_x.rst, 2.6: `using future nonrecursive_access_policies`
+    ("future", Version::new(2, 6), None),
+    // 2_x.rst: "Analytical queries with `GROUP`", "Object-level security"
+    // and "Source deletion policies"
+    ("access", Version::new(2, 0), None),
+    ("allow", Version::new(2, 0), None),
+    ("cube", Version::new(2, 0), None),
+    ("deny", Version::new(2, 0), None),
+    ("orphan", Version::new(2, 0), None),
+    ("policy", Version::new(2, 0), None),
+    ("rollup", Version::new(2, 0), None),
+];
+
+/// Set containing the keyword in the current version.
+pub fn class(s: &str) -> Option<KeywordClass> {
+    if PARTIAL_RESERVED_KEYWORDS.contains(s) {
+        Some(KeywordClass::PartialReserved)
+    } else if FUTURE_RESERVED_KEYWORDS.contains(s) {
+        Some(KeywordClass::FutureReserved)
+    } else if CURRENT_RESERVED_KEYWORDS.contains(s) {
+        Some(KeywordClass::Reserved)
+    } else if COMBINED_KEYWORDS.contains(s) {
+        Some(KeywordClass::Combined)
+    } else if UNRESERVED_KEYWORDS.contains(s) {
+        Some(KeywordClass::Unreserved)
+    } else {
+        None
+    }
+}
+
+/// Set containing the keyword in the specified version.
+pub fn class_at(s: &str, version: Version) -> Option<KeywordClass> {
+    let mut class = class(s);
+    for (keyword, since, before) in KEYWORD_CHANGES {
+        if *keyword == s && version < *since {
+            class = *before;
+        }
+    }
+    class
+}
+
+/// Same as [lookup], but with keywords of the specified version.
+pub fn lookup_at(s: &str, version: Version) -> Option<Keyword> {
+    match class_at(s, version)? {
+        KeywordClass::Unreserved | KeywordClass::Combined => None,
+        _ => Some(static_keyword(s)),
+    }
+}
+
+/// Same as [lookup_all], but with keywords of the specified version.
+pub fn lookup_all_at(s: &str, version: Version) -> Option<Keyword> {
+    class_at(s, version).map(|_| static_keyword(s))
+}
+
+fn static_keyword(s: &str) -> Keyword {
+    let keyword = lookup_all(s)
+        .map(|k| k.0)
+        .or_else(|| KEYWORD_CHANGES.iter().map(|c| c.0).find(|k| *k == s))
+        .expect("keyword is in one of the sets");
+    Keyword(keyword)
 }
 
 impl From<Keyword> for &'static str {
@@ -1,13 +1,20 @@
 pub mod ast;
+pub mod diagnostics;
 pub mod expr;
+pub mod format;
 pub mod hash;
 pub mod helpers;
 #[cfg(feature = "python")]
 pub mod into_python;
 pub mod keywords;
+#[cfg(feature = "normalize")]
+pub mod normalize;
 pub mod parser;
 pub mod position;
 pub mod preparser;
+pub mod rewrite;
+pub mod render;
 pub mod schema_file;
 pub mod tokenizer;
+pub mod upgrade;
 pub mod validation;
@@ -0,0 +1,724 @@
+//! Normalization of queries for caching: literals are extracted into query
+//! arguments, so that queries which differ only in constants share a cache
+//! entry (keyed by the hash of the normalized text).
+//!
+//! Which literals are extracted depends on their position in the grammar,
+//! so the normalized text, and the cache key, are only stable for a fixed
+//! grammar spec. For queries that cannot be parsed, the position is guessed
+//! from the preceding token.
+//!
+//! Values of extracted literals are encoded by an [`Encoder`], which is
+//! provided by the caller.
+
+use std::collections::{BTreeSet, HashSet};
+use std::ops::Range;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use bigdecimal::BigDecimal;
+use blake2::{Blake2b512, Digest};
+
+use crate::keywords::Keyword;
+use crate::parser::Spec;
+use crate::position::{Pos, Span};
+use crate::preparser::full_statement;
+use crate::tokenizer::{self, Kind, Token, Tokenizer};
+
+use literals::{guess_context, literal_contexts, LiteralPolicy};
+
+pub mod literals;
+
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Variable {
+    pub value: Value,
+}
+
+/// Value of an extracted literal: the value of its token, or of a literal
+/// which is not a single token (`true`, `-1`, `[1, 2]`).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Value {
+    String(String),
+    Int(i64),
+    Float(f64),
+    Bytes(Vec<u8>),
+
+    /// Radix 16
+    BigInt(String),
+    Decimal(BigDecimal),
+    Bool(bool),
+    /// Elements are of the same variant
+    Array(Vec<Value>),
+}
+
+impl From<tokenizer::Value> for Value {
+    fn from(value: tokenizer::Value) -> Value {
+        match value {
+            tokenizer::Value::String(v) => Value::String(v),
+            tokenizer::Value::Int(v) => Value::Int(v),
+            tokenizer::Value::Float(v) => Value::Float(v),
+            tokenizer::Value::Bytes(v) => Value::Bytes(v),
+            tokenizer::Value::BigInt(v) => Value::BigInt(v),
+            tokenizer::Value::Decimal(v) => Value::Decimal(v),
+        }
+    }
+}
+
+pub struct Entry {
+    pub processed_source: String,
+    pub hash: [u8; 64],
+    pub tokens: Vec<Token<'static>>,
+    pub variables: Vec<Vec<Variable>>,
+    pub named_args: bool,
+    pub first_arg: Option<usize>,
+}
+
+/// PackedEntry is a compact Entry for serialization purposes
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PackedEntry {
+    pub tokens: Vec<Token<'static>>,
+    pub variables: Vec<Vec<Variable>>,
+    pub named_args: bool,
+    pub first_arg: Option<usize>,
+}
+
+impl From<Entry> for PackedEntry {
+    fn from(entry: Entry) -> PackedEntry {
+        PackedEntry {
+            tokens: entry.tokens,
+            variables: entry.variables,
+            named_args: entry.named_args,
+            first_arg: entry.first_arg,
+        }
+    }
+}
+
+impl From<PackedEntry> for Entry {
+    fn from(packed: PackedEntry) -> Entry {
+        let processed_source = serialize_tokens(&packed.tokens[..]);
+        Entry {
+            hash: hash(&processed_source),
+            processed_source,
+            tokens: packed.tokens,
+            variables: packed.variables,
+            named_args: packed.named_args,
+            first_arg: packed.first_arg,
+        }
+    }
+}
+
+/// Encoding of extracted values into the binary format of query arguments
+pub trait Encoder {
+    /// Writes the value, without its length
+    fn encode_value(&self, buf: &mut Vec<u8>, value: &Value) -> Result<(), String>;
+}
+
+/// Encodes variables of a statement, each prefixed with its length
+pub fn encode_variables<E>(encoder: &E, variables: &[Variable]) -> Result<Vec<u8>, String>
+where
+    E: Encoder + ?Sized,
+{
+    let mut buf = Vec::with_capacity(4 * variables.len());
+    for var in variables {
+        encode_element(encoder, &mut buf, &var.value)?;
+    }
+    Ok(buf)
+}
+
+/// Encodes the value prefixed with its length
+pub fn encode_element<E>(encoder: &E, buf: &mut Vec<u8>, value: &Value) -> Result<(), String>
+where
+    E: Encoder + ?Sized,
+{
+    let pos = buf.len();
+    buf.extend_from_slice(&[0; 4]); // replaced after serializing a value
+    encoder.encode_value(buf, value)?;
+    let len = buf.len() - pos - 4;
+    buf[pos..pos + 4].copy_from_slice(
+        &u32::try_from(len)
+            .map_err(|_| "element is too long".to_owned())?
+            .to_be_bytes(),
+    );
+    Ok(())
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Tokenizer(String, u64),
+    Assertion(String, Pos),
+}
+
+#[derive(Clone)]
+pub struct Options<'a> {
+    pub policy: LiteralPolicy,
+    /// Grammar used to find contexts of literals
+    pub spec: &'a Spec,
+}
+
+impl<'a> Options<'a> {
+    /// Options with the default [`LiteralPolicy`]
+    pub fn new(spec: &'a Spec) -> Options<'a> {
+        Options {
+            policy: LiteralPolicy::default(),
+            spec,
+        }
+    }
+}
+
+pub fn normalize(text: &str, spec: &Spec) -> Result<Entry, Error> {
+    normalize_with(text, &Options::new(spec))
+}
+
+pub fn normalize_with(text: &str, options: &Options) -> Result<Entry, Error> {
+    let tokens = Tokenizer::new(text)
+        .validated_values()
+        .with_eof()
+        .map(|x| x.map(|t| t.cloned()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| Error::Tokenizer(e.message, e.span.start))?;
+
+    let (named_args, var_idx) = match scan_vars(&tokens) {
+        Some(pair) => pair,
+        None => {
+            // don't extract from invalid query, let python code do its work
+            let processed_source = serialize_tokens(&tokens);
+            return Ok(Entry {
+                hash: hash(&processed_source),
+                processed_source,
+                tokens,
+                variables: Vec::new(),
+                named_args: false,
+                first_arg: None,
+            });
+        }
+    };
+    let statements = split_statements(text, &tokens);
+    if statements
+        .iter()
+        .all(|s| is_literal_statement(&tokens[s.clone()]))
+    {
+        return Ok(verbatim(text, tokens));
+    }
+
+    let contexts = literal_contexts(text, options.spec);
+    let keep_literal = |tok: &Token, prev: Option<&Token>| {
+        let context = match &contexts {
+            Some(contexts) => contexts.get(&tok.span.start).copied(),
+            None => guess_context(prev),
+        };
+        context.map_or(false, |c| options.policy.keeps(c, &tok.text))
+    };
+
+    let mut rewritten_tokens = Vec::with_capacity(tokens.len());
+    let mut all_variables = Vec::new();
+    let mut counter = var_idx;
+    let mut next_var = || {
+        let n = counter;
+        counter += 1;
+        if named_args {
+            format!("$__edb_arg_{}", n)
+        } else {
+            format!("${}", n)
+        }
+    };
+    for statement in statements {
+        let tokens = &tokens[statement];
+        if is_literal_statement(tokens) {
+            rewritten_tokens.extend(tokens.iter().cloned());
+            all_variables.push(Vec::new());
+            continue;
+        }
+
+        let mut variables = Vec::new();
+        let mut skip = 0;
+        for (idx, tok) in tokens.iter().enumerate() {
+            if skip > 0 {
+                skip -= 1;
+                continue;
+            }
+            // Negative numbers and arrays span multiple tokens
+            if is_value_position(rewritten_tokens.last()) {
+                let literal = match tok.kind {
+                    Kind::Sub => negative_literal(&tokens[idx..]),
+                    Kind::OpenBracket => array_literal(&tokens[idx..]),
+                    _ => None,
+                };
+                if let Some((typ, value, len)) = literal {
+                    let literal_tokens = &tokens[idx..idx + len];
+                    // contexts of elements are guessed from the token before
+                    // the whole literal
+                    let prev = rewritten_tokens.last();
+                    if literal_tokens.iter().any(|t| keep_literal(t, prev)) {
+                        rewritten_tokens.extend(literal_tokens.iter().cloned());
+                    } else {
+                        let span = Span {
+                            start: tok.span.start,
+                            end: tokens[idx + len - 1].span.end,
+                        };
+                        rewritten_tokens.push(arg_type_cast(&typ, next_var(), span));
+                        variables.push(Variable { value });
+                    }
+                    skip = len - 1;
+                    continue;
+                }
+            }
+            match literal(tok) {
+                Some((typ, value)) if !keep_literal(tok, rewritten_tokens.last()) => {
+                    rewritten_tokens.push(arg_type_cast(typ, next_var(), tok.span));
+                    variables.push(Variable { value });
+                }
+                _ => rewritten_tokens.push(tok.clone()),
+            }
+        }
+        all_variables.push(variables);
+    }
+
+    let processed_source = if counter <= var_idx {
+        // Just use the original text when there is no literal to extract,
+        // in order to save the time calling `serialize_tokens()`
+        text.to_string()
+    } else {
+        serialize_tokens(&rewritten_tokens[..])
+    };
+    Ok(Entry {
+        hash: hash(&processed_source),
+        processed_source,
+        named_args,
+        first_arg: if counter <= var_idx {
+            None
+        } else {
+            Some(var_idx)
+        },
+        tokens: rewritten_tokens,
+        variables: all_variables,
+    })
+}
+
+/// Entry of a query whose literals are left in place.
+fn verbatim(text: &str, tokens: Vec<Token<'static>>) -> Entry {
+    Entry {
+        hash: hash(text),
+        processed_source: text.to_string(),
+        tokens,
+        variables: Vec::new(),
+        named_args: false,
+        first_arg: None,
+    }
+}
+
+/// Splits tokens into statements, each including its semicolon. Text after
+/// the last semicolon is the last statement, unless there are no tokens
+/// except EOF, which then belongs to the statement before it.
+///
+/// Semicolons within braces of DDL statements don't split statements.
+fn split_statements(text: &str, tokens: &[Token]) -> Vec<Range<usize>> {
+    let mut ends = Vec::new();
+    let mut offset = 0;
+    while let Ok(len) = full_statement(&text.as_bytes()[offset..], None) {
+        offset += len;
+        ends.push(offset as u64);
+    }
+
+    let mut statements = Vec::with_capacity(ends.len() + 1);
+    let mut start = 0;
+    let mut ends = ends.into_iter().peekable();
+    for (idx, tok) in tokens.iter().enumerate() {
+        if tok.kind == Kind::Semicolon && ends.peek() == Some(&tok.span.end) {
+            ends.next();
+            statements.push(start..idx + 1);
+            start = idx + 1;
+        }
+    }
+    let only_eof = tokens[start..].iter().all(|t| t.kind == Kind::EOF);
+    match statements.last_mut() {
+        Some(last) if only_eof => last.end = tokens.len(),
+        _ => statements.push(start..tokens.len()),
+    }
+    statements
+}
+
+/// Keywords that start a statement, i.e. the ones which can follow a
+/// `WITH` block.
+const STATEMENT_KEYWORDS: &[&str] = &[
+    "select",
+    "insert",
+    "update",
+    "delete",
+    "group",
+    "for",
+    "configure",
+    "create",
+    "alter",
+    "drop",
+    "start",
+    "analyze",
+    "set",
+    "describe",
+    "administer",
+    "reset",
+];
+
+/// Checks if the statement has to be kept as is, without extracting its
+/// literals: DDL, CONFIGURE, SET GLOBAL and other statements that cannot
+/// have parameters. These are recognized by the keyword which starts the
+/// statement.
+fn is_literal_statement(tokens: &[Token]) -> bool {
+    let Some(idx) = statement_start(tokens) else {
+        return false;
+    };
+    match tokens[idx].kind {
+        Kind::Keyword(Keyword(kw)) => {
+            matches!(
+                kw,
+                "configure" | "create" | "alter" | "drop" | "start" | "analyze"
+            ) || kw == "set"
+                && matches!(
+                    tokens.get(idx + 1),
+                    Some(Token {
+                        kind: Kind::Keyword(Keyword("global")),
+                        ..
+                    })
+                )
+        }
+        _ => false,
+    }
+}
+
+/// Index of the token which starts the statement: the first one or, after
+/// a `WITH` block, the first statement keyword outside of brackets.
+fn statement_start(tokens: &[Token]) -> Option<usize> {
+    if tokens.first()?.kind != Kind::Keyword(Keyword("with")) {
+        return Some(0);
+    }
+    let mut depth = 0usize;
+    for (idx, tok) in tokens.iter().enumerate() {
+        match tok.kind {
+            Kind::OpenParen | Kind::OpenBracket | Kind::OpenBrace => {
+                depth += 1;
+            }
+            Kind::CloseParen | Kind::CloseBracket | Kind::CloseBrace => {
+                depth = depth.saturating_sub(1);
+            }
+            Kind::Keyword(Keyword(kw)) if depth == 0 && STATEMENT_KEYWORDS.contains(&kw) => {
+                return Some(idx)
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Normalizes many queries, using up to `threads` threads (or one per CPU
+/// when zero).
+///
+/// Results are returned in the order of `texts`, together with the index of
+/// the text. Queries that normalize to the same hash as a preceding query
+/// are omitted, since they would produce the same cache entry.
+pub fn normalize_batch<S>(
+    texts: &[S],
+    threads: usize,
+    options: &Options,
+) -> Vec<(usize, Result<Entry, Error>)>
+where
+    S: AsRef<str> + Sync,
+{
+    let threads = if threads == 0 {
+        std::thread::available_parallelism().map_or(1, |n| n.get())
+    } else {
+        threads
+    };
+    let threads = threads.min(texts.len()).max(1);
+
+    let mut results: Vec<Option<Result<Entry, Error>>> = Vec::new();
+    results.resize_with(texts.len(), || None);
+    let next = AtomicUsize::new(0);
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    loop {
+                        let idx = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(text) = texts.get(idx) else { break };
+                        done.push((idx, normalize_with(text.as_ref(), options)));
+                    }
+                    done
+                })
+            })
+            .collect();
+        for worker in workers {
+            let done = worker
+                .join()
+                .unwrap_or_else(|e| std::panic::resume_unwind(e));
+            for (idx, result) in done {
+                results[idx] = Some(result);
+            }
+        }
+    });
+
+    let mut seen = HashSet::new();
+    results
+        .into_iter()
+        .map(|r| r.expect("all texts are normalized"))
+        .enumerate()
+        .filter(|(_, r)| match r {
+            Ok(entry) => seen.insert(entry.hash),
+            Err(_) => true,
+        })
+        .collect()
+}
+
+/// Checks if an operand can start after the token, so that `-` is an unary
+/// minus and `[` starts an array (rather than indexing).
+fn is_value_position(prev: Option<&Token>) -> bool {
+    match prev {
+        None => true,
+        Some(Token {
+            kind: Kind::Keyword(kw),
+            ..
+        }) => {
+            // unreserved keywords might be names
+            kw.is_reserved() && !matches!(kw.0, "true" | "false") && !kw.0.starts_with("__")
+        }
+        Some(Token {
+            kind: Kind::CloseParen | Kind::CloseBracket | Kind::CloseBrace | Kind::Dot,
+            ..
+        }) => false,
+        Some(token) => is_operator(token),
+    }
+}
+
+/// Type and value of a literal token (i.e. not an unary minus)
+fn literal(tok: &Token) -> Option<(&'static str, Value)> {
+    let typ = match tok.kind {
+        Kind::IntConst => "int64",
+        Kind::FloatConst => "float64",
+        Kind::BigIntConst => "bigint",
+        Kind::DecimalConst => "decimal",
+        Kind::Str => "str",
+        Kind::BinStr => "bytes",
+        Kind::Keyword(Keyword(kw @ ("true" | "false"))) => {
+            return Some(("bool", Value::Bool(kw == "true")));
+        }
+        _ => return None,
+    };
+    match tok.value.clone()? {
+        // values above i64::MAX are read as negative numbers, these fit into
+        // int64 only with a minus
+        tokenizer::Value::Int(v) if v < 0 => None,
+        value => Some((typ, value.into())),
+    }
+}
+
+/// Number with an unary minus at the start of tokens, returns type, value
+/// and number of tokens.
+fn negative_literal(tokens: &[Token]) -> Option<(String, Value, usize)> {
+    let [minus, number, rest @ ..] = tokens else {
+        return None;
+    };
+    if minus.kind != Kind::Sub {
+        return None;
+    }
+    // `-2 ^ 2` is `-(2 ^ 2)`
+    if matches!(
+        rest.first(),
+        Some(Token {
+            kind: Kind::Pow,
+            ..
+        })
+    ) {
+        return None;
+    }
+    let value = match (number.kind, number.value.clone()?.into()) {
+        (Kind::IntConst, Value::Int(v)) if v >= 0 => Value::Int(-v),
+        // -9223372036854775808 is read as a positive number
+        (Kind::IntConst, Value::Int(i64::MIN)) => Value::Int(i64::MIN),
+        (Kind::FloatConst, Value::Float(v)) => Value::Float(-v),
+        (Kind::BigIntConst, Value::BigInt(v)) => Value::BigInt(format!("-{v}")),
+        (Kind::DecimalConst, Value::Decimal(v)) => Value::Decimal(-v),
+        _ => return None,
+    };
+    let typ = match number.kind {
+        Kind::IntConst => "int64",
+        Kind::FloatConst => "float64",
+        Kind::BigIntConst => "bigint",
+        _ => "decimal",
+    };
+    Some((typ.into(), value, 2))
+}
+
+/// Array of literals of the same type at the start of tokens, i.e.
+/// `[1, -2, 3]`. Returns type, value and number of tokens.
+fn array_literal(tokens: &[Token]) -> Option<(String, Value, usize)> {
+    let mut elements = Vec::new();
+    let mut element_type = None;
+    let mut idx = 1;
+    loop {
+        let (typ, value, len) = negative_literal(&tokens[idx..]).or_else(|| {
+            let (typ, value) = literal(tokens.get(idx)?)?;
+            Some((typ.into(), value, 1))
+        })?;
+        if *element_type.get_or_insert_with(|| typ.clone()) != typ {
+            return None;
+        }
+        elements.push(value);
+        idx += len;
+
+        match tokens.get(idx)?.kind {
+            Kind::Comma => idx += 1,
+            Kind::CloseBracket => break,
+            _ => return None,
+        }
+    }
+    let typ = format!("array<{}>", element_type?);
+    Some((typ, Value::Array(elements), idx + 1))
+}
+
+fn is_operator(token: &Token) -> bool {
+    use crate::tokenizer::Kind::*;
+    match token.kind {
+        Assign | SubAssign | AddAssign | Arrow | Coalesce | Namespace | DoubleSplat
+        | BackwardLink | FloorDiv | Concat | GreaterEq | LessEq | NotEq | NotDistinctFrom
+        | DistinctFrom | Comma | OpenParen | CloseParen | OpenBracket | CloseBracket
+        | OpenBrace | CloseBrace | Dot | Semicolon | Colon | Add | Sub | Mul | Div | Modulo
+        | Pow | Less | Greater | Eq | Ampersand | Pipe | At => true,
+        DecimalConst | FloatConst | IntConst | BigIntConst | BinStr | Parameter
+        | ParameterAndType | Str | BacktickName | Keyword(_) | Ident | Substitution | EOF | EOI
+        | Epsilon | StartBlock | StartExtension | StartFragment | StartMigration
+        | StartSDLDocument | Whitespace | Comment | Unknown => false,
+    }
+}
+
+fn serialize_tokens(tokens: &[Token]) -> String {
+    use crate::tokenizer::Kind::Parameter;
+
+    let mut buf = String::new();
+    let mut needs_space = false;
+    for token in tokens {
+        if matches!(token.kind, Kind::EOF | Kind::EOI) {
+            break;
+        }
+
+        if needs_space && !is_operator(token) && token.kind != Parameter {
+            buf.push(' ');
+        }
+        buf.push_str(&token.text);
+        needs_space = !is_operator(token);
+    }
+    buf
+}
+
+fn scan_vars<'x, 'y: 'x, I>(tokens: I) -> Option<(bool, usize)>
+where
+    I: IntoIterator<Item = &'x Token<'x>>,
+{
+    let mut max_visited = None::<usize>;
+    let mut names = BTreeSet::new();
+    for t in tokens {
+        if t.kind == Kind::Parameter {
+            if let Ok(v) = t.text[1..].parse() {
+                if max_visited.map(|old| v > old).unwrap_or(true) {
+                    max_visited = Some(v);
+                }
+            } else {
+                names.insert(&t.text[..]);
+            }
+        }
+    }
+    if names.is_empty() {
+        let next = max_visited.map(|x| x.checked_add(1)).unwrap_or(Some(0))?;
+        Some((false, next))
+    } else if max_visited.is_some() {
+        None // mixed arguments
+    } else {
+        Some((true, names.len()))
+    }
+}
+
+fn hash(text: &str) -> [u8; 64] {
+    let mut result = [0u8; 64];
+    result.copy_from_slice(&Blake2b512::new_with_prefix(text.as_bytes()).finalize());
+    result
+}
+
+/// Produces tokens corresponding to (<lit typ>$var)
+fn arg_type_cast(typ: &str, var: String, span: Span) -> Token<'static> {
+    // the `lit` is required so these tokens have different text than an actual
+    // type cast and parameter, so their hashes don't clash.
+    Token {
+        kind: Kind::ParameterAndType,
+        text: format!("<lit {typ}>{var}").into(),
+        value: None,
+        span,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{is_literal_statement, scan_vars};
+    use crate::tokenizer::{Token, Tokenizer};
+
+    fn tokenize(s: &str) -> Vec<Token<'_>> {
+        let mut r = Vec::new();
+        let mut s = Tokenizer::new(s);
+        loop {
+            match s.next() {
+                Some(Ok(x)) => r.push(x),
+                None => break,
+                Some(Err(e)) => panic!("Parse error at {}: {}", s.current_pos(), e.message),
+            }
+        }
+        r
+    }
+
+    #[test]
+    fn none() {
+        assert_eq!(scan_vars(&tokenize("SELECT 1+1")).unwrap(), (false, 0));
+    }
+
+    #[test]
+    fn numeric() {
+        assert_eq!(scan_vars(&tokenize("$0 $1 $2")).unwrap(), (false, 3));
+        assert_eq!(scan_vars(&tokenize("$2 $3 $2")).unwrap(), (false, 4));
+        assert_eq!(scan_vars(&tokenize("$0 $0 $0")).unwrap(), (false, 1));
+        assert_eq!(scan_vars(&tokenize("$10 $100")).unwrap(), (false, 101));
+    }
+
+    #[test]
+    fn named() {
+        assert_eq!(scan_vars(&tokenize("$a")).unwrap(), (true, 1));
+        assert_eq!(scan_vars(&tokenize("$b $c $d")).unwrap(), (true, 3));
+        assert_eq!(scan_vars(&tokenize("$b $c $b")).unwrap(), (true, 2));
+        assert_eq!(
+            scan_vars(&tokenize("$a $b $b $a $c $xx")).unwrap(),
+            (true, 4)
+        );
+    }
+
+    #[test]
+    fn literal_statement() {
+        assert!(is_literal_statement(&tokenize("CREATE TYPE Foo")));
+        assert!(is_literal_statement(&tokenize("SET GLOBAL x := 1")));
+        assert!(is_literal_statement(&tokenize(
+            "WITH MODULE foo CREATE TYPE Bar { CREATE PROPERTY x -> str }"
+        )));
+        // keywords of DDL elsewhere in the statement don't count
+        assert!(!is_literal_statement(&tokenize(
+            "SELECT x FILTER .drop = 2 START 'a'"
+        )));
+        assert!(!is_literal_statement(&tokenize(
+            "WITH x := (CREATE) SELECT x"
+        )));
+        assert!(!is_literal_statement(&tokenize(
+            "UPDATE Foo SET { x := GLOBAL y }"
+        )));
+    }
+
+    #[test]
+    fn mixed() {
+        assert_eq!(scan_vars(&tokenize("$a $0")), None);
+        assert_eq!(scan_vars(&tokenize("$0 $a")), None);
+        assert_eq!(scan_vars(&tokenize("$b $c $100")), None);
+        assert_eq!(scan_vars(&tokenize("$10 $xx $yy")), None);
+    }
+}
@@ -0,0 +1,200 @@
+//! Positions of literals in the grammar, which decide whether a literal can
+//! be extracted into a query parameter during normalization.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::keywords::Keyword;
+use crate::parser::visit::{walk, Visitor, Walk};
+use crate::parser::{CSTNode, Context, Spec, Terminal};
+use crate::tokenizer::{Kind, Token};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LiteralContext {
+    /// `LIMIT 10`
+    Limit,
+    /// `OFFSET 10`
+    Offset,
+    /// Element of an unnamed tuple, i.e. `.0`. Is never extracted, since it
+    /// is not an expression.
+    TupleIndex,
+    /// Index or bounds of a slice, i.e. `x[1]` or `x[1:2]`
+    Index,
+    /// Operand of a type cast, i.e. `<int32>1`
+    Cast,
+    /// Value of an annotation
+    Annotation,
+}
+
+/// Productions in which a literal (optionally in parentheses) has the
+/// context. Non-terminals of clauses are inlined into the expression.
+const CONTEXTS: &[(&str, Option<&str>, LiteralContext)] = &[
+    ("LimitClause", None, LiteralContext::Limit),
+    ("OffsetClause", None, LiteralContext::Offset),
+    (
+        "PathStep",
+        Some("reduce_DOT_ICONST"),
+        LiteralContext::TupleIndex,
+    ),
+    ("IndirectionEl", None, LiteralContext::Index),
+    (
+        "Expr",
+        Some("reduce_LANGBRACKET_FullTypeExpr_RANGBRACKET_Expr"),
+        LiteralContext::Cast,
+    ),
+    (
+        "Expr",
+        Some("reduce_LANGBRACKET_OPTIONAL_FullTypeExpr_RANGBRACKET_Expr"),
+        LiteralContext::Cast,
+    ),
+    (
+        "Expr",
+        Some("reduce_LANGBRACKET_REQUIRED_FullTypeExpr_RANGBRACKET_Expr"),
+        LiteralContext::Cast,
+    ),
+    (
+        "AtomicExpr",
+        Some("reduce_LANGBRACKET_FullTypeExpr_RANGBRACKET_AtomicExpr"),
+        LiteralContext::Cast,
+    ),
+    (
+        "CreateAnnotationValueStmt",
+        None,
+        LiteralContext::Annotation,
+    ),
+    ("AlterAnnotationValueStmt", None, LiteralContext::Annotation),
+    ("SetAnnotation", None, LiteralContext::Annotation),
+];
+
+/// Which literals must stay in the query text.
+#[derive(Debug, Clone)]
+pub struct LiteralPolicy {
+    keep: HashSet<LiteralContext>,
+    keep_limit_one: bool,
+}
+
+impl Default for LiteralPolicy {
+    /// Keeps tuple indexes and `LIMIT 1`.
+    fn default() -> Self {
+        LiteralPolicy::new([])
+    }
+}
+
+impl LiteralPolicy {
+    /// Keeps literals in the contexts. Tuple indexes and `LIMIT 1` are kept
+    /// regardless.
+    pub fn new(keep: impl IntoIterator<Item = LiteralContext>) -> Self {
+        LiteralPolicy {
+            keep: keep.into_iter().collect(),
+            keep_limit_one: true,
+        }
+    }
+
+    /// Whether to keep `LIMIT 1` (which the planner optimizes specially),
+    /// when other limits are extracted.
+    pub fn with_limit_one(mut self, keep: bool) -> Self {
+        self.keep_limit_one = keep;
+        self
+    }
+
+    pub fn keeps(&self, context: LiteralContext, text: &str) -> bool {
+        match context {
+            LiteralContext::TupleIndex => true,
+            LiteralContext::Limit if self.keep_limit_one && text == "1" => true,
+            _ => self.keep.contains(&context),
+        }
+    }
+}
+
+/// Contexts of literals in the source, by their start offsets.
+///
+/// Is `None` when the source cannot be parsed.
+pub fn literal_contexts(source: &str, spec: &Spec) -> Option<HashMap<u64, LiteralContext>> {
+    let ctx = Context::new(spec);
+    let result = ctx.parse_block(source);
+    if result.has_errors() {
+        return None;
+    }
+    Some(find_contexts(result.cst.as_ref()?, spec))
+}
+
+/// Contexts of literals in the tree, by their start offsets.
+pub fn find_contexts(root: &CSTNode, spec: &Spec) -> HashMap<u64, LiteralContext> {
+    let mut finder = ContextFinder {
+        spec,
+        contexts: HashMap::new(),
+    };
+    walk(root, &mut finder);
+    finder.contexts
+}
+
+/// Context of a literal in a query that cannot be parsed, guessed from the
+/// preceding token.
+pub fn guess_context(prev: Option<&Token>) -> Option<LiteralContext> {
+    match prev?.kind {
+        Kind::Dot => Some(LiteralContext::TupleIndex),
+        Kind::Keyword(Keyword("limit")) => Some(LiteralContext::Limit),
+        Kind::Keyword(Keyword("offset")) => Some(LiteralContext::Offset),
+        _ => None,
+    }
+}
+
+struct ContextFinder<'s> {
+    spec: &'s Spec,
+    contexts: HashMap<u64, LiteralContext>,
+}
+
+impl<'a, 's> Visitor<'a> for ContextFinder<'s> {
+    fn enter(&mut self, node: &'a CSTNode<'a>, parents: &[&'a CSTNode<'a>]) -> Walk {
+        let Some(terminal) = node.as_terminal() else {
+            return Walk::Continue;
+        };
+        if !is_literal(terminal) {
+            return Walk::Continue;
+        }
+
+        // climb up while ancestors contain nothing but the literal
+        for parent in parents.iter().rev() {
+            let context = CONTEXTS.iter().find(|(non_term, production, _)| {
+                self.spec.is_production(parent, non_term, *production)
+            });
+            if let Some((_, _, context)) = context {
+                self.contexts.insert(terminal.span.start, *context);
+                break;
+            }
+            if !is_wrapper(parent, terminal) {
+                break;
+            }
+        }
+        Walk::Continue
+    }
+}
+
+fn is_literal(terminal: &Terminal) -> bool {
+    matches!(
+        terminal.kind,
+        Kind::IntConst
+            | Kind::FloatConst
+            | Kind::BigIntConst
+            | Kind::DecimalConst
+            | Kind::Str
+            | Kind::BinStr
+            | Kind::Keyword(Keyword("true" | "false"))
+    )
+}
+
+/// Checks if the node contains only the literal, in parentheses or with
+/// an unary sign.
+fn is_wrapper(node: &CSTNode, literal: &Terminal) -> bool {
+    match node {
+        CSTNode::Empty => true,
+        CSTNode::Terminal(t) => {
+            std::ptr::eq(*t, literal)
+                || matches!(
+                    t.kind,
+                    Kind::OpenParen | Kind::CloseParen | Kind::Add | Kind::Sub
+                )
+        }
+        CSTNode::Production(_) => node.children().iter().all(|c| is_wrapper(c, literal)),
+        CSTNode::Missing(_) | CSTNode::Skipped(_) => false,
+    }
+}
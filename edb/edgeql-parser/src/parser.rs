@@ -1,15 +1,35 @@
+mod complete;
 mod custom_errors;
+#[cfg(feature = "embedded-spec")]
+mod embedded;
+mod entry;
+pub mod lower;
+mod owned;
+mod recovery;
+pub mod trace;
+pub mod visit;
 
 use append_only_vec::AppendOnlyVec;
 use indexmap::IndexMap;
 
+use crate::diagnostics::{ErrorCode, Suggestion};
 use crate::helpers::quote_name;
-use crate::keywords::{self, Keyword};
+use crate::keywords::Keyword;
 use crate::position::Span;
 use crate::tokenizer::{Error, Kind, Token, Value};
 
+pub use complete::Completion;
+#[cfg(feature = "embedded-spec")]
+pub use embedded::{parse_block, parse_sdl, spec};
+pub use entry::{with_start_token, ParseResult};
+pub use owned::{OwnedCSTNode, OwnedProduction, OwnedSkipped};
+pub use recovery::RecoveryConfig;
+pub use trace::{Event, EventLog, Tracer};
+
 pub struct Context<'s> {
     spec: &'s Spec,
+    recovery: RecoveryConfig,
+    tracer: Option<&'s dyn Tracer>,
     arena: bumpalo::Bump,
     terminal_arena: AppendOnlyVec<Terminal>,
 }
@@ -18,17 +38,82 @@ impl<'s> Context<'s> {
     pub fn new(spec: &'s Spec) -> Self {
         Context {
             spec,
+            recovery: RecoveryConfig::default(),
+            tracer: None,
             arena: bumpalo::Bump::new(),
             terminal_arena: AppendOnlyVec::new(),
         }
     }
+
+    /// Sets how the parser recovers from errors.
+    pub fn with_recovery(mut self, recovery: RecoveryConfig) -> Self {
+        self.recovery = recovery;
+        self
+    }
+
+    /// Reports actions of the parser to the tracer.
+    pub fn with_tracer(mut self, tracer: &'s dyn Tracer) -> Self {
+        self.tracer = Some(tracer);
+        self
+    }
 }
 
 /// This is a const just so we remember to update it everywhere
 /// when changing.
 const UNEXPECTED: &str = "Unexpected";
 
-pub fn parse<'a>(input: &'a [Terminal], ctx: &'a Context) -> (Option<&'a CSTNode<'a>>, Vec<Error>) {
+pub fn parse<'a>(
+    input: impl IntoIterator<Item = &'a Terminal>,
+    ctx: &'a Context,
+) -> (Option<&'a CSTNode<'a>>, Vec<Error>) {
+    // append EIO
+    let input: Vec<_> = input.into_iter().collect();
+    let end = input.last().map(|t| t.span.end).unwrap_or_default();
+    let eoi = ctx.alloc_terminal(Terminal {
+        kind: Kind::EOI,
+        span: Span { start: end, end },
+        text: "".to_string(),
+        value: None,
+        is_placeholder: false,
+    });
+    let input = input.into_iter().chain(Some(eoi));
+
+    let parsers = match run(input, ctx) {
+        Ok(parsers) => parsers,
+        Err(error) => return (None, custom_errors::post_process(vec![error])),
+    };
+
+    // there will always be a parser left,
+    // since we always allow a token to be skipped
+    let mut parser = parsers.into_iter().min_by_key(|p| p.error_cost).unwrap();
+
+    // with custom recovery costs, even the end of input might be skipped
+    let accepted = matches!(
+        parser.stack_top.value.shifted_terminal(),
+        Some(Terminal {
+            kind: Kind::EOI,
+            ..
+        })
+    );
+    if accepted {
+        parser.finish();
+    }
+
+    let node = if parser.can_recover && accepted {
+        Some(&parser.stack_top.value)
+    } else {
+        None
+    };
+    let errors = custom_errors::post_process(parser.errors);
+    (node, errors)
+}
+
+/// Feeds the input to the parser, recovering from errors. Returns the
+/// parsers that are left, or the first error if recovery is disabled.
+fn run<'a>(
+    input: impl IntoIterator<Item = &'a Terminal>,
+    ctx: &'a Context,
+) -> Result<Vec<Parser<'a>>, Error> {
     let stack_top = ctx.arena.alloc(StackNode {
         parent: None,
         state: 0,
@@ -41,25 +126,16 @@ pub fn parse<'a>(input: &'a [Terminal], ctx: &'a Context) -> (Option<&'a CSTNode
         can_recover: true,
         errors: Vec::new(),
         has_custom_error: false,
+        skipped: Vec::new(),
     };
 
-    // append EIO
-    let end = input.last().map(|t| t.span.end).unwrap_or_default();
-    let eoi = ctx.alloc_terminal(Terminal {
-        kind: Kind::EOI,
-        span: Span { start: end, end },
-        text: "".to_string(),
-        value: None,
-        is_placeholder: false,
-    });
-    let input = input.iter().chain(Some(eoi));
-
     let mut parsers = vec![initial_track];
     let mut prev_span: Option<Span> = None;
     let mut new_parsers = Vec::with_capacity(parsers.len() + 5);
+    let recovery = &ctx.recovery;
 
     for token in input {
-        // println!("token {:?}", token);
+        ctx.trace(Event::Token { token });
         while let Some(mut parser) = parsers.pop() {
             let res = parser.act(ctx, token);
 
@@ -67,6 +143,17 @@ pub fn parse<'a>(input: &'a [Terminal], ctx: &'a Context) -> (Option<&'a CSTNode
                 // base case: ok
                 parser.node_successful();
                 new_parsers.push(parser);
+                continue;
+            }
+
+            let state = parser.stack_top.state;
+            ctx.trace(Event::Unexpected { token, state });
+            if !recovery.enabled {
+                // error: report it and stop
+                return Err(match parser.custom_error(ctx, token) {
+                    Some(error) => error.default_span_to(token.span),
+                    None => unexpected_token_error(token),
+                });
             } else {
                 // error: try to recover
 
@@ -80,24 +167,28 @@ pub fn parse<'a>(input: &'a [Terminal], ctx: &'a Context) -> (Option<&'a CSTNode
                 };
 
                 // option 1: inject a token
-                if parser.error_cost <= ERROR_COST_INJECT_MAX {
+                if parser.error_cost <= recovery.max_inject_cost {
                     let possible_actions = &ctx.spec.actions[parser.stack_top.state];
                     for token_kind in possible_actions.keys() {
                         let mut inject = parser.clone();
 
-                        let injection = new_token_for_injection(*token_kind, ctx);
+                        let injection = new_token_for_injection(*token_kind, gap_span, ctx);
 
-                        let cost = injection_cost(token_kind);
-                        let error = Error::new(format!("Missing {injection}")).with_span(gap_span);
+                        let cost = recovery.injection_cost(token_kind);
+                        let error = missing_token_error(&inject, injection, gap_span);
                         inject.push_error(error, cost);
 
-                        if inject.error_cost <= ERROR_COST_INJECT_MAX {
-                            if inject.act(ctx, injection).is_ok() {
-                                // println!("   --> [inject {injection}]");
-
-                                // insert into parsers, to retry the original token
-                                parsers.push(inject);
-                            }
+                        if inject.error_cost <= recovery.max_inject_cost
+                            && inject.act(ctx, injection).is_ok()
+                        {
+                            let cost = inject.error_cost;
+                            ctx.trace(Event::Inject {
+                                token: injection,
+                                cost,
+                            });
+
+                            // insert into parsers, to retry the original token
+                            parsers.push(inject);
                         }
                     }
                 }
@@ -107,34 +198,42 @@ pub fn parse<'a>(input: &'a [Terminal], ctx: &'a Context) -> (Option<&'a CSTNode
                 //   error, not during all the steps of recovery.
                 if parser.error_cost == 0 {
                     if let Some(error) = parser.custom_error(ctx, token) {
-                        parser
-                            .push_error(error.default_span_to(token.span), ERROR_COST_CUSTOM_ERROR);
+                        let error = error.default_span_to(token.span);
+                        let cost = parser.error_cost + recovery.custom_error_cost;
+                        ctx.trace(Event::CustomError {
+                            error: &error,
+                            cost,
+                        });
+
+                        parser.push_error(error, recovery.custom_error_cost);
                         parser.has_custom_error = true;
-
-                        // println!("   --> [custom error]");
+                        parser.skipped.push(token);
                         new_parsers.push(parser);
                         continue;
                     }
                 } else if parser.has_custom_error {
                     // when there is a custom error, just skip the tokens until
                     // the parser recovers
-                    // println!("   --> [skip because of custom error]");
+                    let cost = parser.error_cost;
+                    ctx.trace(Event::Skip { token, cost });
+                    parser.skipped.push(token);
                     new_parsers.push(parser);
                     continue;
                 }
 
                 // option 3: skip the token
                 let mut skip = parser;
-                let error = Error::new(format!("{UNEXPECTED} {token}")).with_span(token.span);
-                skip.push_error(error, ERROR_COST_SKIP);
+                skip.push_error(unexpected_token_error(token), recovery.skip_cost);
+                skip.skipped.push(token);
                 if token.kind == Kind::EOF || token.kind == Kind::Semicolon {
                     // extra penalty
-                    skip.error_cost += ERROR_COST_INJECT_MAX;
+                    skip.error_cost += recovery.max_inject_cost;
                     skip.can_recover = false;
                 }
 
                 // insert into new_parsers, so the token is skipped
-                // println!("   --> [skip] {}", skip.error_cost);
+                let cost = skip.error_cost;
+                ctx.trace(Event::Skip { token, cost });
                 new_parsers.push(skip);
             }
         }
@@ -154,42 +253,42 @@ pub fn parse<'a>(input: &'a [Terminal], ctx: &'a Context) -> (Option<&'a CSTNode
                     recovered.error_cost = 0;
                     recovered.has_custom_error = false;
 
+                    let discarded = new_parsers.len();
+                    ctx.trace(Event::Recover { discarded });
                     new_parsers.clear();
                     new_parsers.push(recovered);
                 }
             }
         }
 
-        // prune: pick only 1 best parsers that has cost > ERROR_COST_INJECT_MAX
-        if new_parsers[0].error_cost > ERROR_COST_INJECT_MAX {
-            new_parsers.drain(1..);
+        // prune: pick only 1 best parsers that has cost > max_inject_cost
+        let mut kept = new_parsers.len();
+        if new_parsers[0].error_cost > recovery.max_inject_cost {
+            kept = 1;
         }
 
         // prune: pick only X best parsers
-        if new_parsers.len() > PARSER_COUNT_MAX {
-            new_parsers.drain(PARSER_COUNT_MAX..);
+        kept = kept.min(recovery.max_parsers.max(1));
+        if new_parsers.len() > kept {
+            let discarded = new_parsers.len() - kept;
+            ctx.trace(Event::Prune { kept, discarded });
+            new_parsers.drain(kept..);
         }
 
         assert!(parsers.is_empty());
         std::mem::swap(&mut parsers, &mut new_parsers);
         prev_span = Some(token.span);
     }
-
-    // there will always be a parser left,
-    // since we always allow a token to be skipped
-    let mut parser = parsers.into_iter().min_by_key(|p| p.error_cost).unwrap();
-    parser.finish();
-
-    let node = if parser.can_recover {
-        Some(&parser.stack_top.value)
-    } else {
-        None
-    };
-    let errors = custom_errors::post_process(parser.errors);
-    (node, errors)
+    Ok(parsers)
 }
 
 impl<'s> Context<'s> {
+    fn trace(&self, event: Event) {
+        if let Some(tracer) = self.tracer {
+            tracer.trace(&event);
+        }
+    }
+
     fn alloc_terminal(&self, t: Terminal) -> &'_ Terminal {
         let idx = self.terminal_arena.push(t);
         &self.terminal_arena[idx]
@@ -206,7 +305,36 @@ impl<'s> Context<'s> {
     }
 }
 
-fn new_token_for_injection<'a>(kind: Kind, ctx: &'a Context) -> &'a Terminal {
+fn unexpected_token_error(token: &Terminal) -> Error {
+    Error::new(format!("{UNEXPECTED} {token}"))
+        .with_span(token.span)
+        .with_code(ErrorCode::UnexpectedToken)
+        .with_suggestion(Suggestion::remove(token.span))
+}
+
+fn missing_token_error(parser: &Parser, injection: &Terminal, span: Span) -> Error {
+    let mut error = Error::new(format!("Missing {injection}"))
+        .with_span(span)
+        .with_code(ErrorCode::MissingToken);
+
+    let text = match injection.kind {
+        Kind::Keyword(Keyword(kw)) => Some(kw.to_ascii_uppercase()),
+        Kind::Ident | Kind::EOF | Kind::EOI => None,
+        kind => kind.text().map(String::from),
+    };
+    if let Some(text) = text {
+        error = error.with_suggestion(Suggestion::insert(span.start, text));
+    }
+
+    if let Some(open) = parser.unclosed_bracket(injection.kind) {
+        error = error.with_label(open.span, format!("unclosed '{}'", open.text));
+    }
+    error
+}
+
+/// Token injected by error recovery. It is placed at the start of the gap
+/// between the previous and the current token.
+fn new_token_for_injection<'a>(kind: Kind, gap: Span, ctx: &'a Context) -> &'a Terminal {
     let (text, value) = match kind {
         Kind::Keyword(Keyword(kw)) => (kind.text(), Some(Value::String(kw.to_string()))),
         Kind::Ident => {
@@ -220,7 +348,10 @@ fn new_token_for_injection<'a>(kind: Kind, ctx: &'a Context) -> &'a Terminal {
         kind,
         text: text.unwrap_or_default().to_string(),
         value,
-        span: Span::default(),
+        span: Span {
+            start: gap.start,
+            end: gap.start,
+        },
         is_placeholder: true,
     })
 }
@@ -264,8 +395,17 @@ pub enum CSTNode<'a> {
     Empty,
     Terminal(&'a Terminal),
     Production(Production<'a>),
+
+    /// A token that is missing from the source, injected by error recovery.
+    /// Its span is empty.
+    Missing(&'a Terminal),
+
+    /// Tokens that were skipped by error recovery, in front of the node that
+    /// was parsed after them.
+    Skipped(&'a Skipped<'a>),
 }
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Terminal {
     pub kind: Kind,
     pub text: String,
@@ -284,6 +424,14 @@ pub struct Production<'a> {
     pub inlined_ids: Option<&'a [usize]>,
 }
 
+#[derive(Debug, Clone, Copy)]
+pub struct Skipped<'a> {
+    pub tokens: &'a [&'a Terminal],
+
+    /// A [CSTNode::Terminal] or a [CSTNode::Missing].
+    pub node: CSTNode<'a>,
+}
+
 struct StackNode<'p> {
     parent: Option<&'p StackNode<'p>>,
 
@@ -309,13 +457,14 @@ struct Parser<'s> {
     /// A flag that is used to make the parser prefer custom errors over other
     /// recovery paths
     has_custom_error: bool,
+
+    /// tokens skipped since the last shift, they are attached to the next
+    /// shifted terminal
+    skipped: Vec<&'s Terminal>,
 }
 
 impl<'s> Parser<'s> {
     fn act(&mut self, ctx: &'s Context, token: &'s Terminal) -> Result<(), ()> {
-        // self.print_stack();
-        // println!("INPUT: {}", token.text);
-
         loop {
             // find next action
             let Some(action) = ctx.spec.actions[self.stack_top.state].get(&token.kind) else {
@@ -324,10 +473,24 @@ impl<'s> Parser<'s> {
 
             match action {
                 Action::Shift(next) => {
-                    // println!("   --> [shift {next}]");
+                    ctx.trace(Event::Shift {
+                        token,
+                        state: *next,
+                    });
+
+                    let mut node = if token.is_placeholder {
+                        CSTNode::Missing(token)
+                    } else {
+                        CSTNode::Terminal(token)
+                    };
+                    if !self.skipped.is_empty() {
+                        let tokens = ctx.arena.alloc_slice_copy(&self.skipped);
+                        self.skipped.clear();
+                        node = CSTNode::Skipped(ctx.arena.alloc(Skipped { tokens, node }));
+                    }
 
                     // push on stack
-                    self.push_on_stack(ctx, *next, CSTNode::Terminal(token));
+                    self.push_on_stack(ctx, *next, node);
                     return Ok(());
                 }
                 Action::Reduce(reduce) => {
@@ -354,6 +517,15 @@ impl<'s> Parser<'s> {
         let nstate = self.stack_top.state;
 
         let next = *ctx.spec.goto[nstate].get(&reduce.non_term).unwrap();
+        if ctx.tracer.is_some() {
+            let (non_term, production) = ctx.spec.production_name(reduce.production_id);
+            ctx.trace(Event::Reduce {
+                non_term,
+                production,
+                args: reduce.cnt,
+                state: next,
+            });
+        }
 
         // inline (if there is an inlining rule)
         let mut value = value;
@@ -365,6 +537,14 @@ impl<'s> Parser<'s> {
                 let span = get_span_of_nodes(args);
 
                 value = args[*inline_position as usize];
+                if ctx.tracer.is_some() {
+                    let (non_term, production) = ctx.spec.production_name(inlined_id);
+                    ctx.trace(Event::Inline {
+                        non_term,
+                        production,
+                        position: *inline_position,
+                    });
+                }
 
                 // save inlined id
                 if let CSTNode::Production(new_prod) = &mut value {
@@ -380,12 +560,6 @@ impl<'s> Parser<'s> {
         }
 
         self.push_on_stack(ctx, next, value);
-
-        // println!(
-        //     "   --> [reduce {} ::= ({} popped) at {}/{}]",
-        //     production, cnt, state, nstate
-        // );
-        // self.print_stack();
     }
 
     pub fn push_on_stack(&mut self, ctx: &'s Context, state: usize, value: CSTNode<'s>) {
@@ -399,17 +573,14 @@ impl<'s> Parser<'s> {
 
     pub fn finish(&mut self) {
         debug_assert!(matches!(
-            &self.stack_top.value,
-            CSTNode::Terminal(Terminal {
+            self.stack_top.value.shifted_terminal(),
+            Some(Terminal {
                 kind: Kind::EOI,
                 ..
             })
         ));
         self.stack_top = self.stack_top.parent.unwrap();
 
-        // self.print_stack();
-        // println!("   --> accept");
-
         #[cfg(debug_assertions)]
         {
             let first = self.stack_top.parent.unwrap();
@@ -421,38 +592,32 @@ impl<'s> Parser<'s> {
         }
     }
 
-    #[cfg(never)]
-    fn print_stack(&self, ctx: &'s Context) {
-        let prefix = "STACK: ";
+    /// Finds the opening bracket on the stack, which would be closed by
+    /// a closing bracket of the given kind.
+    fn unclosed_bracket(&self, closing: Kind) -> Option<&'s Terminal> {
+        let opening = match closing {
+            Kind::CloseParen => Kind::OpenParen,
+            Kind::CloseBracket => Kind::OpenBracket,
+            Kind::CloseBrace => Kind::OpenBrace,
+            _ => return None,
+        };
 
-        let mut stack = Vec::new();
+        let mut depth = 0;
         let mut node = Some(self.stack_top);
         while let Some(n) = node {
-            stack.push(n);
-            node = n.parent.clone();
-        }
-        stack.reverse();
-
-        let names = stack
-            .iter()
-            .map(|s| match s.value {
-                CSTNode::Empty => format!("Empty"),
-                CSTNode::Terminal(term) => format!("{term}"),
-                CSTNode::Production(prod) => {
-                    let prod_name = &ctx.spec.production_names[prod.id];
-                    format!("{}.{}", prod_name.0, prod_name.1)
+            if let Some(t) = n.value.shifted_terminal() {
+                if t.kind == closing {
+                    depth += 1;
+                } else if t.kind == opening {
+                    if depth == 0 {
+                        return Some(t);
+                    }
+                    depth -= 1;
                 }
-            })
-            .collect::<Vec<_>>();
-
-        let mut states = format!("{:5}", ' ');
-        for (index, node) in stack.iter().enumerate() {
-            let name_width = names[index].chars().count();
-            states += &format!("  {:<width$}", node.state, width = name_width);
+            }
+            node = n.parent;
         }
-
-        println!("{}{}", prefix, names.join("  "));
-        println!("{}", states);
+        None
     }
 
     fn push_error(&mut self, error: Error, cost: u16) {
@@ -511,14 +676,16 @@ impl<'a> StackNode<'a> {
 
 fn get_span_of_nodes(args: &[CSTNode]) -> Option<Span> {
     let start = args.iter().find_map(|x| match x {
-        CSTNode::Terminal(t) => Some(t.span.start),
+        CSTNode::Terminal(t) | CSTNode::Missing(t) => Some(t.span.start),
         CSTNode::Production(p) => get_span_of_nodes(p.args).map(|x| x.start),
-        _ => None,
+        CSTNode::Skipped(s) => s.tokens.first().map(|t| t.span.start),
+        CSTNode::Empty => None,
     })?;
     let end = args.iter().rev().find_map(|x| match x {
-        CSTNode::Terminal(t) => Some(t.span.end),
+        CSTNode::Terminal(t) | CSTNode::Missing(t) => Some(t.span.end),
         CSTNode::Production(p) => get_span_of_nodes(p.args).map(|x| x.end),
-        _ => None,
+        CSTNode::Skipped(s) => get_span_of_nodes(std::slice::from_ref(&s.node)).map(|x| x.end),
+        CSTNode::Empty => None,
     })?;
     Some(Span { start, end })
 }
@@ -543,46 +710,6 @@ fn extend_span<'a>(value: &mut CSTNode<'a>, span: Option<Span>, ctx: &'a Context
     *terminal = ctx.alloc_terminal(new_term);
 }
 
-const PARSER_COUNT_MAX: usize = 10;
-
-const ERROR_COST_INJECT_MAX: u16 = 15;
-const ERROR_COST_SKIP: u16 = 3;
-const ERROR_COST_CUSTOM_ERROR: u16 = 3;
-
-fn injection_cost(kind: &Kind) -> u16 {
-    use Kind::*;
-
-    match kind {
-        Ident => 10,
-        Substitution => 8,
-
-        // Manual keyword tweaks to encourage some error messages and discourage others.
-        Keyword(keywords::Keyword(
-            "delete" | "update" | "migration" | "role" | "global" | "administer" | "future" | "database",
-        )) => 100,
-        Keyword(keywords::Keyword("insert" | "module" | "extension" | "branch")) => 20,
-        Keyword(keywords::Keyword("select" | "property" | "type")) => 10,
-        Keyword(_) => 15,
-
-        Dot => 5,
-        OpenBrace | OpenBracket => 5,
-        OpenParen => 4,
-
-        CloseBrace | CloseBracket | CloseParen => 1,
-
-        Namespace => 10,
-        Comma | Colon | Semicolon => 2,
-        Eq => 5,
-
-        At => 6,
-        IntConst => 8,
-
-        Assign | Arrow => 5,
-
-        _ => 100, // forbidden
-    }
-}
-
 impl std::fmt::Display for Terminal {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         if (self.is_placeholder && self.kind == Kind::Ident) || self.text.is_empty() {
@@ -610,8 +737,12 @@ impl Terminal {
 
     #[cfg(feature = "serde")]
     pub fn from_start_name(start_name: &str) -> Self {
+        Self::from_start_kind(get_token_kind(start_name))
+    }
+
+    pub fn from_start_kind(kind: Kind) -> Self {
         Terminal {
-            kind: get_token_kind(start_name),
+            kind,
             text: "".to_string(),
             value: None,
             span: Default::default(),
@@ -0,0 +1,156 @@
+//! Completion of partially typed source: tokens that can follow the text
+//! before the cursor, for example keywords to suggest in an editor.
+
+use crate::keywords::Keyword;
+use crate::position::Span;
+use crate::tokenizer::Kind;
+
+use super::visit::{walk, Visitor, Walk};
+use super::{new_token_for_injection, parse, run, CSTNode, Context, Terminal};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Completion<'s> {
+    /// Tokens that can follow the text before the cursor. A class of tokens
+    /// is represented by a single kind, i.e. [Kind::Ident] for any name or
+    /// [Kind::Str] for any string.
+    pub expected: Vec<Kind>,
+
+    /// The innermost non-terminal that contains the cursor, i.e. `Shape`
+    /// within `{ ... }`.
+    pub non_term: Option<&'s str>,
+
+    /// The word that ends at the cursor. It is being typed, so it is not
+    /// taken into account and can be replaced by the completion.
+    pub word: Option<Span>,
+}
+
+impl<'s> Completion<'s> {
+    /// Expected keywords, lowercase.
+    pub fn keywords(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.expected.iter().filter_map(|kind| match kind {
+            Kind::Keyword(Keyword(kw)) => Some(*kw),
+            _ => None,
+        })
+    }
+}
+
+impl<'s> Context<'s> {
+    /// Completes a block of statements at the byte offset.
+    pub fn complete_block(&self, source: &str, offset: u64) -> Completion<'s> {
+        self.complete(Kind::StartBlock, source, offset)
+    }
+
+    /// Completes an SDL document at the byte offset.
+    pub fn complete_sdl_document(&self, source: &str, offset: u64) -> Completion<'s> {
+        self.complete(Kind::StartSDLDocument, source, offset)
+    }
+
+    /// Completes the source, parsed from the start token, at the byte offset.
+    ///
+    /// Text after the offset is ignored. Errors before the offset are
+    /// recovered from, as when parsing.
+    pub fn complete(&self, start: Kind, source: &str, offset: u64) -> Completion<'s> {
+        let mut end = (offset as usize).min(source.len());
+        while !source.is_char_boundary(end) {
+            end -= 1;
+        }
+        let (mut input, _) = self.tokenize(start, &source[..end]);
+
+        // the last token before EOF
+        let mut word = None;
+        if let [.., last, _eof] = input[..] {
+            let is_word = matches!(last.kind, Kind::Ident | Kind::Keyword(_));
+            if is_word && last.span.end as usize == end {
+                word = Some(last.span);
+                input.remove(input.len() - 2);
+            }
+        }
+
+        let eof = input.pop();
+        let expected = self.expected_tokens(input.iter().copied());
+
+        let cursor = input.last().map_or(0, |t| t.span.end);
+        let (cst, _) = parse(input.into_iter().chain(eof), self);
+        let non_term = cst.and_then(|cst| self.enclosing_non_term(cst, cursor));
+
+        Completion {
+            expected,
+            non_term,
+            word,
+        }
+    }
+
+    fn expected_tokens<'a>(&'a self, input: impl IntoIterator<Item = &'a Terminal>) -> Vec<Kind> {
+        let Ok(parsers) = run(input, self) else {
+            return Vec::new();
+        };
+        let Some(parser) = parsers.into_iter().min_by_key(|p| p.error_cost) else {
+            return Vec::new();
+        };
+
+        // Reduce actions of LALR states might be taken on tokens which then
+        // cannot be shifted, so each token is tried.
+        let actions = &self.spec.actions[parser.stack_top.state];
+        (actions.keys())
+            .filter(|kind| !matches!(kind, Kind::EOF | Kind::EOI))
+            .filter(|kind| {
+                let token = new_token_for_injection(**kind, Span::default(), self);
+                parser.clone().act(self, token).is_ok()
+            })
+            .copied()
+            .collect()
+    }
+
+    /// Finds the innermost production that contains both text before the
+    /// cursor and the token that would follow it, or the root. Tokens after
+    /// the cursor were injected by error recovery or are the EOF.
+    fn enclosing_non_term<'a>(&self, cst: &'a CSTNode<'a>, cursor: u64) -> Option<&'s str> {
+        struct Finder<'a> {
+            cursor: u64,
+            parents: Option<Vec<&'a CSTNode<'a>>>,
+        }
+
+        impl<'a> Visitor<'a> for Finder<'a> {
+            fn enter(&mut self, node: &'a CSTNode<'a>, parents: &[&'a CSTNode<'a>]) -> Walk {
+                if self.parents.is_some() {
+                    return Walk::SkipChildren;
+                }
+                let is_next = match node {
+                    CSTNode::Missing(t) => t.span.start >= self.cursor,
+                    CSTNode::Terminal(t) => t.kind == Kind::EOF,
+                    _ => false,
+                };
+                if is_next {
+                    self.parents = Some(parents.to_vec());
+                }
+                Walk::Continue
+            }
+        }
+
+        let mut finder = Finder {
+            cursor,
+            parents: None,
+        };
+        walk(cst, &mut finder);
+
+        // the root contains the cursor even when there is no text
+        let parents = finder.parents?;
+        let node = (parents.iter().rev())
+            .find(|n| has_source_text(n))
+            .or(parents.first())?;
+        match node {
+            CSTNode::Production(p) => Some(self.spec.production_name(p.id).0),
+            _ => None,
+        }
+    }
+}
+
+/// Checks if the node contains a terminal from the source, other than
+/// start tokens and EOF.
+fn has_source_text(node: &CSTNode) -> bool {
+    match node {
+        CSTNode::Terminal(t) => !t.text.is_empty(),
+        CSTNode::Production(_) | CSTNode::Skipped(_) => node.children().iter().any(has_source_text),
+        CSTNode::Empty | CSTNode::Missing(_) => false,
+    }
+}
@@ -1,3 +1,5 @@
+use crate::diagnostics::{ErrorCode, Suggestion};
+use crate::helpers::quote_name;
 use crate::tokenizer::Kind;
 use crate::{keywords::Keyword, position::Span};
 
@@ -7,19 +9,18 @@ impl<'s> Parser<'s> {
     pub(super) fn custom_error(&self, ctx: &Context, token: &Terminal) -> Option<Error> {
         let ltok = self.get_from_top(0).unwrap();
 
-        if let Some(value) = self.custom_error_from_rule(token, ctx) {
+        // Look at the parsing stack and use tokens and
+        // non-terminals to infer the parser rule when the
+        // error occurred.
+        let rule = self.get_rule(ctx);
+
+        if let Some(value) = self.custom_error_from_rule(token, ctx, rule) {
             return Some(value);
         }
 
-        if matches!(token.kind, Kind::Keyword(Keyword("explain"))) {
-            return Some({
-                Error {
-                    message: format!("Unexpected keyword '{}'", token.text.to_uppercase()),
-                    span: Span::default(),
-                    hint: Some("Use `analyze` to show query performance details".to_string()),
-                    details: None,
-                }
-            });
+        let rule = rule.map(|(_, rule)| rule);
+        if let Some(mistake) = MISTAKES.iter().find(|m| m.check(self, token, rule, ctx)) {
+            return Some(mistake.to_error(self, token));
         }
 
         if let Kind::Keyword(kw) = token.kind {
@@ -33,19 +34,20 @@ impl<'s> Parser<'s> {
         None
     }
 
-    fn custom_error_from_rule(&self, token: &Terminal, ctx: &Context) -> Option<Error> {
+    fn custom_error_from_rule(
+        &self,
+        token: &Terminal,
+        ctx: &Context,
+        rule: Option<(usize, ParserRule)>,
+    ) -> Option<Error> {
         let last = self.get_from_top(0).unwrap();
 
-        let (i, rule) = self.get_rule(ctx)?;
-        // Look at the parsing stack and use tokens and
-        // non-terminals to infer the parser rule when the
-        // error occurred.
-
+        let (i, rule) = rule?;
         match rule {
             ParserRule::ListOfArguments
                 // The stack is like <NodeName> LPAREN <AnyIdentifier>
                 if i == 1
-                    && Cond::AnyOf(vec![
+                    && Cond::AnyOf(&[
                         Cond::Production("AnyIdentifier"),
                         Cond::keyword("with"),
                         Cond::keyword("select"),
@@ -56,13 +58,12 @@ impl<'s> Parser<'s> {
                     ])
                     .check(last, ctx)
             => {
-                return Some(Error {
-                    message: "Missing parentheses around statement used as an expression"
-                        .to_string(),
-                    span: super::get_span_of_nodes(&[last.value]).unwrap_or_default(),
-                    hint: None,
-                    details: None,
-                });
+                let span = super::get_span_of_nodes(&[last.value]).unwrap_or_default();
+                return Some(
+                    Error::new("Missing parentheses around statement used as an expression")
+                        .with_span(span)
+                        .with_code(ErrorCode::MissingParentheses),
+                );
             }
 
             ParserRule::ArraySlice
@@ -71,23 +72,14 @@ impl<'s> Parser<'s> {
             => {
                 // The offending token was something that could
                 // make an expression
-                return Some(Error::new(format!(
-                    "It appears that a ':' is missing in {rule} before {}",
-                    token.text
-                )));
-            },
-
-            ParserRule::Definition if token.kind == Kind::Ident => {
-                // Something went wrong in a definition, so check
-                // if the last successful token is a keyword.
-                if Cond::Production("Identifier").check(last, ctx)
-                // TODO: && ltok.value.upper() == "INDEX"
-                {
-                    return Some(Error::new(format!(
-                        "Expected 'ON', but got '{}' instead",
+                return Some(
+                    Error::new(format!(
+                        "It appears that a ':' is missing in {rule} before {}",
                         token.text
-                    )));
-                }
+                    ))
+                    .with_code(ErrorCode::MissingToken)
+                    .with_suggestion(Suggestion::insert(token.span.start, ":")),
+                );
             },
 
             ParserRule::ForIterator => {
@@ -98,13 +90,14 @@ impl<'s> Parser<'s> {
                 } else {
                     token.span
                 };
-                return Some(Error {
-                    message: "Missing parentheses around complex expression in \
-                              a FOR iterator clause".to_string(),
-                    span,
-                    hint: None,
-                    details: None,
-                });
+                return Some(
+                    Error::new(
+                        "Missing parentheses around complex expression in \
+                         a FOR iterator clause",
+                    )
+                    .with_span(span)
+                    .with_code(ErrorCode::MissingParentheses),
+                );
             },
 
             _ => {}
@@ -119,7 +112,7 @@ impl<'s> Parser<'s> {
         // so we need to find a match for it before deciding what rule
         // context we're in.
         let mut need_match = self.compare_stack(
-            &[Cond::AnyOf(vec![
+            &[Cond::AnyOf(&[
                 Cond::Terminal(Kind::CloseBrace),
                 Cond::Terminal(Kind::CloseParen),
                 Cond::Terminal(Kind::CloseBracket),
@@ -138,8 +131,8 @@ impl<'s> Parser<'s> {
             // We'll need the element right before "{", "[", or "(".
             let prevel = el.parent;
 
-            match el.value {
-                CSTNode::Terminal(Terminal {
+            match el.value.shifted_terminal() {
+                Some(Terminal {
                     kind: Kind::OpenBrace,
                     ..
                 }) => {
@@ -152,6 +145,7 @@ impl<'s> Parser<'s> {
                         return Some((i, ParserRule::Definition));
                     } else if prevel.map_or(false, |prevel| {
                         Cond::Production("Expr").check(prevel, ctx)
+                            || Cond::keyword("set").check(prevel, ctx)
                             || (Cond::Terminal(Kind::Colon).check(prevel, ctx)
                                 && Cond::Production("ShapePointer").check_opt(prevel.parent, ctx))
                     }) {
@@ -162,7 +156,7 @@ impl<'s> Parser<'s> {
                     }
                 }
 
-                CSTNode::Terminal(Terminal {
+                Some(Terminal {
                     kind: Kind::OpenParen,
                     ..
                 }) => {
@@ -172,7 +166,7 @@ impl<'s> Parser<'s> {
                         need_match = false
                     } else if Cond::Production("NodeName").check_opt(prevel, ctx) {
                         return Some((i, ParserRule::ListOfArguments));
-                    } else if Cond::AnyOf(vec![
+                    } else if Cond::AnyOf(&[
                         Cond::keyword("for"),
                         Cond::keyword("select"),
                         Cond::keyword("update"),
@@ -190,7 +184,7 @@ impl<'s> Parser<'s> {
                     }
                 }
 
-                CSTNode::Terminal(Terminal {
+                Some(Terminal {
                     kind: Kind::OpenBracket,
                     ..
                 }) => {
@@ -269,71 +263,265 @@ impl<'s> Parser<'s> {
 
 fn unexpected_reserved_keyword(text: &str, span: Span) -> Error {
     let text_upper = text.to_uppercase();
-    Error {
-        message: format!("Unexpected keyword '{text_upper}'"),
-        span,
-        details: Some(format!(
+    Error::new(format!("Unexpected keyword '{text_upper}'"))
+        .with_span(span)
+        .with_details(
             "This name is a reserved keyword and cannot be \
-            used as an identifier"
-        )),
-        hint: Some(format!(
+            used as an identifier",
+        )
+        .with_hint(format!(
             "Use a different identifier or quote the name \
             with backticks: `{text}`"
-        )),
-    }
+        ))
+        .with_code(ErrorCode::ReservedKeyword)
+        .with_suggestion(Suggestion::replace(span, quote_name(text)))
 }
 
 /// Condition for a stack node. An easier way to match stack node kinds.
-enum Cond {
+enum Cond<'a> {
     Terminal(Kind),
     Production(&'static str),
-    AnyOf(Vec<Cond>),
+    /// Identifier or keyword with this text, in any case. Matches
+    /// productions that consist of a single such token too.
+    Word(&'static str),
+    AnyOf(&'a [Cond<'a>]),
 }
 
-impl Cond {
-    fn keyword(kw: &'static str) -> Self {
+impl Cond<'_> {
+    const fn keyword(kw: &'static str) -> Self {
         Cond::Terminal(Kind::Keyword(Keyword(kw)))
     }
 
     fn check(&self, node: &StackNode, ctx: &Context) -> bool {
         match self {
             Cond::Terminal(kind) => matches!(
-                node.value,
-                CSTNode::Terminal(Terminal { kind: k, .. }) if k == kind
+                node.value.shifted_terminal(),
+                Some(Terminal { kind: k, .. }) if k == kind
             ),
-            Cond::Production(non_term) => match node.value {
-                CSTNode::Production(prod) => {
-                    let (pn, _) = &ctx.spec.production_names[prod.id];
-                    if non_term == pn {
-                        return true;
-                    }
-
-                    // When looking for a production, it might have happened
-                    // that it was inlined and superseded by one of its
-                    // arguments. That's why we save the id of the parent into
-                    // child's `inlined_ids` and check all of them here.
-                    if let Some(inlined_ids) = prod.inlined_ids {
-                        for prod_id in inlined_ids {
-                            let (pn, _) = &ctx.spec.production_names[*prod_id];
-                            if non_term == pn {
-                                return true;
-                            }
-                        }
-                    }
-                    false
-                }
-                _ => false,
-            },
+            Cond::Production(non_term) => ctx.spec.is_production(&node.value, non_term, None),
+            Cond::Word(_) => single_terminal(&node.value).map_or(false, |t| self.check_token(t)),
             Cond::AnyOf(options) => options.iter().any(|v| v.check(node, ctx)),
         }
     }
 
+    /// Checks the token that was not expected by the parser.
+    fn check_token(&self, token: &Terminal) -> bool {
+        match self {
+            Cond::Terminal(kind) => token.kind == *kind,
+            Cond::Production(_) => false,
+            Cond::Word(word) => {
+                matches!(token.kind, Kind::Ident | Kind::Keyword(_))
+                    && token.text.eq_ignore_ascii_case(word)
+            }
+            Cond::AnyOf(options) => options.iter().any(|v| v.check_token(token)),
+        }
+    }
+
     fn check_opt(&self, node: Option<&StackNode>, ctx: &Context) -> bool {
         node.map_or(false, |x| self.check(x, ctx))
     }
 }
 
-#[derive(Debug)]
+/// Terminal of a node that consists of a single token.
+fn single_terminal<'a>(node: &CSTNode<'a>) -> Option<&'a Terminal> {
+    match node {
+        CSTNode::Terminal(t) | CSTNode::Missing(t) => Some(t),
+        CSTNode::Skipped(skipped) => single_terminal(&skipped.node),
+        CSTNode::Production(prod) => match prod.args {
+            [arg] => single_terminal(arg),
+            _ => None,
+        },
+        CSTNode::Empty => None,
+    }
+}
+
+/// A common mistake, recognized by the unexpected token and the nodes on
+/// top of the stack.
+struct Mistake {
+    /// Parser rule the error occurred in, if it matters.
+    within: Option<ParserRule>,
+
+    /// Alternative sequences of nodes on top of the stack, the last node
+    /// being on top. Empty means that the stack is not checked.
+    stack: &'static [&'static [Cond<'static>]],
+
+    token: Cond<'static>,
+
+    /// `{}` is replaced with the text of the token.
+    message: &'static str,
+    hint: &'static str,
+    code: ErrorCode,
+    fix: Fix,
+}
+
+/// Edit of the source that fixes a [Mistake].
+enum Fix {
+    /// Replace the token.
+    Replace(&'static str),
+    /// Insert text before the token.
+    Insert(&'static str),
+    /// Remove the last shifted token, which is also reported instead of
+    /// the unexpected one.
+    RemoveLast,
+    None,
+}
+
+impl Mistake {
+    fn check(
+        &self,
+        parser: &Parser,
+        token: &Terminal,
+        rule: Option<ParserRule>,
+        ctx: &Context,
+    ) -> bool {
+        self.token.check_token(token)
+            && self.within.map_or(true, |within| rule == Some(within))
+            && (self.stack.is_empty()
+                || (self.stack.iter()).any(|nodes| parser.compare_stack(nodes, 0, ctx)))
+    }
+
+    fn to_error(&self, parser: &Parser, token: &Terminal) -> Error {
+        let error = Error::new(self.message.replace("{}", &token.text))
+            .with_hint(self.hint)
+            .with_code(self.code);
+
+        match self.fix {
+            Fix::Replace(text) => error.with_suggestion(Suggestion::replace(token.span, text)),
+            Fix::Insert(text) => error.with_suggestion(Suggestion::insert(token.span.start, text)),
+            Fix::RemoveLast => {
+                let last = parser.get_from_top(0).unwrap();
+                match last.value.shifted_terminal() {
+                    Some(t) => error
+                        .with_span(t.span)
+                        .with_suggestion(Suggestion::remove(t.span)),
+                    None => error,
+                }
+            }
+            Fix::None => error,
+        }
+    }
+}
+
+/// Nodes that a shape element starts with, before `:=`.
+const SHAPE_POINTER: Cond<'static> = Cond::AnyOf(&[
+    Cond::Terminal(Kind::Ident),
+    Cond::Production("Identifier"),
+    Cond::Production("PathStepName"),
+    Cond::Production("ShapePath"),
+    Cond::Production("ShapePointer"),
+    Cond::Production("SimpleShapePointer"),
+]);
+
+const FUNCTION_ARGS: &[&[Cond<'static>]] = &[
+    &[
+        Cond::keyword("function"),
+        Cond::Production("NodeName"),
+        Cond::Terminal(Kind::OpenParen),
+        Cond::Terminal(Kind::CloseParen),
+    ],
+    &[
+        Cond::keyword("function"),
+        Cond::Production("NodeName"),
+        Cond::Terminal(Kind::OpenParen),
+        Cond::Production("FuncDeclArgs"),
+        Cond::Terminal(Kind::CloseParen),
+    ],
+    &[
+        Cond::keyword("function"),
+        Cond::Production("NodeName"),
+        Cond::Production("CreateFunctionArgs"),
+    ],
+];
+
+/// Tokens that end a list, where a trailing comma might not be allowed.
+const LIST_END: Cond<'static> = Cond::AnyOf(&[
+    Cond::Terminal(Kind::CloseParen),
+    Cond::Terminal(Kind::CloseBracket),
+    Cond::Terminal(Kind::CloseBrace),
+    Cond::Terminal(Kind::OpenBrace),
+    Cond::Terminal(Kind::Semicolon),
+    Cond::Terminal(Kind::EOF),
+]);
+
+/// Common mistakes, checked in order, after the rules that need to look
+/// deeper into the stack.
+const MISTAKES: &[Mistake] = &[
+    Mistake {
+        within: Some(ParserRule::Shape),
+        stack: &[&[SHAPE_POINTER]],
+        token: Cond::Terminal(Kind::Eq),
+        message: "Unexpected '='",
+        hint: "Use ':=' to assign a value to a shape element",
+        code: ErrorCode::UnexpectedToken,
+        fix: Fix::Replace(":="),
+    },
+    Mistake {
+        within: Some(ParserRule::Definition),
+        stack: &[&[Cond::Word("index")]],
+        token: Cond::Terminal(Kind::Ident),
+        message: "Expected 'ON', but got '{}' instead",
+        hint: "Indexes are defined as `index on (<expr>)`",
+        code: ErrorCode::MissingToken,
+        fix: Fix::Insert("ON "),
+    },
+    Mistake {
+        within: None,
+        stack: FUNCTION_ARGS,
+        token: Cond::Terminal(Kind::Colon),
+        message: "Unexpected ':'",
+        hint: "Use '->' to declare the return type of a function",
+        code: ErrorCode::UnexpectedToken,
+        fix: Fix::Replace("->"),
+    },
+    Mistake {
+        within: None,
+        stack: &[&[Cond::Terminal(Kind::Comma)]],
+        token: LIST_END,
+        message: "Unexpected trailing comma",
+        hint: "Remove the comma after the last element of the list",
+        code: ErrorCode::UnexpectedToken,
+        fix: Fix::RemoveLast,
+    },
+    Mistake {
+        within: None,
+        stack: &[],
+        token: Cond::keyword("explain"),
+        message: "Unexpected keyword 'EXPLAIN'",
+        hint: "Use `analyze` to show query performance details",
+        code: ErrorCode::UnsupportedKeyword,
+        fix: Fix::Replace("analyze"),
+    },
+    Mistake {
+        within: None,
+        stack: &[],
+        token: Cond::Word("where"),
+        message: "Unexpected 'WHERE'",
+        hint: "Use `filter` to select elements that satisfy a condition",
+        code: ErrorCode::UnsupportedKeyword,
+        fix: Fix::Replace("filter"),
+    },
+    Mistake {
+        within: None,
+        stack: &[],
+        token: Cond::Word("join"),
+        message: "Unexpected 'JOIN'",
+        hint: "EdgeQL has no joins: follow links with paths, \
+            or use a nested `select` or `for`",
+        code: ErrorCode::UnsupportedKeyword,
+        fix: Fix::None,
+    },
+    Mistake {
+        within: None,
+        stack: &[],
+        token: Cond::keyword("as"),
+        message: "Unexpected keyword 'AS'",
+        hint: "Use `alias := <expr>` to name an expression",
+        code: ErrorCode::UnsupportedKeyword,
+        fix: Fix::None,
+    },
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum ParserRule {
     ForIterator,
     Definition,
@@ -0,0 +1,52 @@
+//! Grammar spec embedded into the crate at build time, which allows parsing
+//! without loading the spec from Python.
+//!
+//! The spec is read from `grammar.bc` in the crate directory (or from the
+//! path in `EDGEQL_GRAMMAR_SPEC`). The file is generated from the Python
+//! grammar by `python setup.py build_parsers --inplace` and is checked in,
+//! so it has to be regenerated and committed with changes of the grammar.
+
+use once_cell::sync::OnceCell;
+
+use crate::tokenizer::Error;
+
+use super::{Context, ParseResult, Spec, SpecSerializable};
+
+static SPEC_BITCODE: &[u8] = include_bytes!(env!("EDGEQL_GRAMMAR_SPEC"));
+
+static SPEC: OnceCell<Spec> = OnceCell::new();
+
+/// Grammar spec embedded at build time.
+///
+/// It is deserialized on first use.
+pub fn spec() -> &'static Spec {
+    SPEC.get_or_init(|| {
+        bincode::deserialize::<SpecSerializable>(SPEC_BITCODE)
+            .expect("embedded grammar spec is valid")
+            .into()
+    })
+}
+
+/// Checks the syntax of an EdgeQL block (statements separated by
+/// semicolons) and returns all tokenizer and parser errors.
+pub fn parse_block(source: &str) -> Result<(), Vec<Error>> {
+    let ctx = Context::new(spec());
+    into_errors(ctx.parse_block(source))
+}
+
+/// Checks the syntax of an SDL document and returns all tokenizer and
+/// parser errors.
+pub fn parse_sdl(source: &str) -> Result<(), Vec<Error>> {
+    let ctx = Context::new(spec());
+    into_errors(ctx.parse_sdl_document(source))
+}
+
+fn into_errors(result: ParseResult) -> Result<(), Vec<Error>> {
+    if result.has_errors() {
+        let mut errors = result.tokenizer_errors;
+        errors.extend(result.parser_errors);
+        Err(errors)
+    } else {
+        Ok(())
+    }
+}
@@ -0,0 +1,105 @@
+//! Entry points for parsing source text, one for each start symbol of the
+//! grammar.
+
+use crate::tokenizer::{Error, Kind, Tokenizer};
+
+use super::{parse, CSTNode, Context, Terminal};
+
+/// Result of parsing source text.
+#[derive(Debug)]
+pub struct ParseResult<'a> {
+    /// The tree, unless the parser could not recover from an error.
+    pub cst: Option<&'a CSTNode<'a>>,
+    pub tokenizer_errors: Vec<Error>,
+    pub parser_errors: Vec<Error>,
+}
+
+impl<'a> ParseResult<'a> {
+    /// All errors, tokenizer errors first.
+    pub fn errors(&self) -> impl Iterator<Item = &Error> {
+        self.tokenizer_errors.iter().chain(&self.parser_errors)
+    }
+
+    pub fn has_errors(&self) -> bool {
+        !self.tokenizer_errors.is_empty() || !self.parser_errors.is_empty()
+    }
+}
+
+impl<'s> Context<'s> {
+    /// Parses a block of statements, separated by semicolons.
+    pub fn parse_block(&self, source: &str) -> ParseResult<'_> {
+        self.parse_source(Kind::StartBlock, source)
+    }
+
+    /// Parses a single expression or a statement without trailing semicolon.
+    pub fn parse_fragment(&self, source: &str) -> ParseResult<'_> {
+        self.parse_source(Kind::StartFragment, source)
+    }
+
+    /// Parses body of a migration (`CREATE MIGRATION { ... }`).
+    pub fn parse_migration(&self, source: &str) -> ParseResult<'_> {
+        self.parse_source(Kind::StartMigration, source)
+    }
+
+    /// Parses body of an extension package (`CREATE EXTENSION PACKAGE { ... }`).
+    pub fn parse_extension(&self, source: &str) -> ParseResult<'_> {
+        self.parse_source(Kind::StartExtension, source)
+    }
+
+    /// Parses an SDL document.
+    pub fn parse_sdl_document(&self, source: &str) -> ParseResult<'_> {
+        self.parse_source(Kind::StartSDLDocument, source)
+    }
+
+    fn parse_source(&self, start: Kind, source: &str) -> ParseResult<'_> {
+        let (input, tokenizer_errors) = self.tokenize(start, source);
+        let (cst, parser_errors) = parse(input, self);
+        ParseResult {
+            cst,
+            tokenizer_errors,
+            parser_errors,
+        }
+    }
+
+    /// Tokens of the source, allocated in this context, starting with the
+    /// start token and ending with EOF.
+    pub(super) fn tokenize(&self, start: Kind, source: &str) -> (Vec<&Terminal>, Vec<Error>) {
+        let mut tokens = Vec::new();
+        let mut tokenizer_errors = Vec::new();
+        let tokenizer = Tokenizer::new(source).with_error_recovery();
+        for token in tokenizer.validated_values().with_eof() {
+            match token {
+                // already reported as an error
+                Ok(token) if token.kind == Kind::Unknown => {}
+                Ok(token) => tokens.push(Terminal::from_token(token)),
+                Err(e) => tokenizer_errors.push(e),
+            }
+        }
+
+        let input = with_start_token(Terminal::from_start_kind(start), tokens);
+        let input = input.into_iter().map(|t| self.alloc_terminal(t)).collect();
+        (input, tokenizer_errors)
+    }
+}
+
+/// Prepends the start token, which selects the start symbol of the grammar,
+/// to the input of [parse].
+///
+/// The start token is placed at the position of the first token, so errors
+/// reported at it point into the source text.
+pub fn with_start_token(
+    start: Terminal,
+    tokens: impl IntoIterator<Item = Terminal>,
+) -> Vec<Terminal> {
+    let tokens = tokens.into_iter();
+
+    let mut buf = Vec::with_capacity(tokens.size_hint().0 + 1);
+    buf.push(start);
+    buf.extend(tokens);
+
+    if buf.len() >= 2 {
+        buf[0].span.start = buf[1].span.start;
+        buf[0].span.end = buf[1].span.start;
+    }
+    buf
+}
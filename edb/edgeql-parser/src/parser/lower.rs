@@ -0,0 +1,1332 @@
+//! Lowering of CST into the typed [AST](crate::ast).
+//!
+//! This mirrors the reduce methods of the grammar in
+//! `edb/edgeql/parser/grammar`: productions are matched by their names from
+//! [Spec::production_names], so the lowering works with any spec that was
+//! generated from that grammar.
+//!
+//! Supported are expressions, shapes, type expressions and all query
+//! statements (SELECT, INSERT, UPDATE, DELETE, FOR and GROUP, with WITH
+//! blocks). Of DDL, modules, scalar types, pseudo types, object types,
+//! abstract and concrete properties, links, constraints and annotations,
+//! concrete indexes, aliases and globals are supported, together with the
+//! commands nested in their blocks. Functions, operators, casts, abstract
+//! indexes, access policies, triggers, rewrites, migrations, databases,
+//! roles, extensions and DDL with a WITH block are not supported yet.
+//! Lowering any other production results in an error.
+
+use indexmap::IndexMap;
+
+use crate::ast::*;
+use crate::position::Span;
+use crate::tokenizer::{Error, Kind, Value};
+
+use super::{CSTNode, Spec, Terminal};
+
+#[cfg(feature = "python")]
+use edgeql_parser_derive::IntoPython;
+
+mod ddl;
+
+/// A top-level statement of an EdgeQL block.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", derive(IntoPython))]
+#[cfg_attr(feature = "python", py_union)]
+pub enum Statement {
+    Query(Query),
+    Command(Command),
+    /// A DDL command, which unlike [Command] holds the commands nested in
+    /// its block.
+    DDL(DDLOperation),
+}
+
+/// Lowers a CST parsed from the `STARTBLOCK` start symbol into statements.
+pub fn lower_block(node: &CSTNode, spec: &Spec) -> Result<Vec<Statement>, Error> {
+    Lowering { spec }.block(node)
+}
+
+/// Lowers a CST parsed from the `STARTFRAGMENT` start symbol into an
+/// expression.
+pub fn lower_expr(node: &CSTNode, spec: &Spec) -> Result<Expr, Error> {
+    Lowering { spec }.expr(node)
+}
+
+struct Lowering<'s> {
+    spec: &'s Spec,
+}
+
+type Production<'s, 'n> = (&'s str, &'s str, &'n [CSTNode<'n>]);
+
+type OffsetLimit = (Option<Box<Expr>>, Option<Box<Expr>>);
+
+impl<'s> Lowering<'s> {
+    fn block(&self, node: &CSTNode) -> Result<Vec<Statement>, Error> {
+        match self.production(node)? {
+            ("EdgeQLBlock", "reduce_OptSemicolons", _) => Ok(Vec::new()),
+            _ => self.list(node, "StatementBlock", |n| self.statement(n)),
+        }
+    }
+
+    fn statement(&self, node: &CSTNode) -> Result<Statement, Error> {
+        match self.production(node)? {
+            ("SingleStatement", "reduce_IfThenElseExpr", [expr]) => {
+                let query = select(None, self.expr(expr)?, None, None, None, None, true);
+                Ok(Statement::Query(query))
+            }
+            (non_term, _, _)
+                if ["Create", "Alter", "Drop"]
+                    .iter()
+                    .any(|prefix| non_term.starts_with(prefix)) =>
+            {
+                Ok(Statement::DDL(self.ddl(node)?))
+            }
+            _ => match self.expr(node)?.kind {
+                ExprKind::Query(query) => Ok(Statement::Query(query)),
+                _ => Err(self.unsupported(node)),
+            },
+        }
+    }
+
+    fn expr(&self, node: &CSTNode) -> Result<Expr, Error> {
+        let kind = match self.production(node)? {
+            (
+                "Expr",
+                "reduce_EXISTS_Expr" | "reduce_DISTINCT_Expr" | "reduce_NOT_Expr",
+                [op, x],
+            ) => self.unary_op(self.operator(op)?, x)?,
+            ("Expr", "reduce_PLUS_Expr", [op, x]) => self.unary_op(self.text(op)?, x)?,
+            ("Expr", "reduce_MINUS_Expr", [op, x]) => match self.expr(x)?.kind {
+                ExprKind::BaseConstant(BaseConstant {
+                    kind:
+                        BaseConstantKind::Constant(Constant {
+                            kind:
+                                kind @ (ConstantKind::INTEGER
+                                | ConstantKind::FLOAT
+                                | ConstantKind::BIGINT
+                                | ConstantKind::DECIMAL),
+                            value,
+                        }),
+                }) => constant(kind, format!("-{value}")),
+                kind => ExprKind::UnaryOp(UnaryOp {
+                    op: self.text(op)?,
+                    operand: Box::new(Expr { kind }),
+                }),
+            },
+            ("Expr", "reduce_DETACHED_Expr", [_, x]) => ExprKind::DetachedExpr(DetachedExpr {
+                expr: self.boxed_expr(x)?,
+                preserve_path_prefix: false,
+            }),
+            ("Expr", "reduce_GLOBAL_NodeName", [_, name]) => ExprKind::GlobalExpr(GlobalExpr {
+                name: self.object_ref(name)?,
+            }),
+            ("Expr", "reduce_Expr_IndirectionEl", [x, el]) => {
+                let el = self.indirection(el)?;
+                match self.expr(x)?.kind {
+                    ExprKind::Indirection(mut ind) => {
+                        ind.indirection.push(el);
+                        ExprKind::Indirection(ind)
+                    }
+                    kind => ExprKind::Indirection(Indirection {
+                        arg: Box::new(Expr { kind }),
+                        indirection: vec![el],
+                    }),
+                }
+            }
+            ("Expr", "reduce_Expr_Shape", [x, shape]) => ExprKind::Shape(Shape {
+                expr: Some(self.boxed_expr(x)?),
+                elements: self.shape(shape)?,
+            }),
+            ("Expr", "reduce_LANGBRACKET_FullTypeExpr_RANGBRACKET_Expr", [_, ty, _, x])
+            | (
+                "AtomicExpr",
+                "reduce_LANGBRACKET_FullTypeExpr_RANGBRACKET_AtomicExpr",
+                [_, ty, _, x],
+            ) => self.type_cast(ty, x, None)?,
+            (
+                "Expr",
+                "reduce_LANGBRACKET_OPTIONAL_FullTypeExpr_RANGBRACKET_Expr",
+                [_, _, ty, _, x],
+            ) => self.type_cast(ty, x, Some(CardinalityModifier::Optional))?,
+            (
+                "Expr",
+                "reduce_LANGBRACKET_REQUIRED_FullTypeExpr_RANGBRACKET_Expr",
+                [_, _, ty, _, x],
+            ) => self.type_cast(ty, x, Some(CardinalityModifier::Required))?,
+            ("Expr", "reduce_Expr_IS_TypeExpr", [l, _, ty]) => self.is_op(l, "IS", ty)?,
+            ("Expr", "reduce_Expr_IS_NOT_TypeExpr", [l, _, _, ty]) => {
+                self.is_op(l, "IS NOT", ty)?
+            }
+            ("Expr", "reduce_INTROSPECT_TypeExpr", [_, ty]) => ExprKind::Introspect(Introspect {
+                r#type: self.type_expr(ty)?,
+            }),
+            ("Expr", "reduce_Expr_NOT_LIKE_Expr", [l, _, _, r]) => self.bin_op(l, "NOT LIKE", r)?,
+            ("Expr", "reduce_Expr_NOT_ILIKE_Expr", [l, _, _, r]) => {
+                self.bin_op(l, "NOT ILIKE", r)?
+            }
+            ("Expr", "reduce_Expr_NOT_IN_Expr", [l, _, _, r]) => self.bin_op(l, "NOT IN", r)?,
+            ("Expr", "reduce_Expr_IF_Expr_ELSE_Expr", [if_expr, _, condition, _, else_expr]) => {
+                self.if_else(condition, if_expr, else_expr, true)?
+            }
+            ("IfThenElseExpr", _, [_, condition, _, if_expr, _, else_expr]) => {
+                self.if_else(condition, if_expr, else_expr, false)?
+            }
+            ("Expr", prod, [l, op @ CSTNode::Terminal(_), r]) if prod.ends_with("_Expr") => {
+                let op = self.operator(op)?;
+                self.bin_op(l, &op, r)?
+            }
+
+            ("BaseAtomicExpr", "reduce_NodeName", [name]) => ExprKind::Path(Path {
+                steps: vec![PathSteps::ObjectRef(self.object_ref(name)?)],
+                partial: false,
+            }),
+            ("BaseAtomicExpr", "reduce_PathStep", [step]) => ExprKind::Path(Path {
+                steps: vec![self.path_step(step)?],
+                partial: true,
+            }),
+            ("BaseAtomicExpr", _, [anchor @ CSTNode::Terminal(_)]) => {
+                let anchor = ExprKind::Anchor(Anchor {
+                    name: self.text(anchor)?.to_lowercase(),
+                    kind: AnchorKind::SpecialAnchor(SpecialAnchor {}),
+                });
+                ExprKind::Path(Path {
+                    steps: vec![PathSteps::Expr(Box::new(Expr { kind: anchor }))],
+                    partial: false,
+                })
+            }
+            ("Path", "reduce_Expr_PathStep", [x, step])
+            | ("AtomicPath", "reduce_AtomicExpr_PathStep", [x, step]) => {
+                let mut path = match self.expr(x)?.kind {
+                    ExprKind::Path(path) => path,
+                    kind => Path {
+                        steps: vec![PathSteps::Expr(Box::new(Expr { kind }))],
+                        partial: false,
+                    },
+                };
+                path.steps.push(self.path_step(step)?);
+                ExprKind::Path(path)
+            }
+
+            ("Constant", "reduce_PARAMETER", [param]) => ExprKind::Parameter(Parameter {
+                name: self.string_value(param)?,
+            }),
+            ("BaseNumberConstant", prod, [value]) => {
+                let kind = match prod {
+                    "reduce_ICONST" => ConstantKind::INTEGER,
+                    "reduce_FCONST" => ConstantKind::FLOAT,
+                    "reduce_NICONST" => ConstantKind::BIGINT,
+                    "reduce_NFCONST" => ConstantKind::DECIMAL,
+                    _ => return Err(self.unsupported(node)),
+                };
+                constant(kind, self.text(value)?)
+            }
+            ("BaseStringConstant", "reduce_SCONST", [value]) => {
+                constant(ConstantKind::STRING, self.string_value(value)?)
+            }
+            ("BaseBytesConstant", "reduce_BCONST", [value]) => {
+                let CSTNode::Terminal(Terminal {
+                    value: Some(Value::Bytes(bytes)),
+                    ..
+                }) = value
+                else {
+                    return Err(self.unsupported(value));
+                };
+                ExprKind::BaseConstant(BaseConstant {
+                    kind: BaseConstantKind::BytesConstant(BytesConstant {
+                        value: bytes.clone(),
+                    }),
+                })
+            }
+            ("BaseBooleanConstant", prod, [_]) => {
+                constant(ConstantKind::BOOLEAN, (prod == "reduce_TRUE").to_string())
+            }
+
+            ("Tuple", "reduce_LPAREN_RPAREN", _) => ExprKind::Tuple(Tuple { elements: vec![] }),
+            ("Tuple", _, [_, first, _, rest, _]) => {
+                let mut elements = vec![self.boxed_expr(first)?];
+                elements.extend(self.expr_list(rest)?);
+                ExprKind::Tuple(Tuple { elements })
+            }
+            ("NamedTuple", _, [_, elements, _]) => ExprKind::NamedTuple(NamedTuple {
+                elements: self.list(elements, "NamedTupleElementList", |el| {
+                    let [name, _, val] = self.args(el, "NamedTupleElement")? else {
+                        return Err(self.unsupported(el));
+                    };
+                    Ok(TupleElement {
+                        name: Ptr {
+                            name: self.object_ref(name)?.name,
+                            direction: None,
+                            r#type: None,
+                        },
+                        val: self.boxed_expr(val)?,
+                    })
+                })?,
+            }),
+            ("Set", _, [_, elements, _]) => ExprKind::Set(Set {
+                elements: self.expr_list(elements)?,
+            }),
+            ("Collection", _, [_, elements, _]) => ExprKind::Array(Array {
+                elements: self.expr_list(elements)?,
+            }),
+
+            ("FuncApplication", _, [name, _, args, _]) => self.function_call(name, args)?,
+
+            ("FreeShape", _, [_, elements, _]) => ExprKind::Shape(Shape {
+                expr: None,
+                elements: self.list(elements, "FreeComputableShapePointerList", |n| {
+                    self.shape_element(n)
+                })?,
+            }),
+
+            ("ExprStmt", "reduce_WithBlock_ExprStmtCore", [with, stmt]) => {
+                let ExprKind::Query(mut query) = self.expr(stmt)?.kind else {
+                    return Err(self.unsupported(stmt));
+                };
+                query.aliases = Some(self.with_block(with)?);
+                ExprKind::Query(query)
+            }
+            ("SimpleSelect", "reduce_Select", [_, result, filter, order, limit]) => {
+                let (result_alias, result) = self.optionally_aliased_expr(result)?;
+                let filter = self.opt(filter, |n| self.boxed_expr(n))?;
+                let order = self.opt(order, |n| self.sort_clause(n))?;
+                let (offset, limit) = self.select_limit(limit)?;
+                let query = select(result_alias, result, filter, order, offset, limit, false);
+                ExprKind::Query(query)
+            }
+            ("SimpleInsert", "reduce_Insert", [_, subject, unless_conflict]) => {
+                ExprKind::Query(self.insert(subject, unless_conflict)?)
+            }
+            ("SimpleUpdate", "reduce_Update", [_, subject, filter, _, shape]) => {
+                ExprKind::Query(Query {
+                    aliases: None,
+                    kind: QueryKind::UpdateQuery(UpdateQuery {
+                        shape: self.shape(shape)?,
+                        subject: self.boxed_expr(subject)?,
+                        r#where: self.opt(filter, |n| self.boxed_expr(n))?,
+                    }),
+                })
+            }
+            ("SimpleDelete", "reduce_Delete", [_, subject, filter, order, limit]) => {
+                let (offset, limit) = self.select_limit(limit)?;
+                ExprKind::Query(Query {
+                    aliases: None,
+                    kind: QueryKind::DeleteQuery(DeleteQuery {
+                        subject: self.boxed_expr(subject)?,
+                        r#where: self.opt(filter, |n| self.boxed_expr(n))?,
+                        orderby: self.opt(order, |n| self.sort_clause(n))?,
+                        offset,
+                        limit,
+                    }),
+                })
+            }
+            ("SimpleFor", prod, [_, optional, alias, _, iterator, .., result]) => {
+                ExprKind::Query(Query {
+                    aliases: None,
+                    kind: QueryKind::ForQuery(ForQuery {
+                        from_desugaring: false,
+                        has_union: prod == "reduce_ForIn",
+                        optional: !self.is_empty(optional),
+                        iterator: self.boxed_expr(iterator)?,
+                        iterator_alias: self.identifier(alias)?,
+                        result_alias: None,
+                        result: self.boxed_expr(result)?,
+                    }),
+                })
+            }
+            ("SimpleGroup", "reduce_Group", [_, subject, using, by]) => {
+                let (subject_alias, subject) = self.optionally_aliased_expr(subject)?;
+                let using = self.opt(using, |n| {
+                    self.list(n, "AliasedExprList", |n| self.aliased_expr(n))
+                })?;
+                ExprKind::Query(Query {
+                    aliases: None,
+                    kind: QueryKind::GroupQuery(GroupQuery {
+                        subject_alias,
+                        using,
+                        by: self.list(by, "GroupingElementList", |n| self.grouping_element(n))?,
+                        subject: Box::new(subject),
+                        kind: None,
+                    }),
+                })
+            }
+
+            _ => return Err(self.unsupported(node)),
+        };
+        Ok(Expr { kind })
+    }
+
+    fn boxed_expr(&self, node: &CSTNode) -> Result<Box<Expr>, Error> {
+        self.expr(node).map(Box::new)
+    }
+
+    #[allow(clippy::vec_box)] // that's what the AST holds
+    fn expr_list(&self, node: &CSTNode) -> Result<Vec<Box<Expr>>, Error> {
+        if self.is_empty(node) {
+            return Ok(Vec::new());
+        }
+        self.list(node, "ExprList", |n| self.boxed_expr(n))
+    }
+
+    fn unary_op(&self, op: String, operand: &CSTNode) -> Result<ExprKind, Error> {
+        Ok(ExprKind::UnaryOp(UnaryOp {
+            op,
+            operand: self.boxed_expr(operand)?,
+        }))
+    }
+
+    fn if_else(
+        &self,
+        condition: &CSTNode,
+        if_expr: &CSTNode,
+        else_expr: &CSTNode,
+        python_style: bool,
+    ) -> Result<ExprKind, Error> {
+        Ok(ExprKind::IfElse(IfElse {
+            condition: self.boxed_expr(condition)?,
+            if_expr: self.boxed_expr(if_expr)?,
+            else_expr: self.boxed_expr(else_expr)?,
+            python_style,
+        }))
+    }
+
+    fn bin_op(&self, left: &CSTNode, op: &str, right: &CSTNode) -> Result<ExprKind, Error> {
+        Ok(ExprKind::BinOp(BinOp {
+            left: self.boxed_expr(left)?,
+            op: op.to_string(),
+            right: self.boxed_expr(right)?,
+            rebalanced: false,
+            set_constructor: false,
+        }))
+    }
+
+    /// Operators that are keywords are normalized to upper case.
+    fn operator(&self, node: &CSTNode) -> Result<String, Error> {
+        match node {
+            CSTNode::Terminal(Terminal {
+                kind: Kind::Keyword(_),
+                text,
+                ..
+            }) => Ok(text.to_uppercase()),
+            _ => self.text(node),
+        }
+    }
+
+    fn indirection(&self, node: &CSTNode) -> Result<IndirectionIndirection, Error> {
+        let opt = |n: &CSTNode| self.boxed_expr(n).map(Some);
+        Ok(match self.production(node)? {
+            ("IndirectionEl", "reduce_LBRACKET_Expr_RBRACKET", [_, index, _]) => {
+                IndirectionIndirection::Index(Index {
+                    index: self.boxed_expr(index)?,
+                })
+            }
+            ("IndirectionEl", _, [_, start, _, stop, _]) => IndirectionIndirection::Slice(Slice {
+                start: opt(start)?,
+                stop: opt(stop)?,
+            }),
+            ("IndirectionEl", "reduce_LBRACKET_Expr_COLON_RBRACKET", [_, start, _, _]) => {
+                IndirectionIndirection::Slice(Slice {
+                    start: opt(start)?,
+                    stop: None,
+                })
+            }
+            ("IndirectionEl", "reduce_LBRACKET_COLON_Expr_RBRACKET", [_, _, stop, _]) => {
+                IndirectionIndirection::Slice(Slice {
+                    start: None,
+                    stop: opt(stop)?,
+                })
+            }
+            _ => return Err(self.unsupported(node)),
+        })
+    }
+
+    fn path_step(&self, node: &CSTNode) -> Result<PathSteps, Error> {
+        let (name, direction, r#type) = match self.production(node)? {
+            ("PathStep", "reduce_DOT_PathStepName", [_, name]) => {
+                (self.object_ref(name)?.name, ">", None)
+            }
+            ("PathStep", "reduce_DOT_ICONST", [_, index]) => (self.text(index)?, ">", None),
+            ("PathStep", "reduce_DOTBW_PathStepName", [_, name]) => {
+                (self.object_ref(name)?.name, "<", None)
+            }
+            ("PathStep", "reduce_AT_PathNodeName", [_, name]) => (
+                self.object_ref(name)?.name,
+                ">",
+                Some("property".to_string()),
+            ),
+            ("TypeIntersection", _, _) => {
+                return Ok(PathSteps::TypeIntersection(self.type_intersection(node)?));
+            }
+            _ => return Err(self.unsupported(node)),
+        };
+        Ok(PathSteps::Ptr(Ptr {
+            name,
+            direction: Some(direction.to_string()),
+            r#type,
+        }))
+    }
+
+    fn function_call(&self, name: &CSTNode, args: &CSTNode) -> Result<ExprKind, Error> {
+        let name = self.object_ref(name)?;
+        let func = match name.module {
+            Some(module) => FunctionCallFunc::Tuple((module, name.name)),
+            None => FunctionCallFunc::str(name.name),
+        };
+
+        let mut positional = Vec::new();
+        let mut kwargs = IndexMap::new();
+        let mut last_named_seen = None;
+        let args = if self.is_empty(args) {
+            Vec::new()
+        } else {
+            self.list(args, "FuncArgList", |arg| Ok(*arg))?
+        };
+        for arg in args {
+            let [arg_expr, filter, order] = self.args(&arg, "FuncCallArg")? else {
+                return Err(self.unsupported(&arg));
+            };
+
+            let (arg_name, value) = match self.production(arg_expr)? {
+                ("FuncCallArgExpr", "reduce_Expr", [value]) => (None, value),
+                ("FuncCallArgExpr", "reduce_AnyIdentifier_ASSIGN_Expr", [name, _, value]) => {
+                    (Some((self.identifier(name)?, self.span(name))), value)
+                }
+                _ => return Err(self.unsupported(arg_expr)),
+            };
+
+            let mut value = self.boxed_expr(value)?;
+            if !self.is_empty(filter) || !self.is_empty(order) {
+                let filter = self.opt(filter, |n| self.boxed_expr(n))?;
+                let order = self.opt(order, |n| self.sort_clause(n))?;
+                let query = select(None, *value, filter, order, None, None, true);
+                value = Box::new(Expr {
+                    kind: ExprKind::Query(query),
+                });
+            }
+
+            if let Some((arg_name, span)) = arg_name {
+                if kwargs.contains_key(&arg_name) {
+                    let message = format!("duplicate named argument `{arg_name}`");
+                    return Err(Error::new(message).with_span(span));
+                }
+                last_named_seen = Some(arg_name.clone());
+                kwargs.insert(arg_name, value);
+            } else {
+                if let Some(last_named_seen) = &last_named_seen {
+                    let message =
+                        format!("positional argument after named argument `{last_named_seen}`");
+                    return Err(Error::new(message).with_span(self.span(arg_expr)));
+                }
+                positional.push(value);
+            }
+        }
+
+        Ok(ExprKind::FunctionCall(FunctionCall {
+            func,
+            args: positional,
+            kwargs,
+            window: None,
+        }))
+    }
+
+    fn optionally_aliased_expr(&self, node: &CSTNode) -> Result<(Option<String>, Expr), Error> {
+        match self.production(node)? {
+            ("OptionallyAliasedExpr", "reduce_Expr", [expr]) => Ok((None, self.expr(expr)?)),
+            ("OptionallyAliasedExpr", "reduce_AliasedExpr", [aliased]) => {
+                let AliasedExpr { alias, expr } = self.aliased_expr(aliased)?;
+                Ok((Some(alias), *expr))
+            }
+            _ => Err(self.unsupported(node)),
+        }
+    }
+
+    fn aliased_expr(&self, node: &CSTNode) -> Result<AliasedExpr, Error> {
+        let [alias, _, expr] = self.args(node, "AliasedExpr")? else {
+            return Err(self.unsupported(node));
+        };
+        Ok(AliasedExpr {
+            alias: self.identifier(alias)?,
+            expr: self.boxed_expr(expr)?,
+        })
+    }
+
+    fn with_block(&self, node: &CSTNode) -> Result<Vec<QueryAliases>, Error> {
+        let [_, decls] = self.args(node, "WithBlock")? else {
+            return Err(self.unsupported(node));
+        };
+        self.list(decls, "WithDeclList", |decl| {
+            Ok(match self.production(decl)? {
+                ("AliasDecl", "reduce_MODULE_ModuleName", [_, module]) => {
+                    QueryAliases::ModuleAliasDecl(ModuleAliasDecl {
+                        module: self.module_name(module)?.join("::"),
+                        alias: None,
+                    })
+                }
+                ("AliasDecl", "reduce_Identifier_AS_MODULE_ModuleName", [alias, _, _, module]) => {
+                    QueryAliases::ModuleAliasDecl(ModuleAliasDecl {
+                        module: self.module_name(module)?.join("::"),
+                        alias: Some(self.identifier(alias)?),
+                    })
+                }
+                ("AliasedExpr", _, _) => QueryAliases::AliasedExpr(self.aliased_expr(decl)?),
+                _ => return Err(self.unsupported(decl)),
+            })
+        })
+    }
+
+    fn insert(&self, subject: &CSTNode, unless_conflict: &CSTNode) -> Result<Query, Error> {
+        let (path, shape) = match self.production(subject)? {
+            ("Expr", "reduce_Expr_Shape", [path, shape]) => (path, self.shape(shape)?),
+            ("FreeShape", _, _) => {
+                return Err(Error::new("insert shape expressions must have a type name")
+                    .with_span(self.span(subject)));
+            }
+            _ => (subject, Vec::new()),
+        };
+
+        let subject = match self.expr(path)?.kind {
+            ExprKind::Path(Path { steps, .. }) if steps.len() == 1 => {
+                match steps.into_iter().next() {
+                    Some(PathSteps::ObjectRef(object_ref)) => Some(object_ref),
+                    _ => None,
+                }
+            }
+            ExprKind::IfElse(_) => {
+                return Err(Error::new(
+                    "INSERT only works with object types, not conditional expressions",
+                )
+                .with_hint(
+                    "To resolve this try surrounding the INSERT branch of the conditional \
+                    expression with parentheses. This way the INSERT will be triggered \
+                    conditionally in one of the branches.",
+                )
+                .with_span(self.span(path)));
+            }
+            _ => None,
+        };
+        let Some(subject) = subject else {
+            return Err(Error::new(
+                "INSERT only works with object types, not arbitrary expressions",
+            )
+            .with_hint(
+                "To resolve this try to surround the entire INSERT statement with \
+                        parentheses in order to separate it from the rest of the expression.",
+            )
+            .with_span(self.span(path)));
+        };
+
+        let unless_conflict = match self.production(unless_conflict)? {
+            ("OptUnlessConflictClause", "reduce_empty", _) => None,
+            ("UnlessConflictSpecifier", "reduce_empty", _) => Some((None, None)),
+            ("UnlessConflictSpecifier", "reduce_ON_Expr", [_, on]) => {
+                Some((Some(self.boxed_expr(on)?), None))
+            }
+            ("UnlessConflictSpecifier", "reduce_ON_Expr_ELSE_Expr", [_, on, _, else_expr]) => {
+                Some((
+                    Some(self.boxed_expr(on)?),
+                    Some(self.boxed_expr(else_expr)?),
+                ))
+            }
+            _ => return Err(self.unsupported(unless_conflict)),
+        };
+
+        Ok(Query {
+            aliases: None,
+            kind: QueryKind::InsertQuery(InsertQuery {
+                subject,
+                shape,
+                unless_conflict,
+            }),
+        })
+    }
+
+    fn grouping_element(&self, node: &CSTNode) -> Result<GroupingElement, Error> {
+        let kind = match self.production(node)? {
+            ("GroupingElement", "reduce_GroupingAtom", [atom]) => {
+                let element = match self.grouping_atom(atom)? {
+                    GroupingIdentListElements::ObjectRef(r) => GroupingSimpleElement::ObjectRef(r),
+                    GroupingIdentListElements::Path(p) => GroupingSimpleElement::Path(p),
+                    GroupingIdentListElements::GroupingIdentList(l) => {
+                        GroupingSimpleElement::GroupingIdentList(l)
+                    }
+                };
+                GroupingElementKind::GroupingSimple(GroupingSimple { element })
+            }
+            ("GroupingElement", "reduce_LBRACE_GroupingElementList_RBRACE", [_, sets, _]) => {
+                GroupingElementKind::GroupingSets(GroupingSets {
+                    sets: self.list(sets, "GroupingElementList", |n| self.grouping_element(n))?,
+                })
+            }
+            ("GroupingElement", _, [oper, _, atoms, _]) => {
+                let elements = self.list(atoms, "GroupingAtomList", |atom| {
+                    Ok(match self.grouping_atom(atom)? {
+                        GroupingIdentListElements::ObjectRef(r) => {
+                            GroupingOperationElements::ObjectRef(r)
+                        }
+                        GroupingIdentListElements::Path(p) => GroupingOperationElements::Path(p),
+                        GroupingIdentListElements::GroupingIdentList(l) => {
+                            GroupingOperationElements::GroupingIdentList(l)
+                        }
+                    })
+                })?;
+                GroupingElementKind::GroupingOperation(GroupingOperation {
+                    oper: self.text(oper)?.to_lowercase(),
+                    elements,
+                })
+            }
+            _ => return Err(self.unsupported(node)),
+        };
+        Ok(GroupingElement { kind })
+    }
+
+    fn grouping_atom(&self, node: &CSTNode) -> Result<GroupingIdentListElements, Error> {
+        let ptr = |name, r#type: Option<&str>| {
+            GroupingIdentListElements::Path(Path {
+                steps: vec![PathSteps::Ptr(Ptr {
+                    name,
+                    direction: None,
+                    r#type: r#type.map(String::from),
+                })],
+                partial: true,
+            })
+        };
+        Ok(match self.production(node)? {
+            ("GroupingIdent", "reduce_Identifier", [n]) => {
+                GroupingIdentListElements::ObjectRef(ObjectRef {
+                    name: self.identifier(n)?,
+                    module: None,
+                    itemclass: None,
+                })
+            }
+            ("GroupingIdent", "reduce_DOT_Identifier", [_, n]) => ptr(self.identifier(n)?, None),
+            ("GroupingIdent", "reduce_AT_Identifier", [_, n]) => {
+                ptr(self.identifier(n)?, Some("property"))
+            }
+            ("GroupingAtom", "reduce_LPAREN_GroupingIdentList_RPAREN", [_, list, _]) => {
+                GroupingIdentListElements::GroupingIdentList(GroupingIdentList {
+                    elements: self.list(list, "GroupingIdentList", |n| self.grouping_atom(n))?,
+                })
+            }
+            _ => return Err(self.unsupported(node)),
+        })
+    }
+
+    fn shape(&self, node: &CSTNode) -> Result<Vec<ShapeElement>, Error> {
+        match self.production(node)? {
+            ("Shape", "reduce_LBRACE_RBRACE", _) => Ok(Vec::new()),
+            _ => self.list(node, "ShapeElementList", |n| self.shape_element(n)),
+        }
+    }
+
+    fn shape_element(&self, node: &CSTNode) -> Result<ShapeElement, Error> {
+        match self.production(node)? {
+            (
+                "ShapeElement",
+                "reduce_ShapeElementWithSubShape",
+                [pointer, sub_shape, filter, order, limit],
+            ) => {
+                let mut element = self.shape_pointer(pointer)?;
+                element.elements = Some(if self.is_empty(sub_shape) {
+                    Vec::new()
+                } else {
+                    self.shape(sub_shape)?
+                });
+                element.r#where = self.opt(filter, |n| self.boxed_expr(n))?;
+                element.orderby = self.opt(order, |n| self.sort_clause(n))?;
+                (element.offset, element.limit) = self.select_limit(limit)?;
+                Ok(element)
+            }
+            (
+                "ComputableShapePointer" | "FreeComputableShapePointer",
+                _,
+                [quals @ .., pointer, op, compexpr],
+            ) => {
+                let mut element = self.shape_pointer(pointer)?;
+                for qual in quals {
+                    match self.text(qual)?.to_lowercase().as_str() {
+                        "optional" => element.required = Some(false),
+                        "required" => element.required = Some(true),
+                        "single" => element.cardinality = Some(SchemaCardinality::One),
+                        "multi" => element.cardinality = Some(SchemaCardinality::Many),
+                        _ => return Err(self.unsupported(node)),
+                    }
+                }
+                let op = match op {
+                    CSTNode::Terminal(Terminal {
+                        kind: Kind::Assign, ..
+                    }) => ShapeOp::ASSIGN,
+                    CSTNode::Terminal(Terminal {
+                        kind: Kind::AddAssign,
+                        ..
+                    }) => ShapeOp::APPEND,
+                    CSTNode::Terminal(Terminal {
+                        kind: Kind::SubAssign,
+                        ..
+                    }) => ShapeOp::SUBTRACT,
+                    _ => return Err(self.unsupported(node)),
+                };
+                element.compexpr = Some(self.boxed_expr(compexpr)?);
+                element.operation = ShapeOperation { op };
+                Ok(element)
+            }
+            _ => Err(self.unsupported(node)),
+        }
+    }
+
+    fn shape_pointer(&self, node: &CSTNode) -> Result<ShapeElement, Error> {
+        let expr = match self.production(node)? {
+            ("ShapePointer", "reduce_ShapePath", [path])
+            | ("SimpleShapePointer", "reduce_SimpleShapePath", [path]) => self.shape_path(path)?,
+            ("FreeSimpleShapePointer", "reduce_FreeStepName", [name]) => Path {
+                steps: vec![self.outbound_ptr(name)?],
+                partial: false,
+            },
+            _ => return Err(self.unsupported(node)),
+        };
+        Ok(ShapeElement {
+            expr,
+            elements: None,
+            compexpr: None,
+            cardinality: None,
+            required: None,
+            operation: ShapeOperation {
+                op: ShapeOp::ASSIGN,
+            },
+            origin: ShapeOrigin::EXPLICIT,
+            r#where: None,
+            orderby: None,
+            offset: None,
+            limit: None,
+        })
+    }
+
+    fn shape_path(&self, node: &CSTNode) -> Result<Path, Error> {
+        let steps = match self.production(node)? {
+            ("ShapePath", "reduce_PathStepName_OptTypeIntersection", [name, intersection]) => {
+                let mut steps = vec![self.outbound_ptr(name)?];
+                if !self.is_empty(intersection) {
+                    let intersection = self.type_intersection(intersection)?;
+                    steps.push(PathSteps::TypeIntersection(intersection));
+                }
+                steps
+            }
+            (
+                "ShapePath",
+                "reduce_TypeIntersection_DOT_PathStepName_OptTypeIntersection",
+                [first, _, name, intersection],
+            ) => {
+                let first = self.type_intersection(first)?;
+                let mut steps = vec![PathSteps::TypeIntersection(first), self.outbound_ptr(name)?];
+                if !self.is_empty(intersection) {
+                    let intersection = self.type_intersection(intersection)?;
+                    steps.push(PathSteps::TypeIntersection(intersection));
+                }
+                steps
+            }
+            ("ShapePath" | "SimpleShapePath", "reduce_AT_PathNodeName", [_, name]) => {
+                vec![PathSteps::Ptr(Ptr {
+                    name: self.object_ref(name)?.name,
+                    direction: None,
+                    r#type: Some("property".to_string()),
+                })]
+            }
+            ("SimpleShapePath", "reduce_PathStepName", [name]) => vec![self.outbound_ptr(name)?],
+            ("Splat", prod, args) => vec![PathSteps::Splat(self.splat(prod, args)?)],
+            _ => return Err(self.unsupported(node)),
+        };
+        Ok(Path {
+            steps,
+            partial: false,
+        })
+    }
+
+    fn outbound_ptr(&self, name: &CSTNode) -> Result<PathSteps, Error> {
+        Ok(PathSteps::Ptr(Ptr {
+            name: self.object_ref(name)?.name,
+            direction: Some(">".to_string()),
+            r#type: None,
+        }))
+    }
+
+    /// Splats are `*` or `**`, optionally preceded by a type and a type
+    /// intersection, as in `Type[is Other].**`.
+    fn splat(&self, prod: &str, args: &[CSTNode]) -> Result<Splat, Error> {
+        let depth = if prod.ends_with("_DOUBLESTAR") || prod == "reduce_DOUBLESTAR" {
+            2
+        } else {
+            1
+        };
+        let (r#type, rest) = match args {
+            [ty, rest @ ..] if prod.starts_with("reduce_ParenTypeExpr") => {
+                (Some(self.type_expr(ty)?), rest)
+            }
+            [name, rest @ ..]
+                if prod.starts_with("reduce_PathStepName")
+                    || prod.starts_with("reduce_PtrQualifiedNodeName") =>
+            {
+                (Some(type_name(self.object_ref(name)?)), rest)
+            }
+            _ => (None, args),
+        };
+        let intersection = match rest {
+            [first, ..] if self.names(first).map(|(nt, _)| nt) == Some("TypeIntersection") => {
+                Some(self.type_intersection(first)?)
+            }
+            _ => None,
+        };
+        Ok(Splat {
+            depth,
+            r#type,
+            intersection,
+        })
+    }
+
+    fn type_intersection(&self, node: &CSTNode) -> Result<TypeIntersection, Error> {
+        let [_, _, ty, _] = self.args(node, "TypeIntersection")? else {
+            return Err(self.unsupported(node));
+        };
+        Ok(TypeIntersection {
+            r#type: self.type_expr(ty)?,
+        })
+    }
+
+    fn type_cast(
+        &self,
+        ty: &CSTNode,
+        expr: &CSTNode,
+        cardinality_mod: Option<CardinalityModifier>,
+    ) -> Result<ExprKind, Error> {
+        Ok(ExprKind::TypeCast(TypeCast {
+            expr: self.boxed_expr(expr)?,
+            r#type: self.type_expr(ty)?,
+            cardinality_mod,
+        }))
+    }
+
+    fn is_op(&self, left: &CSTNode, op: &str, ty: &CSTNode) -> Result<ExprKind, Error> {
+        Ok(ExprKind::IsOp(IsOp {
+            left: self.boxed_expr(left)?,
+            op: op.to_string(),
+            right: self.type_expr(ty)?,
+        }))
+    }
+
+    fn type_expr(&self, node: &CSTNode) -> Result<TypeExpr, Error> {
+        let kind = match self.production(node)? {
+            ("SimpleTypeName", "reduce_PtrNodeName", [name]) => {
+                return Ok(type_name(self.object_ref(name)?));
+            }
+            (
+                "SimpleTypeName",
+                "reduce_ANYTYPE" | "reduce_ANYTUPLE" | "reduce_ANYOBJECT",
+                [name],
+            ) => TypeExprKind::TypeName(TypeName {
+                maintype: BaseObjectRef {
+                    kind: BaseObjectRefKind::PseudoObjectRef(PseudoObjectRef {
+                        name: self.text(name)?.to_lowercase(),
+                    }),
+                },
+                subtypes: None,
+                dimensions: None,
+            }),
+            (
+                "CollectionTypeName",
+                "reduce_NodeName_LANGBRACKET_SubtypeList_RANGBRACKET",
+                [name, _, list, _],
+            ) => {
+                let subtypes = self.list(list, "SubtypeList", |n| self.subtype(n))?;
+                validate_subtypes(&subtypes).map_err(|e| e.with_span(self.span(list)))?;
+                TypeExprKind::TypeName(TypeName {
+                    maintype: BaseObjectRef {
+                        kind: BaseObjectRefKind::ObjectRef(self.object_ref(name)?),
+                    },
+                    subtypes: Some(subtypes),
+                    dimensions: None,
+                })
+            }
+            ("CollectionTypeName", "reduce_NodeName_LANGBRACKET_RANGBRACKET", [_, lt, _]) => {
+                return Err(
+                    Error::new("parametrized type must have at least one argument")
+                        .with_span(self.span(lt)),
+                );
+            }
+            ("FullTypeExpr" | "NontrivialTypeExpr", "reduce_TYPEOF_Expr", [_, expr]) => {
+                TypeExprKind::TypeOf(TypeOf {
+                    expr: self.boxed_expr(expr)?,
+                })
+            }
+            (
+                "FullTypeExpr" | "NontrivialTypeExpr",
+                _,
+                [left, op @ CSTNode::Terminal(_), right],
+            ) => TypeExprKind::TypeOp(TypeOp {
+                left: Box::new(self.type_expr(left)?),
+                op: self.text(op)?,
+                right: Box::new(self.type_expr(right)?),
+            }),
+            _ => return Err(self.unsupported(node)),
+        };
+        Ok(TypeExpr { name: None, kind })
+    }
+
+    fn subtype(&self, node: &CSTNode) -> Result<TypeExpr, Error> {
+        match self.production(node)? {
+            ("Subtype", "reduce_Identifier_COLON_FullTypeExpr", [name, _, ty]) => {
+                let mut ty = self.type_expr(ty)?;
+                ty.name = Some(self.identifier(name)?);
+                Ok(ty)
+            }
+            ("Subtype", "reduce_BaseStringConstant" | "reduce_BaseNumberConstant", [val]) => {
+                let ExprKind::BaseConstant(BaseConstant {
+                    kind: BaseConstantKind::Constant(val),
+                }) = self.expr(val)?.kind
+                else {
+                    return Err(self.unsupported(val));
+                };
+                Ok(TypeExpr {
+                    name: None,
+                    kind: TypeExprKind::TypeExprLiteral(TypeExprLiteral { val }),
+                })
+            }
+            _ => self.type_expr(node),
+        }
+    }
+
+    fn sort_clause(&self, node: &CSTNode) -> Result<Vec<SortExpr>, Error> {
+        self.list(node, "OrderbyList", |n| {
+            let [path, direction, nones_order] = self.args(n, "OrderbyExpr")? else {
+                return Err(self.unsupported(n));
+            };
+            let direction = match self.production(direction)? {
+                (_, "reduce_DESC", _) => SortOrder::Desc,
+                _ => SortOrder::Asc,
+            };
+            let nones_order = match self.production(nones_order)? {
+                (_, "reduce_EMPTY_FIRST", _) => Some(NonesOrder::First),
+                (_, "reduce_EMPTY_LAST", _) => Some(NonesOrder::Last),
+                _ => None,
+            };
+            Ok(SortExpr {
+                path: self.boxed_expr(path)?,
+                direction: Some(direction),
+                nones_order,
+            })
+        })
+    }
+
+    fn select_limit(&self, node: &CSTNode) -> Result<OffsetLimit, Error> {
+        if self.is_empty(node) {
+            return Ok((None, None));
+        }
+        Ok(match self.production(node)? {
+            ("SelectLimit", "reduce_OffsetClause_LimitClause", [offset, limit]) => (
+                Some(self.boxed_expr(offset)?),
+                Some(self.boxed_expr(limit)?),
+            ),
+            ("SelectLimit", "reduce_OffsetClause", [offset]) => {
+                (Some(self.boxed_expr(offset)?), None)
+            }
+            ("SelectLimit", "reduce_LimitClause", [limit]) => (None, Some(self.boxed_expr(limit)?)),
+            _ => return Err(self.unsupported(node)),
+        })
+    }
+
+    fn object_ref(&self, node: &CSTNode) -> Result<ObjectRef, Error> {
+        let (module, name) = match self.production(node)? {
+            ("NodeName", "reduce_BaseName", [name])
+            | ("PtrNodeName", "reduce_PtrName", [name])
+            | ("PtrQualifiedNodeName", "reduce_QualifiedName", [name]) => {
+                let mut parts = self.base_name(name)?;
+                let name = parts.pop().unwrap_or_default();
+                let module = Some(parts.join("::")).filter(|m| !m.is_empty());
+                (module, name)
+            }
+            ("ShortNodeName", "reduce_Identifier", [name])
+            | ("PathNodeName", "reduce_PtrIdentifier", [name])
+            | ("AnyNodeName", "reduce_AnyIdentifier", [name]) => (None, self.identifier(name)?),
+            ("PathStepName" | "FreeStepName" | "PointerName", "reduce_DUNDERTYPE", [name]) => {
+                (None, self.text(name)?)
+            }
+            _ => return Err(self.unsupported(node)),
+        };
+        Ok(ObjectRef {
+            name,
+            module,
+            itemclass: None,
+        })
+    }
+
+    fn base_name(&self, node: &CSTNode) -> Result<Vec<String>, Error> {
+        match self.production(node)? {
+            ("BaseName", "reduce_Identifier", [name])
+            | ("PtrName", "reduce_PtrIdentifier", [name]) => Ok(vec![self.identifier(name)?]),
+            ("QualifiedName", prod, [first, _, rest]) => {
+                let first = match prod {
+                    "reduce_DUNDERSTD_DOUBLECOLON_ColonedIdents" => "__std__".to_string(),
+                    _ => self.identifier(first)?,
+                };
+                let mut parts = vec![first];
+                parts.extend(self.list(rest, "ColonedIdents", |n| self.identifier(n))?);
+                Ok(parts)
+            }
+            _ => Err(self.unsupported(node)),
+        }
+    }
+
+    fn module_name(&self, node: &CSTNode) -> Result<Vec<String>, Error> {
+        self.list(node, "ModuleName", |dot_name| {
+            let [idents] = self.args(dot_name, "DotName")? else {
+                return Err(self.unsupported(dot_name));
+            };
+            let parts = self.list(idents, "DottedIdents", |n| self.identifier(n))?;
+            Ok(parts.join("."))
+        })
+    }
+
+    fn identifier(&self, node: &CSTNode) -> Result<String, Error> {
+        match self.production(node)? {
+            ("Identifier", "reduce_IDENT", [ident]) => self.string_value(ident),
+            ("AnyIdentifier", "reduce_ReservedKeyword", [keyword]) => {
+                let name = self.identifier(keyword)?;
+                if name.starts_with("__") && name.ends_with("__") {
+                    return Err(Error::new(
+                        "identifiers surrounded by double underscores are forbidden",
+                    )
+                    .with_span(self.span(keyword)));
+                }
+                Ok(name)
+            }
+            ("UnreservedKeyword" | "PartialReservedKeyword" | "ReservedKeyword", _, [kw]) => {
+                self.text(kw)
+            }
+            _ => Err(self.unsupported(node)),
+        }
+    }
+
+    /// Collects elements of a list non-terminal (`ListNonterm` in
+    /// `edb/common/parsing.py`), which may be left-recursive, separated and
+    /// may allow trailing separators via an `*Inner` non-terminal.
+    fn list<'n, T>(
+        &self,
+        node: &CSTNode<'n>,
+        list: &str,
+        mut element: impl FnMut(&CSTNode<'n>) -> Result<T, Error>,
+    ) -> Result<Vec<T>, Error> {
+        let mut res = Vec::new();
+        self.collect_list(node, list, &mut element, &mut res)?;
+        Ok(res)
+    }
+
+    fn collect_list<'n, T>(
+        &self,
+        node: &CSTNode<'n>,
+        list: &str,
+        element: &mut impl FnMut(&CSTNode<'n>) -> Result<T, Error>,
+        res: &mut Vec<T>,
+    ) -> Result<(), Error> {
+        let non_term = |n: &CSTNode| self.names(n).map(|(non_term, _)| non_term);
+        let is_list = |n: &CSTNode| {
+            non_term(n).map_or(false, |nt| {
+                nt == list || nt.strip_suffix("Inner") == Some(list)
+            })
+        };
+        if !is_list(node) {
+            res.push(element(node)?);
+            return Ok(());
+        }
+
+        let (_, _, args) = self.production(node)?;
+        match args {
+            [inner] if is_list(inner) => self.collect_list(inner, list, element, res)?,
+            [el] => res.push(element(el)?),
+            [inner, el] if non_term(inner) == non_term(node) => {
+                self.collect_list(inner, list, element, res)?;
+                res.push(element(el)?);
+            }
+            // trailing separator
+            [inner, _] => self.collect_list(inner, list, element, res)?,
+            [inner, _, el] => {
+                self.collect_list(inner, list, element, res)?;
+                res.push(element(el)?);
+            }
+            _ => return Err(self.unsupported(node)),
+        }
+        Ok(())
+    }
+
+    /// Lowers an optional node, which is empty when it was reduced by
+    /// a `reduce_empty` production.
+    fn opt<'n, T>(
+        &self,
+        node: &CSTNode<'n>,
+        f: impl FnOnce(&CSTNode<'n>) -> Result<T, Error>,
+    ) -> Result<Option<T>, Error> {
+        if self.is_empty(node) {
+            Ok(None)
+        } else {
+            f(node).map(Some)
+        }
+    }
+
+    fn is_empty(&self, node: &CSTNode) -> bool {
+        match node {
+            CSTNode::Empty => true,
+            CSTNode::Terminal(_) | CSTNode::Missing(_) => false,
+            CSTNode::Skipped(s) => self.is_empty(&s.node),
+            CSTNode::Production(p) => {
+                p.args.is_empty() || self.spec.production_names[p.id].1 == "reduce_empty"
+            }
+        }
+    }
+
+    fn names(&self, node: &CSTNode) -> Option<(&'s str, &'s str)> {
+        match node {
+            CSTNode::Production(p) => {
+                let (non_term, production) = &self.spec.production_names[p.id];
+                Some((non_term, production))
+            }
+            _ => None,
+        }
+    }
+
+    fn production<'n>(&self, node: &CSTNode<'n>) -> Result<Production<'s, 'n>, Error> {
+        match node {
+            CSTNode::Production(p) => {
+                let (non_term, production) = &self.spec.production_names[p.id];
+                Ok((non_term, production, p.args))
+            }
+            CSTNode::Terminal(t) => Err(Error::new(format!("unexpected {t}")).with_span(t.span)),
+            CSTNode::Missing(t) => Err(Error::new(format!("missing {t}")).with_span(t.span)),
+            CSTNode::Skipped(s) => self.production(&s.node),
+            CSTNode::Empty => Err(Error::new("missing node")),
+        }
+    }
+
+    /// Arguments of a production of the given non-terminal.
+    fn args<'n>(&self, node: &CSTNode<'n>, non_term: &str) -> Result<&'n [CSTNode<'n>], Error> {
+        match self.production(node)? {
+            (nt, _, args) if nt == non_term => Ok(args),
+            _ => Err(self.unsupported(node)),
+        }
+    }
+
+    fn text(&self, node: &CSTNode) -> Result<String, Error> {
+        match node {
+            CSTNode::Terminal(t) => Ok(t.text.clone()),
+            CSTNode::Skipped(s) => self.text(&s.node),
+            _ => Err(self.unsupported(node)),
+        }
+    }
+
+    fn string_value(&self, node: &CSTNode) -> Result<String, Error> {
+        match node {
+            CSTNode::Terminal(Terminal {
+                value: Some(Value::String(value)),
+                ..
+            }) => Ok(value.clone()),
+            CSTNode::Terminal(t) => Ok(t.text.clone()),
+            CSTNode::Skipped(s) => self.string_value(&s.node),
+            _ => Err(self.unsupported(node)),
+        }
+    }
+
+    fn span(&self, node: &CSTNode) -> Span {
+        node.span().unwrap_or_default()
+    }
+
+    fn unsupported(&self, node: &CSTNode) -> Error {
+        let message = match self.names(node) {
+            Some((non_term, production)) => {
+                format!("cannot lower {non_term}.{production} into AST: not supported")
+            }
+            None => "cannot lower node into AST: not supported".to_string(),
+        };
+        Error::new(message).with_span(self.span(node))
+    }
+}
+
+fn select(
+    result_alias: Option<String>,
+    result: Expr,
+    r#where: Option<Box<Expr>>,
+    orderby: Option<Vec<SortExpr>>,
+    offset: Option<Box<Expr>>,
+    limit: Option<Box<Expr>>,
+    implicit: bool,
+) -> Query {
+    if offset.is_some() || limit.is_some() {
+        // OFFSET and LIMIT apply after filtering and ordering
+        let inner = select(result_alias, result, r#where, orderby, None, None, true);
+        let result = Expr {
+            kind: ExprKind::Query(inner),
+        };
+        return select_query(None, result, None, None, offset, limit, implicit);
+    }
+    select_query(result_alias, result, r#where, orderby, None, None, implicit)
+}
+
+fn select_query(
+    result_alias: Option<String>,
+    result: Expr,
+    r#where: Option<Box<Expr>>,
+    orderby: Option<Vec<SortExpr>>,
+    offset: Option<Box<Expr>>,
+    limit: Option<Box<Expr>>,
+    implicit: bool,
+) -> Query {
+    Query {
+        aliases: None,
+        kind: QueryKind::SelectQuery(SelectQuery {
+            result_alias,
+            result: Box::new(result),
+            r#where,
+            orderby,
+            offset,
+            limit,
+            rptr_passthrough: false,
+            implicit,
+        }),
+    }
+}
+
+/// Checks that subtypes of a collection are either all string literals, all
+/// named or all unnamed types.
+fn validate_subtypes(subtypes: &[TypeExpr]) -> Result<(), Error> {
+    let (mut has_strval, mut has_items, mut has_nonstrval) = (false, false, false);
+    for subtype in subtypes {
+        match (&subtype.kind, &subtype.name) {
+            (TypeExprKind::TypeExprLiteral(_), _) => has_strval = true,
+            (TypeExprKind::TypeName(_), Some(_)) => has_items = true,
+            (TypeExprKind::TypeName(_), None) => has_nonstrval = true,
+            _ => {}
+        }
+    }
+    if (has_nonstrval || has_items) && has_strval {
+        return Err(Error::new(
+            "mixing string type literals and type names is not supported",
+        ));
+    }
+    if has_items && has_nonstrval {
+        return Err(Error::new(
+            "mixing named and unnamed subtype declarations is not supported",
+        ));
+    }
+    Ok(())
+}
+
+fn constant(kind: ConstantKind, value: String) -> ExprKind {
+    ExprKind::BaseConstant(BaseConstant {
+        kind: BaseConstantKind::Constant(Constant { kind, value }),
+    })
+}
+
+fn type_name(maintype: ObjectRef) -> TypeExpr {
+    TypeExpr {
+        name: None,
+        kind: TypeExprKind::TypeName(TypeName {
+            maintype: BaseObjectRef {
+                kind: BaseObjectRefKind::ObjectRef(maintype),
+            },
+            subtypes: None,
+            dimensions: None,
+        }),
+    }
+}
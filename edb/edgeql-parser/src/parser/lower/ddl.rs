@@ -0,0 +1,1134 @@
+//! Lowering of DDL commands, mirroring `ddl.py` and `commondl.py` of the
+//! grammar.
+//!
+//! Nested commands are lowered from blocks generated by `commands_block`,
+//! which are named after the statement they belong to, i.e.
+//! `CreateObjectTypeCommandsBlock` holds a `CreateObjectTypeCommandsList`
+//! of `CreateObjectTypeCommand`s.
+
+use indexmap::IndexMap;
+
+use crate::ast::*;
+use crate::parser::CSTNode;
+use crate::position::Span;
+use crate::tokenizer::Error;
+
+use super::{constant, Lowering};
+
+impl<'s> Lowering<'s> {
+    pub(super) fn ddl(&self, node: &CSTNode) -> Result<DDLOperation, Error> {
+        let op = match self.production(node)? {
+            ("CreateModuleStmt", _, [_, _, name, if_not_exists, commands]) => {
+                let create = CreateObject {
+                    r#abstract: false,
+                    sdl_alter_if_exists: false,
+                    create_if_not_exists: !self.is_empty(if_not_exists),
+                    kind: Some(CreateObjectKind::CreateModule(CreateModule {})),
+                };
+                object_ddl(
+                    self.module_ref(name)?,
+                    ObjectDDLKind::CreateObject(create),
+                    self.commands(commands)?,
+                )
+            }
+            ("AlterModuleStmt", _, [_, _, name, commands]) => object_ddl(
+                self.module_ref(name)?,
+                alter(AlterObjectKind::AlterModule(AlterModule {})),
+                self.commands(commands)?,
+            ),
+            ("DropModuleStmt", _, [_, _, name]) => object_ddl(
+                self.module_ref(name)?,
+                drop_object(DropObjectKind::DropModule(DropModule {})),
+                vec![],
+            ),
+
+            ("CreatePseudoTypeStmt", _, [_, _, _, name, commands]) => object_ddl(
+                self.object_ref(name)?,
+                create(
+                    false,
+                    CreateObjectKind::CreatePseudoType(CreatePseudoType {}),
+                ),
+                self.commands(commands)?,
+            ),
+
+            ("CreateScalarTypeStmt", prod, [.., name, bases, commands]) => object_ddl(
+                self.object_ref(name)?,
+                create_extending(
+                    prod == "reduce_CreateAbstractScalarTypeStmt",
+                    prod == "reduce_CreateFinalScalarTypeStmt",
+                    self.bases(bases)?,
+                    CreateExtendingObjectKind::CreateScalarType(CreateScalarType {}),
+                ),
+                self.commands(commands)?,
+            ),
+            ("AlterScalarTypeStmt", _, [_, _, _, name, commands]) => object_ddl(
+                self.object_ref(name)?,
+                alter(AlterObjectKind::AlterScalarType(AlterScalarType {})),
+                self.commands(commands)?,
+            ),
+            ("DropScalarTypeStmt", _, [_, _, _, name]) => object_ddl(
+                self.object_ref(name)?,
+                drop_object(DropObjectKind::DropScalarType(DropScalarType {})),
+                vec![],
+            ),
+
+            ("CreateAnnotationStmt", prod, [.., name, commands]) => {
+                let annotation = CreateAnnotation {
+                    r#type: None,
+                    inheritable: prod == "reduce_CreateInheritableAnnotation",
+                };
+                object_ddl(
+                    self.object_ref(name)?,
+                    create_extending(
+                        false,
+                        false,
+                        vec![],
+                        CreateExtendingObjectKind::CreateAnnotation(annotation),
+                    ),
+                    self.commands(commands)?,
+                )
+            }
+            ("AlterAnnotationStmt", _, [_, _, _, name, commands]) => object_ddl(
+                self.object_ref(name)?,
+                alter(AlterObjectKind::AlterAnnotation(AlterAnnotation {})),
+                self.commands(commands)?,
+            ),
+            ("DropAnnotationStmt", _, [_, _, _, name]) => object_ddl(
+                self.object_ref(name)?,
+                drop_object(DropObjectKind::DropAnnotation(DropAnnotation {})),
+                vec![],
+            ),
+
+            ("CreateObjectTypeStmt", prod, [.., name, bases, commands]) => object_ddl(
+                self.object_ref(name)?,
+                create_extending(
+                    prod == "reduce_CreateAbstractObjectTypeStmt",
+                    false,
+                    self.bases(bases)?,
+                    CreateExtendingObjectKind::CreateObjectType(CreateObjectType {}),
+                ),
+                self.commands(commands)?,
+            ),
+            ("AlterObjectTypeStmt", _, [_, _, name, commands]) => object_ddl(
+                self.object_ref(name)?,
+                alter(AlterObjectKind::AlterObjectType(AlterObjectType {})),
+                self.commands(commands)?,
+            ),
+            ("DropObjectTypeStmt", _, [_, _, name, commands]) => object_ddl(
+                self.object_ref(name)?,
+                drop_object(DropObjectKind::DropObjectType(DropObjectType {})),
+                self.commands(commands)?,
+            ),
+
+            ("CreatePropertyStmt", _, [_, _, _, name, bases, commands]) => {
+                let (bases, commands) = self.extract_bases(bases, commands)?;
+                object_ddl(
+                    self.object_ref(name)?,
+                    create_extending(
+                        true,
+                        false,
+                        bases,
+                        CreateExtendingObjectKind::CreateProperty(CreateProperty {}),
+                    ),
+                    commands,
+                )
+            }
+            ("AlterPropertyStmt", _, [_, _, _, name, commands]) => object_ddl(
+                self.object_ref(name)?,
+                alter(AlterObjectKind::AlterProperty(AlterProperty {})),
+                self.commands(commands)?,
+            ),
+            ("DropPropertyStmt", _, [_, _, _, name]) => object_ddl(
+                self.object_ref(name)?,
+                drop_object(DropObjectKind::DropProperty(DropProperty {})),
+                vec![],
+            ),
+            ("CreateConcretePropertyStmt", _, args) => self.create_concrete_pointer(
+                args,
+                "property",
+                CreateConcretePointerKind::CreateConcreteProperty(CreateConcreteProperty {}),
+            )?,
+            ("AlterConcretePropertyStmt", _, [_, _, name, commands]) => object_ddl(
+                self.pointer_name(name)?,
+                alter(AlterObjectKind::AlterConcreteProperty(
+                    AlterConcreteProperty {},
+                )),
+                self.commands(commands)?,
+            ),
+            ("DropConcretePropertyStmt", _, [_, _, name]) => object_ddl(
+                self.pointer_name(name)?,
+                drop_object(DropObjectKind::DropConcreteProperty(
+                    DropConcreteProperty {},
+                )),
+                vec![],
+            ),
+
+            ("CreateLinkStmt", _, [_, _, _, name, bases, commands]) => {
+                let (bases, commands) = self.extract_bases(bases, commands)?;
+                object_ddl(
+                    self.object_ref(name)?,
+                    create_extending(
+                        true,
+                        false,
+                        bases,
+                        CreateExtendingObjectKind::CreateLink(CreateLink {}),
+                    ),
+                    commands,
+                )
+            }
+            ("AlterLinkStmt", _, [_, _, _, name, commands]) => object_ddl(
+                self.object_ref(name)?,
+                alter(AlterObjectKind::AlterLink(AlterLink {})),
+                self.commands(commands)?,
+            ),
+            ("DropLinkStmt", _, [_, _, _, name, commands]) => object_ddl(
+                self.object_ref(name)?,
+                drop_object(DropObjectKind::DropLink(DropLink {})),
+                self.commands(commands)?,
+            ),
+            ("CreateConcreteLinkStmt", _, args) => self.create_concrete_pointer(
+                args,
+                "link",
+                CreateConcretePointerKind::CreateConcreteLink(CreateConcreteLink {}),
+            )?,
+            ("AlterConcreteLinkStmt", _, [_, _, name, commands]) => object_ddl(
+                self.pointer_name(name)?,
+                alter(AlterObjectKind::AlterConcreteLink(AlterConcreteLink {})),
+                self.commands(commands)?,
+            ),
+            ("DropConcreteLinkStmt", _, [_, _, name, commands]) => object_ddl(
+                self.pointer_name(name)?,
+                drop_object(DropObjectKind::DropConcreteLink(DropConcreteLink {})),
+                self.commands(commands)?,
+            ),
+
+            ("CreateAliasStmt", "reduce_CreateAliasShortStmt", [_, _, name, _, expr]) => {
+                let expr = SetFieldValue::Expr(self.boxed_expr(expr)?);
+                object_ddl(
+                    self.object_ref(name)?,
+                    create(false, CreateObjectKind::CreateAlias(CreateAlias {})),
+                    vec![set_field("expr", expr, true, None)],
+                )
+            }
+            ("CreateAliasStmt", "reduce_CreateAliasRegularStmt", [_, _, name, commands]) => {
+                object_ddl(
+                    self.object_ref(name)?,
+                    create(false, CreateObjectKind::CreateAlias(CreateAlias {})),
+                    self.commands(commands)?,
+                )
+            }
+            ("AlterAliasStmt", _, [_, _, name, commands]) => object_ddl(
+                self.object_ref(name)?,
+                alter(AlterObjectKind::AlterAlias(AlterAlias {})),
+                self.commands(commands)?,
+            ),
+            ("DropAliasStmt", _, [_, _, name]) => object_ddl(
+                self.object_ref(name)?,
+                drop_object(DropObjectKind::DropAlias(DropAlias {})),
+                vec![],
+            ),
+
+            ("CreateGlobalStmt", _, args) => self.create_global(args)?,
+            ("AlterGlobalStmt", _, [_, _, name, commands]) => object_ddl(
+                self.object_ref(name)?,
+                alter(AlterObjectKind::AlterGlobal(AlterGlobal {})),
+                self.commands(commands)?,
+            ),
+            ("DropGlobalStmt", _, [_, _, name]) => object_ddl(
+                self.object_ref(name)?,
+                drop_object(DropObjectKind::DropGlobal(DropGlobal {})),
+                vec![],
+            ),
+
+            ("CreateConstraintStmt", _, [_, _, _, name, rest @ ..]) => {
+                let (params, on, bases, commands) = match rest {
+                    [params, on, bases, commands] => {
+                        (self.func_params(params)?, on, bases, commands)
+                    }
+                    [on, bases, commands] => (vec![], on, bases, commands),
+                    _ => return Err(self.unsupported(node)),
+                };
+                let constraint = CreateConstraint {
+                    r#abstract: true,
+                    subjectexpr: self.opt(on, |n| self.boxed_expr(n))?,
+                    params,
+                };
+                object_ddl(
+                    self.object_ref(name)?,
+                    create_extending(
+                        true,
+                        false,
+                        self.bases(bases)?,
+                        CreateExtendingObjectKind::CreateConstraint(constraint),
+                    ),
+                    self.commands(commands)?,
+                )
+            }
+            ("AlterConstraintStmt", _, [_, _, _, name, commands]) => object_ddl(
+                self.object_ref(name)?,
+                alter(AlterObjectKind::AlterConstraint(AlterConstraint {})),
+                self.commands(commands)?,
+            ),
+            ("DropConstraintStmt", _, [_, _, _, name]) => object_ddl(
+                self.object_ref(name)?,
+                drop_object(DropObjectKind::DropConstraint(DropConstraint {})),
+                vec![],
+            ),
+            (
+                "CreateConcreteConstraintStmt",
+                _,
+                [_, delegated, _, name, args, on, except, commands],
+            ) => {
+                let constraint = CreateConcreteConstraint {
+                    delegated: !self.is_empty(delegated),
+                    args: self.constraint_args(args)?,
+                    subjectexpr: self.opt(on, |n| self.boxed_expr(n))?,
+                    except_expr: self.opt(except, |n| self.boxed_expr(n))?,
+                };
+                object_ddl(
+                    self.object_ref(name)?,
+                    create(
+                        false,
+                        CreateObjectKind::CreateConcreteConstraint(constraint),
+                    ),
+                    self.commands(commands)?,
+                )
+            }
+            ("AlterConcreteConstraintStmt", _, [_, _, name, args, on, except, commands]) => {
+                let constraint = AlterConcreteConstraint {
+                    args: self.constraint_args(args)?,
+                    subjectexpr: self.opt(on, |n| self.boxed_expr(n))?,
+                    except_expr: self.opt(except, |n| self.boxed_expr(n))?,
+                };
+                object_ddl(
+                    self.object_ref(name)?,
+                    alter(AlterObjectKind::AlterConcreteConstraint(constraint)),
+                    self.commands(commands)?,
+                )
+            }
+            ("DropConcreteConstraintStmt", _, [_, _, name, args, on, except]) => {
+                let constraint = DropConcreteConstraint {
+                    args: self.constraint_args(args)?,
+                    subjectexpr: self.opt(on, |n| self.boxed_expr(n))?,
+                    except_expr: self.opt(except, |n| self.boxed_expr(n))?,
+                };
+                object_ddl(
+                    self.object_ref(name)?,
+                    drop_object(DropObjectKind::DropConcreteConstraint(constraint)),
+                    vec![],
+                )
+            }
+
+            ("CreateConcreteIndexStmt", _, [_, deferred, _, rest @ ..]) => {
+                let (name, kwargs, on, except, commands) = self.concrete_index(node, rest)?;
+                let index = CreateConcreteIndex {
+                    kwargs,
+                    expr: self.boxed_expr(on)?,
+                    except_expr: self.opt(except, |n| self.boxed_expr(n))?,
+                    deferred: (!self.is_empty(deferred)).then_some(true),
+                };
+                object_ddl(
+                    name,
+                    create(false, CreateObjectKind::CreateConcreteIndex(index)),
+                    self.commands(commands)?,
+                )
+            }
+            ("AlterConcreteIndexStmt", _, [_, _, rest @ ..]) => {
+                let (name, kwargs, on, except, commands) = self.concrete_index(node, rest)?;
+                let index = AlterConcreteIndex {
+                    kwargs,
+                    expr: self.boxed_expr(on)?,
+                    except_expr: self.opt(except, |n| self.boxed_expr(n))?,
+                    deferred: None,
+                };
+                object_ddl(
+                    name,
+                    alter(AlterObjectKind::AlterConcreteIndex(index)),
+                    self.commands(commands)?,
+                )
+            }
+            ("DropConcreteIndexStmt", _, [_, _, rest @ ..]) => {
+                let (name, kwargs, on, except, commands) = self.concrete_index(node, rest)?;
+                let index = DropConcreteIndex {
+                    kwargs,
+                    expr: self.boxed_expr(on)?,
+                    except_expr: self.opt(except, |n| self.boxed_expr(n))?,
+                    deferred: None,
+                };
+                object_ddl(
+                    name,
+                    drop_object(DropObjectKind::DropConcreteIndex(index)),
+                    self.commands(commands)?,
+                )
+            }
+
+            ("CreateAnnotationValueStmt", _, [_, _, name, _, value]) => {
+                let value = CreateAnnotationValue {
+                    value: self.boxed_expr(value)?,
+                };
+                object_ddl(
+                    self.object_ref(name)?,
+                    create(false, CreateObjectKind::CreateAnnotationValue(value)),
+                    vec![],
+                )
+            }
+            (
+                "AlterAnnotationValueStmt",
+                "reduce_ALTER_ANNOTATION_NodeName_ASSIGN_Expr",
+                [_, _, name, _, value],
+            ) => {
+                let value = AlterAnnotationValue {
+                    value: Some(self.boxed_expr(value)?),
+                };
+                object_ddl(
+                    self.object_ref(name)?,
+                    alter(AlterObjectKind::AlterAnnotationValue(value)),
+                    vec![],
+                )
+            }
+            (
+                "AlterAnnotationValueStmt",
+                "reduce_ALTER_ANNOTATION_NodeName_DROP_OWNED",
+                [_, _, name, _, _],
+            ) => object_ddl(
+                self.object_ref(name)?,
+                alter(AlterObjectKind::AlterAnnotationValue(
+                    AlterAnnotationValue { value: None },
+                )),
+                vec![set_field("owned", bool_value(false), true, None)],
+            ),
+            ("DropAnnotationValueStmt", _, [_, _, name]) => object_ddl(
+                self.object_ref(name)?,
+                drop_object(DropObjectKind::DropAnnotationValue(DropAnnotationValue {})),
+                vec![],
+            ),
+
+            ("RenameStmt", _, [_, _, name]) => {
+                let new_name = self.object_ref(name)?;
+                named_ddl(
+                    new_name.clone(),
+                    NamedDDLKind::Rename(Rename { new_name }),
+                    vec![],
+                )
+            }
+
+            ("UsingStmt", "reduce_USING_ParenExpr", [_, expr]) => {
+                let expr = SetFieldValue::Expr(self.boxed_expr(expr)?);
+                set_field("expr", expr, true, None)
+            }
+            ("UsingStmt", "reduce_RESET_EXPRESSION", _) => {
+                set_field("expr", SetFieldValue::NoneType(()), true, None)
+            }
+            ("SetFieldStmt", _, [_, name, _, value]) => {
+                let name = self.identifier(name)?.to_lowercase();
+                let value = SetFieldValue::Expr(self.boxed_expr(value)?);
+                set_field(&name, value, false, None)
+            }
+            ("ResetFieldStmt", "reduce_RESET_IDENT", [_, name]) => {
+                let name = self.string_value(name)?.to_lowercase();
+                set_field(&name, SetFieldValue::NoneType(()), false, None)
+            }
+            ("ResetFieldStmt", "reduce_RESET_DEFAULT", _) => {
+                set_field("default", SetFieldValue::NoneType(()), false, None)
+            }
+
+            ("AlterAbstract", prod, _) => {
+                let value = match prod {
+                    "reduce_SET_ABSTRACT" => bool_value(true),
+                    "reduce_RESET_ABSTRACT" => SetFieldValue::NoneType(()),
+                    _ => bool_value(false),
+                };
+                set_field("abstract", value, true, None)
+            }
+            ("AlterOwnedStmt", prod, _) => {
+                set_field("owned", bool_value(prod == "reduce_SET_OWNED"), true, None)
+            }
+            ("SetDelegatedStmt", prod, _) => {
+                let value = match prod {
+                    "reduce_SET_DELEGATED" => bool_value(true),
+                    "reduce_RESET_DELEGATED" => SetFieldValue::NoneType(()),
+                    _ => bool_value(false),
+                };
+                set_field("delegated", value, true, None)
+            }
+            ("AlterDeferredStmt", prod, _) => set_field(
+                "deferred",
+                bool_value(prod == "reduce_SET_DEFERRED"),
+                true,
+                None,
+            ),
+
+            ("CreateSimpleExtending", _, [_, bases]) => {
+                operation(DDLOperationKind::AlterAddInherit(AlterAddInherit {
+                    position: None,
+                    bases: self.bases(bases)?,
+                }))
+            }
+            (
+                "AlterSimpleExtending" | "AlterExtending",
+                "reduce_EXTENDING_SimpleTypeNameList_OptPosition"
+                | "reduce_EXTENDING_TypeNameList_OptPosition",
+                [_, bases, position],
+            ) => operation(DDLOperationKind::AlterAddInherit(AlterAddInherit {
+                position: self.opt(position, |n| self.position(n))?,
+                bases: self.bases(bases)?,
+            })),
+            ("AlterSimpleExtending" | "AlterExtending", _, [_, _, bases]) => {
+                operation(DDLOperationKind::AlterDropInherit(AlterDropInherit {
+                    bases: self.bases(bases)?,
+                }))
+            }
+
+            ("SetRequiredInCreateStmt" | "SetRequiredStmt", prod, args) => {
+                let (value, fill_expr) = match (prod, args) {
+                    ("reduce_SET_REQUIRED_OptAlterUsingClause", [_, _, using]) => {
+                        (bool_value(true), self.opt(using, |n| self.boxed_expr(n))?)
+                    }
+                    ("reduce_RESET_OPTIONALITY", _) => (SetFieldValue::NoneType(()), None),
+                    _ => (bool_value(false), None),
+                };
+                let kind = SetFieldKind::SetPointerOptionality(SetPointerOptionality {
+                    name: "required".to_string(),
+                    special_syntax: true,
+                    fill_expr,
+                });
+                set_field("required", value, true, Some(kind))
+            }
+            ("SetCardinalityStmt", prod, args) => {
+                let (value, conv_expr) = match (prod, args) {
+                    ("reduce_SET_SINGLE_OptAlterUsingClause", [_, _, using]) => {
+                        let value = string_value("One");
+                        (value, self.opt(using, |n| self.boxed_expr(n))?)
+                    }
+                    ("reduce_SET_MULTI", _) => (string_value("Many"), None),
+                    ("reduce_RESET_CARDINALITY_OptAlterUsingClause", [_, _, using]) => {
+                        let conv_expr = self.opt(using, |n| self.boxed_expr(n))?;
+                        (SetFieldValue::NoneType(()), conv_expr)
+                    }
+                    _ => return Err(self.unsupported(node)),
+                };
+                let kind = SetFieldKind::SetPointerCardinality(SetPointerCardinality {
+                    name: "cardinality".to_string(),
+                    special_syntax: true,
+                    conv_expr,
+                });
+                set_field("cardinality", value, true, Some(kind))
+            }
+            ("SetPointerTypeStmt", prod, args) => {
+                let (value, cast_expr) = match (prod, args) {
+                    ("reduce_SETTYPE_FullTypeExpr_OptAlterUsingClause", [_, ty, using]) => (
+                        Some(self.type_expr(ty)?),
+                        self.opt(using, |n| self.boxed_expr(n))?,
+                    ),
+                    ("reduce_RESET_TYPE", _) => (None, None),
+                    _ => return Err(self.unsupported(node)),
+                };
+                let kind = SetFieldKind::SetPointerType(SetPointerType {
+                    name: "target".to_string(),
+                    value: value.clone(),
+                    special_syntax: true,
+                    cast_expr,
+                });
+                set_field("target", type_value(value), true, Some(kind))
+            }
+            ("SetGlobalTypeStmt", prod, args) => {
+                let (value, cast_expr, reset_value) = match (prod, args) {
+                    ("reduce_SETTYPE_FullTypeExpr_OptAlterUsingClause", [_, ty, using]) => (
+                        Some(self.type_expr(ty)?),
+                        self.opt(using, |n| self.boxed_expr(n))?,
+                        false,
+                    ),
+                    ("reduce_SETTYPE_FullTypeExpr_RESET_TO_DEFAULT", [_, ty, ..]) => {
+                        (Some(self.type_expr(ty)?), None, true)
+                    }
+                    ("reduce_RESET_TYPE", _) => (None, None, false),
+                    _ => return Err(self.unsupported(node)),
+                };
+                let kind = SetFieldKind::SetGlobalType(SetGlobalType {
+                    name: "target".to_string(),
+                    value: value.clone(),
+                    special_syntax: true,
+                    cast_expr,
+                    reset_value,
+                });
+                set_field("target", type_value(value), true, Some(kind))
+            }
+
+            ("OnTargetDeleteStmt" | "OnTargetDeleteResetStmt", prod, _) => {
+                let cascade = match prod {
+                    "reduce_ON_TARGET_DELETE_RESTRICT" => Some(LinkTargetDeleteAction::Restrict),
+                    "reduce_ON_TARGET_DELETE_DELETE_SOURCE" => {
+                        Some(LinkTargetDeleteAction::DeleteSource)
+                    }
+                    "reduce_ON_TARGET_DELETE_ALLOW" => Some(LinkTargetDeleteAction::Allow),
+                    "reduce_ON_TARGET_DELETE_DEFERRED_RESTRICT" => {
+                        Some(LinkTargetDeleteAction::DeferredRestrict)
+                    }
+                    _ => None,
+                };
+                operation(DDLOperationKind::OnTargetDelete(OnTargetDelete { cascade }))
+            }
+            ("OnSourceDeleteStmt" | "OnSourceDeleteResetStmt", prod, _) => {
+                let cascade = match prod {
+                    "reduce_ON_SOURCE_DELETE_DELETE_TARGET" => {
+                        Some(LinkSourceDeleteAction::DeleteTarget)
+                    }
+                    "reduce_ON_SOURCE_DELETE_ALLOW" => Some(LinkSourceDeleteAction::Allow),
+                    "reduce_ON_SOURCE_DELETE_DELETE_TARGET_IF_ORPHAN" => {
+                        Some(LinkSourceDeleteAction::DeleteTargetIfOrphan)
+                    }
+                    _ => None,
+                };
+                operation(DDLOperationKind::OnSourceDelete(OnSourceDelete { cascade }))
+            }
+
+            _ => return Err(self.unsupported(node)),
+        };
+        Ok(op)
+    }
+
+    /// Lowers a (possibly optional) commands block into its commands.
+    fn commands(&self, node: &CSTNode) -> Result<Vec<DDLOperation>, Error> {
+        let commands = self.commands_with_spans(node)?;
+        Ok(commands.into_iter().map(|(command, _)| command).collect())
+    }
+
+    fn commands_with_spans(&self, node: &CSTNode) -> Result<Vec<(DDLOperation, Span)>, Error> {
+        if self.is_empty(node) {
+            return Ok(Vec::new());
+        }
+        let (non_term, _, args) = self.production(node)?;
+        if let (Some(_), [block]) = (non_term.strip_prefix("Opt"), args) {
+            return self.commands_with_spans(block);
+        }
+        let Some(parent) = non_term.strip_suffix("CommandsBlock") else {
+            return Err(self.unsupported(node));
+        };
+
+        let command = format!("{parent}Command");
+        let list = format!("{parent}CommandsList");
+        let non_term = |n: &CSTNode| self.names(n).map(|(non_term, _)| non_term);
+        let lower_command = |n: &CSTNode| match self.production(n)? {
+            (nt, _, [inner]) if nt == command => Ok((self.ddl(inner)?, self.span(n))),
+            _ => Err(self.unsupported(n)),
+        };
+        match args {
+            [cmd] if non_term(cmd) == Some(&command) => Ok(vec![lower_command(cmd)?]),
+            _ => match args.iter().find(|n| non_term(n) == Some(&list)) {
+                Some(cmds) => self.list(cmds, &list, lower_command),
+                None => Ok(Vec::new()),
+            },
+        }
+    }
+
+    /// Moves `EXTENDING` of a commands block into bases, as does
+    /// `commondl.extract_bases`.
+    fn extract_bases(
+        &self,
+        bases: &CSTNode,
+        commands: &CSTNode,
+    ) -> Result<(Vec<TypeExpr>, Vec<DDLOperation>), Error> {
+        let mut bases = self.bases(bases)?;
+        let mut res = Vec::new();
+        for (command, span) in self.commands_with_spans(commands)? {
+            match command.kind {
+                DDLOperationKind::AlterAddInherit(inherit) => {
+                    if !bases.is_empty() {
+                        return Err(
+                            Error::new("specifying EXTENDING twice is not allowed").with_span(span)
+                        );
+                    }
+                    bases = inherit.bases;
+                }
+                _ => res.push(command),
+            }
+        }
+        Ok((bases, res))
+    }
+
+    fn create_concrete_pointer(
+        &self,
+        args: &[CSTNode],
+        kind_name: &str,
+        kind: CreateConcretePointerKind,
+    ) -> Result<DDLOperation, Error> {
+        let (quals, name, bases, target, commands) = match args {
+            [_, quals, _, name, bases, _, target, commands] => {
+                let (bases, commands) = self.extract_bases(bases, commands)?;
+                let target = CreateConcretePointerTarget::TypeExpr(self.type_expr(target)?);
+                (quals, name, bases, target, commands)
+            }
+            [_, quals, _, name, _, expr] => {
+                let target = CreateConcretePointerTarget::Expr(self.boxed_expr(expr)?);
+                (quals, name, vec![], target, vec![])
+            }
+            [_, quals, _, name, commands] => {
+                let commands = self.commands(commands)?;
+                if (commands.iter()).any(|c| matches!(c.kind, DDLOperationKind::AlterAddInherit(_)))
+                {
+                    let message = format!("computed {kind_name} cannot specify EXTENDING");
+                    return Err(Error::new(message).with_span(self.span(name)));
+                }
+                let target = self.computed_target(&commands, kind_name, name)?;
+                let target = CreateConcretePointerTarget::Expr(target);
+                (quals, name, vec![], target, commands)
+            }
+            _ => return Err(Error::new("cannot lower pointer into AST: not supported")),
+        };
+        let (is_required, cardinality) = self.ptr_quals(quals)?;
+        let pointer = CreateConcretePointer {
+            is_required,
+            declared_overloaded: false,
+            target,
+            cardinality,
+            bases,
+            kind: Some(kind),
+        };
+        Ok(object_ddl(
+            self.pointer_name(name)?,
+            create(false, CreateObjectKind::CreateConcretePointer(pointer)),
+            commands,
+        ))
+    }
+
+    fn create_global(&self, args: &[CSTNode]) -> Result<DDLOperation, Error> {
+        let (quals, name, target, commands) = match args {
+            [_, quals, _, name, _, target, commands] => {
+                let target = CreateGlobalTarget::TypeExpr(self.type_expr(target)?);
+                (quals, name, target, self.commands(commands)?)
+            }
+            [_, quals, _, name, _, expr] => {
+                let target = CreateGlobalTarget::Expr(self.boxed_expr(expr)?);
+                (quals, name, target, vec![])
+            }
+            [_, quals, _, name, commands] => {
+                let commands = self.commands(commands)?;
+                let target = self.computed_target(&commands, "global", name)?;
+                let target = CreateGlobalTarget::Expr(target);
+                (quals, name, target, commands)
+            }
+            _ => return Err(Error::new("cannot lower global into AST: not supported")),
+        };
+        let (is_required, cardinality) = self.ptr_quals(quals)?;
+        let global = CreateGlobal {
+            is_required,
+            target,
+            cardinality,
+        };
+        Ok(object_ddl(
+            self.object_ref(name)?,
+            create(false, CreateObjectKind::CreateGlobal(global)),
+            commands,
+        ))
+    }
+
+    /// Expression of a computed pointer or global, which is given by
+    /// `USING (...)` in its commands block.
+    fn computed_target(
+        &self,
+        commands: &[DDLOperation],
+        kind_name: &str,
+        name: &CSTNode,
+    ) -> Result<Box<Expr>, Error> {
+        let mut target = None;
+        for command in commands {
+            let DDLOperationKind::SetField(field) = &command.kind else {
+                continue;
+            };
+            if field.name != "expr" {
+                continue;
+            }
+            if target.is_some() {
+                let message = format!("computed {kind_name} with more than one expression");
+                return Err(Error::new(message).with_span(self.span(name)));
+            }
+            target = match &field.value {
+                SetFieldValue::Expr(expr) => Some(expr.clone()),
+                _ => None,
+            };
+        }
+        target.ok_or_else(|| {
+            let message = format!("computed {kind_name} without expression");
+            Error::new(message).with_span(self.span(name))
+        })
+    }
+
+    /// Name, keyword arguments, expression, EXCEPT expression and commands
+    /// of a concrete index, which is named `__::idx` if no name is given.
+    #[allow(clippy::type_complexity)]
+    fn concrete_index<'n>(
+        &self,
+        node: &CSTNode,
+        args: &'n [CSTNode<'n>],
+    ) -> Result<
+        (
+            ObjectRef,
+            IndexMap<String, Box<Expr>>,
+            &'n CSTNode<'n>,
+            &'n CSTNode<'n>,
+            &'n CSTNode<'n>,
+        ),
+        Error,
+    > {
+        match args {
+            [on, except, commands] => {
+                let name = ObjectRef {
+                    name: "idx".to_string(),
+                    module: Some("__".to_string()),
+                    itemclass: None,
+                };
+                Ok((name, IndexMap::new(), on, except, commands))
+            }
+            [name, args, on, except, commands] => {
+                let kwargs = self.index_kwargs(args)?;
+                Ok((self.object_ref(name)?, kwargs, on, except, commands))
+            }
+            _ => Err(self.unsupported(node)),
+        }
+    }
+
+    /// Keyword arguments of a concrete index, validated as
+    /// `ProcessIndexMixin._process_arguments` does.
+    fn index_kwargs(&self, node: &CSTNode) -> Result<IndexMap<String, Box<Expr>>, Error> {
+        let mut kwargs = IndexMap::new();
+        if self.is_empty(node) {
+            return Ok(kwargs);
+        }
+        // values are None for definitions of new parameters
+        let args = self.list(node, "IndexArgList", |arg| match self.production(arg)? {
+            ("IndexArg", "reduce_AnyIdentifier_ASSIGN_Expr", [name, _, value]) => Ok((
+                self.identifier(name)?,
+                self.span(name),
+                Some(self.boxed_expr(value)?),
+            )),
+            ("IndexArg", "reduce_kwarg_definition", [kind, name, ..]) => {
+                if self.production(kind)?.1 != "reduce_NAMEDONLY" {
+                    return Err(Error::new("index parameters have to be NAMED ONLY")
+                        .with_span(self.span(kind)));
+                }
+                Ok((self.func_param_name(name)?, self.span(arg), None))
+            }
+            ("IndexArg", "reduce_kwarg_bad_definition", [name, ..]) => {
+                Err(Error::new("index parameters have to be NAMED ONLY").with_span(self.span(name)))
+            }
+            ("IndexArg", "reduce_FuncDeclArgName_OptDefault", [name, _]) => {
+                Err(self.missing_param_type(name)?)
+            }
+            _ => Err(self.unsupported(arg)),
+        })?;
+        for (name, span, value) in args {
+            let Some(value) = value else {
+                let message = format!("unexpected new parameter definition `{name}`");
+                return Err(Error::new(message).with_span(span));
+            };
+            if kwargs.contains_key(&name) {
+                let message = format!("duplicate named argument `{name}`");
+                return Err(Error::new(message).with_span(span));
+            }
+            kwargs.insert(name, value);
+        }
+        Ok(kwargs)
+    }
+
+    #[allow(clippy::vec_box)] // that's what the AST holds
+    fn constraint_args(&self, node: &CSTNode) -> Result<Vec<Box<Expr>>, Error> {
+        if self.is_empty(node) {
+            return Ok(Vec::new());
+        }
+        self.list(node, "PosCallArgList", |arg| {
+            let [value, filter, order] = self.args(arg, "PosCallArg")? else {
+                return Err(self.unsupported(arg));
+            };
+            let value = self.boxed_expr(value)?;
+            if self.is_empty(filter) && self.is_empty(order) {
+                return Ok(value);
+            }
+            let filter = self.opt(filter, |n| self.boxed_expr(n))?;
+            let order = self.opt(order, |n| self.sort_clause(n))?;
+            let query = super::select(None, *value, filter, order, None, None, true);
+            Ok(Box::new(Expr {
+                kind: ExprKind::Query(query),
+            }))
+        })
+    }
+
+    /// Lowers `CreateFunctionArgs` and validates them as
+    /// `ProcessFunctionParamsMixin` does.
+    fn func_params(&self, node: &CSTNode) -> Result<Vec<FuncParam>, Error> {
+        let args = match self.production(node)? {
+            ("CreateFunctionArgs", "reduce_LPAREN_RPAREN", _) => return Ok(Vec::new()),
+            ("CreateFunctionArgs", _, [_, args, _]) => args,
+            _ => return Err(self.unsupported(node)),
+        };
+        let params = self.list(args, "FuncDeclArgList", |arg| {
+            match self.production(arg)? {
+                ("FuncDeclArg", "reduce_kwarg", [kind, name, _, typemod, ty, default]) => {
+                    let kind = match self.production(kind) {
+                        Ok((_, "reduce_VARIADIC", _)) => ParameterKind::VariadicParam,
+                        Ok((_, "reduce_NAMEDONLY", _)) => ParameterKind::NamedOnlyParam,
+                        _ => ParameterKind::PositionalParam,
+                    };
+                    let typemod = match self.production(typemod) {
+                        Ok((_, "reduce_SET_OF", _)) => TypeModifier::SetOfType,
+                        Ok((_, "reduce_OPTIONAL", _)) => TypeModifier::OptionalType,
+                        _ => TypeModifier::SingletonType,
+                    };
+                    let param = FuncParam {
+                        name: self.func_param_name(name)?,
+                        r#type: self.type_expr(ty)?,
+                        typemod,
+                        kind,
+                        default: self.opt(default, |n| self.boxed_expr(n))?,
+                    };
+                    Ok((param, self.span(arg)))
+                }
+                ("FuncDeclArg", _, [_, name, _]) => Err(self.missing_param_type(name)?),
+                _ => Err(self.unsupported(arg)),
+            }
+        })?;
+        validate_params(&params)?;
+        Ok(params.into_iter().map(|(param, _)| param).collect())
+    }
+
+    fn func_param_name(&self, node: &CSTNode) -> Result<String, Error> {
+        match self.production(node)? {
+            ("FuncDeclArgName", "reduce_Identifier", [name]) => self.identifier(name),
+            ("FuncDeclArgName", "reduce_PARAMETER", [param]) => {
+                let name = self.text(param)?;
+                let name = name.trim_start_matches('$');
+                let message = if name.starts_with(|c: char| c.is_ascii_digit()) {
+                    "numeric parameters are not supported".to_string()
+                } else {
+                    format!("function parameters do not need a $ prefix, rewrite as '{name}'")
+                };
+                Err(Error::new(message).with_span(self.span(param)))
+            }
+            _ => Err(self.unsupported(node)),
+        }
+    }
+
+    fn missing_param_type(&self, name: &CSTNode) -> Result<Error, Error> {
+        let message = format!(
+            "missing type declaration for the `{}` parameter",
+            self.func_param_name(name)?
+        );
+        Ok(Error::new(message).with_span(self.span(name)))
+    }
+
+    fn ptr_quals(
+        &self,
+        node: &CSTNode,
+    ) -> Result<(Option<bool>, Option<SchemaCardinality>), Error> {
+        if self.is_empty(node) {
+            return Ok((None, None));
+        }
+        let (_, prod, _) = self.production(node)?;
+        let (mut required, mut cardinality) = (None, None);
+        for qual in prod.trim_start_matches("reduce_").split('_') {
+            match qual {
+                "OPTIONAL" => required = Some(false),
+                "REQUIRED" => required = Some(true),
+                "SINGLE" => cardinality = Some(SchemaCardinality::One),
+                "MULTI" => cardinality = Some(SchemaCardinality::Many),
+                _ => return Err(self.unsupported(node)),
+            }
+        }
+        Ok((required, cardinality))
+    }
+
+    fn bases(&self, node: &CSTNode) -> Result<Vec<TypeExpr>, Error> {
+        if self.is_empty(node) {
+            return Ok(Vec::new());
+        }
+        match self.names(node) {
+            Some((list @ ("TypeNameList" | "SimpleTypeNameList"), _)) => {
+                self.list(node, list, |n| self.type_expr(n))
+            }
+            _ => Ok(vec![self.type_expr(node)?]),
+        }
+    }
+
+    fn position(&self, node: &CSTNode) -> Result<Position, Error> {
+        let (_, prod, args) = self.production(node)?;
+        let (r#ref, position) = match (prod, args) {
+            ("reduce_BEFORE_NodeName", [_, name]) => (Some(self.object_ref(name)?), "BEFORE"),
+            ("reduce_AFTER_NodeName", [_, name]) => (Some(self.object_ref(name)?), "AFTER"),
+            ("reduce_FIRST", _) => (None, "FIRST"),
+            ("reduce_LAST", _) => (None, "LAST"),
+            _ => return Err(self.unsupported(node)),
+        };
+        Ok(Position {
+            r#ref,
+            position: position.to_string(),
+        })
+    }
+
+    fn module_ref(&self, node: &CSTNode) -> Result<ObjectRef, Error> {
+        Ok(ObjectRef {
+            name: self.module_name(node)?.join("::"),
+            module: None,
+            itemclass: None,
+        })
+    }
+
+    /// Name of a concrete pointer, which must not be fully-qualified.
+    fn pointer_name(&self, node: &CSTNode) -> Result<ObjectRef, Error> {
+        let name = match self.production(node)? {
+            ("UnqualifiedPointerName", _, [name]) => self.object_ref(name)?,
+            _ => return Err(self.unsupported(node)),
+        };
+        if name.module.is_some() {
+            return Err(Error::new("unexpected fully-qualified name").with_span(self.span(node)));
+        }
+        Ok(name)
+    }
+}
+
+/// Checks order, names and defaults of function parameters.
+fn validate_params(params: &[(FuncParam, Span)]) -> Result<(), Error> {
+    let mut last_pos_default_arg: Option<&FuncParam> = None;
+    let mut last_named_arg: Option<(&FuncParam, Span)> = None;
+    let mut variadic_arg: Option<&FuncParam> = None;
+    let mut names = Vec::new();
+
+    for (arg, span) in params {
+        let error = |message: String| Err(Error::new(message).with_span(*span));
+        if names.contains(&&arg.name) {
+            return error(format!("duplicate parameter name `{}`", arg.name));
+        }
+        names.push(&arg.name);
+
+        match arg.kind {
+            ParameterKind::VariadicParam => {
+                if variadic_arg.is_some() {
+                    return error("more than one variadic argument".to_string());
+                } else if let Some((last_named_arg, span)) = last_named_arg {
+                    let message = format!(
+                        "NAMED ONLY argument `{}` before VARIADIC argument `{}`",
+                        last_named_arg.name, arg.name
+                    );
+                    return Err(Error::new(message).with_span(span));
+                }
+                variadic_arg = Some(arg);
+                if arg.default.is_some() {
+                    return error(format!(
+                        "VARIADIC argument `{}` cannot have a default value",
+                        arg.name
+                    ));
+                }
+            }
+            ParameterKind::NamedOnlyParam => last_named_arg = Some((arg, *span)),
+            ParameterKind::PositionalParam => {
+                if let Some((last_named_arg, _)) = last_named_arg {
+                    return error(format!(
+                        "positional argument `{}` follows NAMED ONLY argument `{}`",
+                        arg.name, last_named_arg.name
+                    ));
+                }
+                if let Some(variadic_arg) = variadic_arg {
+                    return error(format!(
+                        "positional argument `{}` follows VARIADIC argument `{}`",
+                        arg.name, variadic_arg.name
+                    ));
+                }
+                if arg.default.is_some() {
+                    last_pos_default_arg = Some(arg);
+                } else if let Some(last_pos_default_arg) = last_pos_default_arg {
+                    return error(format!(
+                        "positional argument `{}` without default follows positional \
+                        argument `{}` with default",
+                        arg.name, last_pos_default_arg.name
+                    ));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn named_ddl(name: ObjectRef, kind: NamedDDLKind, commands: Vec<DDLOperation>) -> DDLOperation {
+    DDLOperation {
+        commands,
+        kind: DDLOperationKind::DDLCommand(DDLCommand {
+            kind: DDLCommandKind::NamedDDL(NamedDDL { name, kind }),
+        }),
+    }
+}
+
+fn object_ddl(name: ObjectRef, kind: ObjectDDLKind, commands: Vec<DDLOperation>) -> DDLOperation {
+    named_ddl(name, NamedDDLKind::ObjectDDL(ObjectDDL { kind }), commands)
+}
+
+fn create(r#abstract: bool, kind: CreateObjectKind) -> ObjectDDLKind {
+    ObjectDDLKind::CreateObject(CreateObject {
+        r#abstract,
+        sdl_alter_if_exists: false,
+        create_if_not_exists: false,
+        kind: Some(kind),
+    })
+}
+
+fn create_extending(
+    r#abstract: bool,
+    r#final: bool,
+    bases: Vec<TypeExpr>,
+    kind: CreateExtendingObjectKind,
+) -> ObjectDDLKind {
+    let extending = CreateExtendingObject {
+        r#final,
+        bases,
+        kind: Some(kind),
+    };
+    create(
+        r#abstract,
+        CreateObjectKind::CreateExtendingObject(extending),
+    )
+}
+
+fn alter(kind: AlterObjectKind) -> ObjectDDLKind {
+    ObjectDDLKind::AlterObject(AlterObject { kind: Some(kind) })
+}
+
+fn drop_object(kind: DropObjectKind) -> ObjectDDLKind {
+    ObjectDDLKind::DropObject(DropObject { kind: Some(kind) })
+}
+
+fn operation(kind: DDLOperationKind) -> DDLOperation {
+    DDLOperation {
+        commands: vec![],
+        kind,
+    }
+}
+
+fn set_field(
+    name: &str,
+    value: SetFieldValue,
+    special_syntax: bool,
+    kind: Option<SetFieldKind>,
+) -> DDLOperation {
+    operation(DDLOperationKind::SetField(SetField {
+        name: name.to_string(),
+        value,
+        special_syntax,
+        kind,
+    }))
+}
+
+fn bool_value(value: bool) -> SetFieldValue {
+    SetFieldValue::Expr(Box::new(Expr {
+        kind: constant(ConstantKind::BOOLEAN, value.to_string()),
+    }))
+}
+
+fn string_value(value: &str) -> SetFieldValue {
+    SetFieldValue::Expr(Box::new(Expr {
+        kind: constant(ConstantKind::STRING, value.to_string()),
+    }))
+}
+
+fn type_value(value: Option<TypeExpr>) -> SetFieldValue {
+    match value {
+        Some(ty) => SetFieldValue::TypeExpr(ty),
+        None => SetFieldValue::NoneType(()),
+    }
+}
@@ -0,0 +1,84 @@
+//! CST that owns all of its nodes, so it can outlive the [Context] it was
+//! parsed in, be sent to other threads, cached or serialized.
+
+use super::{CSTNode, Context, Production, Skipped, Terminal};
+
+/// Owned counterpart of [CSTNode].
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OwnedCSTNode {
+    #[default]
+    Empty,
+    Terminal(Terminal),
+    Production(OwnedProduction),
+    Missing(Terminal),
+    Skipped(OwnedSkipped),
+}
+
+/// Owned counterpart of [Production].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OwnedProduction {
+    pub id: usize,
+    pub args: Vec<OwnedCSTNode>,
+    pub inlined_ids: Option<Vec<usize>>,
+}
+
+/// Owned counterpart of [Skipped].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OwnedSkipped {
+    pub tokens: Vec<Terminal>,
+    pub node: Box<OwnedCSTNode>,
+}
+
+impl<'a> From<&CSTNode<'a>> for OwnedCSTNode {
+    fn from(node: &CSTNode<'a>) -> Self {
+        match node {
+            CSTNode::Empty => OwnedCSTNode::Empty,
+            CSTNode::Terminal(t) => OwnedCSTNode::Terminal((*t).clone()),
+            CSTNode::Production(p) => OwnedCSTNode::Production(OwnedProduction {
+                id: p.id,
+                args: p.args.iter().map(OwnedCSTNode::from).collect(),
+                inlined_ids: p.inlined_ids.map(|ids| ids.to_vec()),
+            }),
+            CSTNode::Missing(t) => OwnedCSTNode::Missing((*t).clone()),
+            CSTNode::Skipped(s) => OwnedCSTNode::Skipped(OwnedSkipped {
+                tokens: s.tokens.iter().map(|t| (*t).clone()).collect(),
+                node: Box::new(OwnedCSTNode::from(&s.node)),
+            }),
+        }
+    }
+}
+
+impl<'s> Context<'s> {
+    /// Allocates an owned tree into the arenas of this context, so it can be
+    /// used where a [CSTNode] is expected (i.e. converted to Python objects).
+    pub fn alloc_cst(&self, node: &OwnedCSTNode) -> CSTNode<'_> {
+        match node {
+            OwnedCSTNode::Empty => CSTNode::Empty,
+            OwnedCSTNode::Terminal(t) => CSTNode::Terminal(self.alloc_terminal(t.clone())),
+            OwnedCSTNode::Production(p) => {
+                let args: Vec<_> = p.args.iter().map(|a| self.alloc_cst(a)).collect();
+                CSTNode::Production(Production {
+                    id: p.id,
+                    args: self.arena.alloc_slice_copy(&args),
+                    inlined_ids: p
+                        .inlined_ids
+                        .as_ref()
+                        .map(|ids| &*self.arena.alloc_slice_copy(ids)),
+                })
+            }
+            OwnedCSTNode::Missing(t) => CSTNode::Missing(self.alloc_terminal(t.clone())),
+            OwnedCSTNode::Skipped(s) => {
+                let tokens: Vec<_> = (s.tokens.iter())
+                    .map(|t| self.alloc_terminal(t.clone()))
+                    .collect();
+                CSTNode::Skipped(self.arena.alloc(Skipped {
+                    tokens: self.arena.alloc_slice_copy(&tokens),
+                    node: self.alloc_cst(&s.node),
+                }))
+            }
+        }
+    }
+}
@@ -0,0 +1,112 @@
+//! Cost model of error recovery.
+//!
+//! On an error, the parser explores several ways to recover: injecting a
+//! missing token, reporting a custom error or skipping the unexpected token.
+//! Each of these has a cost and the parser with the lowest cost wins.
+
+use std::collections::HashMap;
+
+use crate::keywords;
+use crate::tokenizer::Kind;
+
+#[derive(Debug, Clone)]
+pub struct RecoveryConfig {
+    /// When disabled, parsing stops at the first error.
+    pub enabled: bool,
+
+    /// Number of recovering parsers that are explored at the same time.
+    pub max_parsers: usize,
+
+    /// Parsers with a higher cost don't try to inject tokens and only the
+    /// best of them is kept.
+    pub max_inject_cost: u16,
+
+    /// Cost of skipping an unexpected token.
+    pub skip_cost: u16,
+
+    /// Cost of reporting a custom error.
+    pub custom_error_cost: u16,
+
+    /// Costs of injecting tokens, overriding the defaults.
+    pub injection_costs: HashMap<Kind, u16>,
+}
+
+impl Default for RecoveryConfig {
+    fn default() -> Self {
+        RecoveryConfig {
+            enabled: true,
+            max_parsers: 10,
+            max_inject_cost: 15,
+            skip_cost: 3,
+            custom_error_cost: 3,
+            injection_costs: HashMap::new(),
+        }
+    }
+}
+
+impl RecoveryConfig {
+    /// Reports only the first error, without trying to recover from it.
+    pub fn disabled() -> Self {
+        RecoveryConfig {
+            enabled: false,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_max_parsers(mut self, max_parsers: usize) -> Self {
+        self.max_parsers = max_parsers;
+        self
+    }
+
+    pub fn with_max_inject_cost(mut self, cost: u16) -> Self {
+        self.max_inject_cost = cost;
+        self
+    }
+
+    pub fn with_injection_cost(mut self, kind: Kind, cost: u16) -> Self {
+        self.injection_costs.insert(kind, cost);
+        self
+    }
+
+    pub fn injection_cost(&self, kind: &Kind) -> u16 {
+        match self.injection_costs.get(kind) {
+            Some(cost) => *cost,
+            None => default_injection_cost(kind),
+        }
+    }
+}
+
+fn default_injection_cost(kind: &Kind) -> u16 {
+    use Kind::*;
+
+    match kind {
+        Ident => 10,
+        Substitution => 8,
+
+        // Manual keyword tweaks to encourage some error messages and discourage others.
+        Keyword(keywords::Keyword(
+            "delete" | "update" | "migration" | "role" | "global" | "administer" | "future"
+            | "database",
+        )) => 100,
+        Keyword(keywords::Keyword("insert" | "module" | "extension" | "branch")) => 20,
+        Keyword(keywords::Keyword("select" | "property" | "type")) => 10,
+        Keyword(_) => 15,
+
+        Dot => 5,
+        OpenBrace | OpenBracket => 5,
+        OpenParen => 4,
+
+        CloseBrace | CloseBracket | CloseParen => 1,
+
+        Namespace => 10,
+        Comma | Colon | Semicolon => 2,
+        Eq => 5,
+
+        At => 6,
+        IntConst => 8,
+
+        Assign | Arrow => 5,
+
+        _ => 100, // forbidden
+    }
+}
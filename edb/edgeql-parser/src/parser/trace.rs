@@ -0,0 +1,128 @@
+//! Tracing of parser actions, for debugging the grammar and error recovery.
+//!
+//! A [Tracer] is set on the [Context](super::Context) and receives an
+//! [Event] for each action of the parser. When error recovery explores
+//! multiple parsers at once, their events are interleaved.
+
+use std::cell::RefCell;
+use std::fmt;
+
+use crate::tokenizer::Error;
+
+use super::Terminal;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Event<'a> {
+    /// Next token of the input is processed by all parsers.
+    Token { token: &'a Terminal },
+
+    /// Token was pushed on the stack.
+    Shift { token: &'a Terminal, state: usize },
+
+    /// Nodes on top of the stack were reduced into a production.
+    Reduce {
+        non_term: &'a str,
+        production: &'a str,
+        args: usize,
+        state: usize,
+    },
+
+    /// Reduced production was replaced by one of its arguments.
+    Inline {
+        non_term: &'a str,
+        production: &'a str,
+        position: u8,
+    },
+
+    /// There is no action for the token in the state.
+    Unexpected { token: &'a Terminal, state: usize },
+
+    /// Recovery: a parser injected the token and will retry the current one.
+    Inject { token: &'a Terminal, cost: u16 },
+
+    /// Recovery: a parser reported a custom error and skipped the token.
+    CustomError { error: &'a Error, cost: u16 },
+
+    /// Recovery: a parser skipped the token.
+    Skip { token: &'a Terminal, cost: u16 },
+
+    /// One of the parsers has recovered, others were discarded.
+    Recover { discarded: usize },
+
+    /// Parsers with the highest costs were discarded.
+    Prune { kept: usize, discarded: usize },
+}
+
+/// Receives events of the parser.
+pub trait Tracer {
+    fn trace(&self, event: &Event);
+}
+
+impl<F: Fn(&Event)> Tracer for F {
+    fn trace(&self, event: &Event) {
+        self(event)
+    }
+}
+
+/// Tracer that collects events as lines of text.
+#[derive(Debug, Default)]
+pub struct EventLog {
+    lines: RefCell<Vec<String>>,
+}
+
+impl EventLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        self.lines.borrow().clone()
+    }
+}
+
+impl Tracer for EventLog {
+    fn trace(&self, event: &Event) {
+        self.lines.borrow_mut().push(event.to_string());
+    }
+}
+
+impl fmt::Display for Event<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Event::Token { token } => write!(f, "token {}", name(token)),
+            Event::Shift { token, state } => write!(f, "  shift {} -> {state}", name(token)),
+            Event::Reduce {
+                non_term,
+                production,
+                args,
+                state,
+            } => write!(f, "  reduce {non_term}.{production} ({args}) -> {state}"),
+            Event::Inline {
+                non_term,
+                production,
+                position,
+            } => write!(f, "  inline {non_term}.{production} [{position}]"),
+            Event::Unexpected { token, state } => {
+                write!(f, "  unexpected {} in {state}", name(token))
+            }
+            Event::Inject { token, cost } => write!(f, "  inject {} (cost {cost})", name(token)),
+            Event::CustomError { error, cost } => {
+                write!(f, "  custom error: {} (cost {cost})", error.message)
+            }
+            Event::Skip { token, cost } => write!(f, "  skip {} (cost {cost})", name(token)),
+            Event::Recover { discarded } => write!(f, "recover, discard {discarded}"),
+            Event::Prune { kept, discarded } => write!(f, "prune to {kept}, discard {discarded}"),
+        }
+    }
+}
+
+/// Token as shown in errors, or its kind for tokens without text (i.e. start
+/// tokens).
+fn name(token: &Terminal) -> String {
+    let text = token.to_string();
+    if text.is_empty() {
+        format!("{:?}", token.kind)
+    } else {
+        text
+    }
+}
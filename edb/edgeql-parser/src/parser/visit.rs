@@ -0,0 +1,182 @@
+//! Walking the CST and matching its nodes by names of productions, instead
+//! of production ids, which change with the grammar.
+
+use crate::position::Span;
+
+use super::{get_span_of_nodes, CSTNode, Production, Spec, Terminal};
+
+impl Spec {
+    /// Names of non-terminal and of the production with the given id,
+    /// i.e. `("ForQuery", "reduce_FOR_...")`.
+    pub fn production_name(&self, id: usize) -> (&str, &str) {
+        let (non_term, production) = &self.production_names[id];
+        (non_term, production)
+    }
+
+    /// Checks if the node is a production of the non-terminal and, if given,
+    /// a specific production of it.
+    ///
+    /// Productions that were inlined into this node are checked as well.
+    pub fn is_production(&self, node: &CSTNode, non_term: &str, production: Option<&str>) -> bool {
+        let CSTNode::Production(prod) = node else {
+            return false;
+        };
+
+        let inlined_ids = prod.inlined_ids.unwrap_or_default();
+        std::iter::once(&prod.id).chain(inlined_ids).any(|id| {
+            let (nt, p) = self.production_name(*id);
+            nt == non_term && production.map_or(true, |x| x == p)
+        })
+    }
+}
+
+impl<'a> CSTNode<'a> {
+    /// Span of all terminals in this node.
+    ///
+    /// Is `None` when the node does not contain any terminals.
+    pub fn span(&self) -> Option<Span> {
+        get_span_of_nodes(std::slice::from_ref(self))
+    }
+
+    /// Child nodes of a production, or the node after skipped tokens. Other
+    /// nodes have none.
+    pub fn children(&self) -> &'a [CSTNode<'a>] {
+        match self {
+            CSTNode::Production(Production { args, .. }) => args,
+            CSTNode::Skipped(skipped) => std::slice::from_ref(&skipped.node),
+            _ => &[],
+        }
+    }
+
+    /// Terminal from the source. Tokens injected by error recovery are not
+    /// included.
+    pub fn as_terminal(&self) -> Option<&'a Terminal> {
+        match self {
+            CSTNode::Terminal(t) => Some(t),
+            _ => None,
+        }
+    }
+
+    /// Checks if the node or any of its descendants was injected or skipped
+    /// by error recovery.
+    pub fn is_recovered(&self) -> bool {
+        match self {
+            CSTNode::Empty | CSTNode::Terminal(_) => false,
+            CSTNode::Missing(_) | CSTNode::Skipped(_) => true,
+            CSTNode::Production(p) => p.args.iter().any(CSTNode::is_recovered),
+        }
+    }
+
+    /// Terminal that was shifted by the parser, including injected ones and
+    /// those after skipped tokens.
+    pub(super) fn shifted_terminal(&self) -> Option<&'a Terminal> {
+        match self {
+            CSTNode::Terminal(t) | CSTNode::Missing(t) => Some(t),
+            CSTNode::Skipped(skipped) => skipped.node.shifted_terminal(),
+            _ => None,
+        }
+    }
+}
+
+/// Tells the walker whether to descend into children of a node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Walk {
+    Continue,
+    SkipChildren,
+}
+
+/// Callbacks invoked by [walk] for each node of the tree, in depth-first
+/// order.
+///
+/// `parents` contains all ancestors of the node, starting with the root.
+pub trait Visitor<'a> {
+    fn enter(&mut self, node: &'a CSTNode<'a>, parents: &[&'a CSTNode<'a>]) -> Walk {
+        let _ = (node, parents);
+        Walk::Continue
+    }
+
+    fn leave(&mut self, node: &'a CSTNode<'a>, parents: &[&'a CSTNode<'a>]) {
+        let _ = (node, parents);
+    }
+}
+
+/// Walks the tree depth-first, calling the visitor for each node.
+pub fn walk<'a>(root: &'a CSTNode<'a>, visitor: &mut impl Visitor<'a>) {
+    let mut parents = Vec::new();
+    walk_node(root, &mut parents, visitor);
+}
+
+fn walk_node<'a>(
+    node: &'a CSTNode<'a>,
+    parents: &mut Vec<&'a CSTNode<'a>>,
+    visitor: &mut impl Visitor<'a>,
+) {
+    if visitor.enter(node, parents) == Walk::SkipChildren {
+        return;
+    }
+
+    parents.push(node);
+    for child in node.children() {
+        walk_node(child, parents, visitor);
+    }
+    parents.pop();
+
+    visitor.leave(node, parents);
+}
+
+/// A node found by [find_all], together with its ancestors.
+#[derive(Debug, Clone)]
+pub struct Match<'a> {
+    pub node: &'a CSTNode<'a>,
+
+    /// Ancestors of the node, starting with the root.
+    pub parents: Vec<&'a CSTNode<'a>>,
+}
+
+impl<'a> Match<'a> {
+    pub fn parent(&self) -> Option<&'a CSTNode<'a>> {
+        self.parents.last().copied()
+    }
+}
+
+/// Finds all productions of a non-terminal (and optionally a specific
+/// production of it), for example all `ForQuery` nodes.
+///
+/// Matches nested within other matches are included.
+pub fn find_all<'a>(
+    root: &'a CSTNode<'a>,
+    spec: &Spec,
+    non_term: &str,
+    production: Option<&str>,
+) -> Vec<Match<'a>> {
+    struct Finder<'a, 'q> {
+        spec: &'q Spec,
+        non_term: &'q str,
+        production: Option<&'q str>,
+        found: Vec<Match<'a>>,
+    }
+
+    impl<'a, 'q> Visitor<'a> for Finder<'a, 'q> {
+        fn enter(&mut self, node: &'a CSTNode<'a>, parents: &[&'a CSTNode<'a>]) -> Walk {
+            if self
+                .spec
+                .is_production(node, self.non_term, self.production)
+            {
+                self.found.push(Match {
+                    node,
+                    parents: parents.to_vec(),
+                });
+            }
+            Walk::Continue
+        }
+    }
+
+    let mut finder = Finder {
+        spec,
+        non_term,
+        production,
+        found: Vec::new(),
+    };
+    walk(root, &mut finder);
+    finder.found
+}
@@ -0,0 +1,359 @@
+//! Rendering of errors for humans (with snippets of the source) or for
+//! tools (as JSON).
+//!
+//! ```text
+//! error[E0102]: Missing ')'
+//!  --> 1:5
+//!   |
+//! 1 | (foo
+//!   |     ^
+//!   | - unclosed '('
+//!   = help: insert ')'
+//! ```
+//!
+//! Line and column numbers are the same as in positions reported elsewhere
+//! in the crate (see [`InflatedPos`]), so a tab is a single column. Only
+//! snippets expand tabs, to align underlines with the text.
+
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+use unicode_width::UnicodeWidthChar;
+
+use crate::position::{InflatedPos, Span};
+use crate::tokenizer::Error;
+
+/// Spans longer than this are shown only by their first and last lines.
+const MAX_SPAN_LINES: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Plain,
+    /// Plain, colored with ANSI escape codes.
+    Ansi,
+    /// Array of error objects, containing also line and column numbers.
+    Json,
+}
+
+pub struct Renderer<'a> {
+    source: &'a str,
+    format: Format,
+    tab_width: usize,
+    file_name: Option<&'a str>,
+
+    /// Byte ranges of lines, excluding line endings.
+    lines: Vec<(usize, usize)>,
+}
+
+/// Underline of (a part of) a span on a single line.
+struct Mark {
+    line: usize,
+    start: usize,
+    end: usize,
+    primary: bool,
+    message: Option<String>,
+}
+
+impl<'a> Renderer<'a> {
+    pub fn new(source: &'a str) -> Self {
+        Renderer {
+            source,
+            format: Format::Plain,
+            tab_width: 4,
+            file_name: None,
+            lines: split_lines(source),
+        }
+    }
+
+    pub fn with_format(mut self, format: Format) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Width of tabs in snippets.
+    pub fn with_tab_width(mut self, tab_width: usize) -> Self {
+        self.tab_width = tab_width.max(1);
+        self
+    }
+
+    /// Name of the file shown before line and column numbers.
+    pub fn with_file_name(mut self, file_name: &'a str) -> Self {
+        self.file_name = Some(file_name);
+        self
+    }
+
+    pub fn render(&self, errors: &[Error]) -> String {
+        if self.format == Format::Json {
+            return self.render_json(errors);
+        }
+
+        let mut out = String::new();
+        for (i, error) in errors.iter().enumerate() {
+            if i > 0 {
+                out.push('\n');
+            }
+            self.render_error(&mut out, error);
+        }
+        out
+    }
+
+    fn render_error(&self, out: &mut String, error: &Error) {
+        let span = self.clamp(error.span);
+
+        let title = match error.code {
+            Some(code) => format!("error[{code}]"),
+            None => "error".to_string(),
+        };
+        let title = self.paint(&title, "1;31");
+        let message = self.paint(&format!(": {}", error.message), "1");
+        writeln!(out, "{title}{message}").unwrap();
+
+        let mut marks = self.marks(span, true, None);
+        for label in error.labels() {
+            let label_span = self.clamp(label.span);
+            marks.extend(self.marks(label_span, false, Some(&label.message)));
+        }
+
+        // lines to show in the snippet
+        let mut shown = BTreeSet::new();
+        for mark in &marks {
+            shown.insert(mark.line);
+        }
+        let gutter = shown.iter().last().map_or(1, |l| (l + 1).to_string().len());
+        let pad = " ".repeat(gutter);
+
+        let (line, column) = self.position(span.start);
+        let file_name = self.file_name.map(|f| format!("{f}:")).unwrap_or_default();
+        let arrow = self.paint("-->", "1;34");
+        writeln!(out, "{pad}{arrow} {file_name}{line}:{column}").unwrap();
+
+        let bar = self.paint("|", "1;34");
+        writeln!(out, "{pad} {bar}").unwrap();
+        let mut prev_line = None;
+        for line in shown {
+            if prev_line.map_or(false, |p| p + 1 < line) {
+                writeln!(out, "{}", self.paint("...", "1;34")).unwrap();
+            }
+            prev_line = Some(line);
+
+            let number = self.paint(&format!("{:>gutter$}", line + 1), "1;34");
+            let text = self.line_text(line);
+            writeln!(out, "{number} {bar} {text}").unwrap();
+
+            let mut line_marks: Vec<_> = marks.iter().filter(|m| m.line == line).collect();
+            line_marks.sort_by_key(|m| (!m.primary, m.start));
+            for mark in line_marks {
+                let underline = if mark.primary { "^" } else { "-" };
+                let color = if mark.primary { "1;31" } else { "1;34" };
+                let underline = underline.repeat(mark.end - mark.start);
+                let mut row = format!("{}{}", " ".repeat(mark.start), underline);
+                if let Some(message) = &mark.message {
+                    row += " ";
+                    row += message;
+                }
+                writeln!(out, "{pad} {bar} {}", self.paint(&row, color)).unwrap();
+            }
+        }
+
+        let eq = self.paint("=", "1;34");
+        if let Some(hint) = &error.hint {
+            writeln!(out, "{pad} {eq} {}: {hint}", self.paint("hint", "1")).unwrap();
+        }
+        if let Some(details) = &error.details {
+            writeln!(out, "{pad} {eq} {}: {details}", self.paint("note", "1")).unwrap();
+        }
+        for suggestion in error.suggestions() {
+            let help = self.paint("help", "1");
+            writeln!(out, "{pad} {eq} {help}: {}", suggestion.message).unwrap();
+        }
+    }
+
+    /// Splits the span into underlines of each of its lines. Long spans are
+    /// represented only by their first and last lines.
+    fn marks(&self, span: Span, primary: bool, message: Option<&String>) -> Vec<Mark> {
+        let start = span.start as usize;
+        let end = span.end as usize;
+        let first = self.line_of(start);
+        // the end is exclusive, so a span ending at the start of a line
+        // does not cover it
+        let last = if end > start {
+            self.line_of(end - 1)
+        } else {
+            first
+        };
+
+        let mut marks = Vec::new();
+        for line in first..=last {
+            if last - first >= MAX_SPAN_LINES && line != first && line != last {
+                continue;
+            }
+            let (line_start, line_end) = self.lines[line];
+            let from = if line == first { start } else { line_start };
+            let to = if line == last { end } else { line_end };
+
+            let from = self.width(&self.source[line_start..from.min(line_end)]);
+            let to = self.width(&self.source[line_start..to.min(line_end)]);
+            marks.push(Mark {
+                line,
+                start: from,
+                end: to.max(from + 1),
+                primary,
+                message: None,
+            });
+        }
+        if let Some(mark) = marks.last_mut() {
+            mark.message = message.cloned();
+        }
+        marks
+    }
+
+    fn render_json(&self, errors: &[Error]) -> String {
+        let span_json = |span: Span| {
+            let span = self.clamp(span);
+            serde_json::json!({
+                "start": span.start,
+                "end": span.end,
+            })
+        };
+        let pos_json = |offset: u64| {
+            let (line, column) = self.position(offset);
+            serde_json::json!({
+                "line": line,
+                "column": column,
+            })
+        };
+
+        let errors: Vec<_> = errors
+            .iter()
+            .map(|e| {
+                let labels: Vec<_> = (e.labels().iter())
+                    .map(|l| serde_json::json!({"span": span_json(l.span), "message": l.message}))
+                    .collect();
+                let suggestions: Vec<_> = (e.suggestions().iter())
+                    .map(|s| {
+                        serde_json::json!({
+                            "message": s.message,
+                            "span": span_json(s.span),
+                            "replacement": s.replacement,
+                        })
+                    })
+                    .collect();
+                let span = self.clamp(e.span);
+                serde_json::json!({
+                    "code": e.code.map(|c| c.as_str()),
+                    "message": e.message,
+                    "span": span_json(span),
+                    "start": pos_json(span.start),
+                    "end": pos_json(span.end),
+                    "hint": e.hint,
+                    "details": e.details,
+                    "labels": labels,
+                    "suggestions": suggestions,
+                })
+            })
+            .collect();
+        serde_json::Value::Array(errors).to_string()
+    }
+
+    /// One-based line and column of the offset, as in [`InflatedPos`].
+    fn position(&self, offset: u64) -> (usize, usize) {
+        let bytes = self.source.as_bytes();
+        let mut offset = offset as usize;
+        // the `\n` of `\r\n` belongs to the line that it ends
+        if offset > 0 && bytes.get(offset) == Some(&b'\n') && bytes[offset - 1] == b'\r' {
+            offset -= 1;
+        }
+        let pos = InflatedPos::from_offset(bytes, offset as u64)
+            .expect("offset is clamped")
+            .deflate();
+        (pos.line, pos.column)
+    }
+
+    /// Index of the line containing the offset. Offsets within a line ending
+    /// (e.g. at the `\n` of `\r\n`) belong to the line that it ends.
+    fn line_of(&self, offset: usize) -> usize {
+        let next = self.lines.partition_point(|&(start, _)| start <= offset);
+        next.saturating_sub(1)
+    }
+
+    /// Text of the line, with tabs expanded.
+    fn line_text(&self, line: usize) -> String {
+        let (start, end) = self.lines[line];
+        let mut text = String::new();
+        let mut column = 0;
+        for c in self.source[start..end].chars() {
+            if c == '\t' {
+                let next = self.next_tab_stop(column);
+                text.extend(std::iter::repeat(' ').take(next - column));
+                column = next;
+            } else {
+                text.push(c);
+                column += c.width().unwrap_or(0);
+            }
+        }
+        text
+    }
+
+    /// Width of text on the terminal.
+    fn width(&self, text: &str) -> usize {
+        text.chars().fold(0, |column, c| {
+            if c == '\t' {
+                self.next_tab_stop(column)
+            } else {
+                column + c.width().unwrap_or(0)
+            }
+        })
+    }
+
+    fn next_tab_stop(&self, column: usize) -> usize {
+        (column / self.tab_width + 1) * self.tab_width
+    }
+
+    /// Makes sure the span is within source and on char boundaries.
+    fn clamp(&self, span: Span) -> Span {
+        let clamp = |offset: u64| {
+            let mut offset = (offset as usize).min(self.source.len());
+            while !self.source.is_char_boundary(offset) {
+                offset -= 1;
+            }
+            offset as u64
+        };
+        let start = clamp(span.start);
+        let end = clamp(span.end).max(start);
+        Span { start, end }
+    }
+
+    fn paint(&self, text: &str, style: &str) -> String {
+        if self.format == Format::Ansi {
+            format!("\x1b[{style}m{text}\x1b[0m")
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+/// Splits source into lines for snippets, the same way as [`InflatedPos`]
+/// counts them.
+fn split_lines(source: &str) -> Vec<(usize, usize)> {
+    let bytes = source.as_bytes();
+    let mut lines = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\r' if bytes.get(i + 1) == Some(&b'\n') => {
+                lines.push((start, i));
+                i += 2;
+                start = i;
+            }
+            b'\r' | b'\n' => {
+                lines.push((start, i));
+                i += 1;
+                start = i;
+            }
+            _ => i += 1,
+        }
+    }
+    lines.push((start, bytes.len()));
+    lines
+}
@@ -0,0 +1,162 @@
+//! Rewriting of queries into a consistent style, without changing their
+//! layout: keywords are upper- or lower-cased and backtick names are quoted
+//! only when they need to be.
+//!
+//! Works on tokens, so strings, comments and whitespace are kept byte for
+//! byte. Reserved keywords are always keywords, but unreserved keywords can
+//! also be used as names (`.type`, `count(Object)`, `{ abstract }`). Only
+//! the grammar can tell those apart, so unreserved keywords are re-cased
+//! only when [Options::spec] is set and the source parses, as a block of
+//! statements or as an SDL document. Keywords which were parsed as names
+//! are left as they are.
+
+use std::borrow::Cow;
+use std::collections::HashSet;
+
+use crate::format::KeywordCase;
+use crate::helpers::quote_name;
+use crate::keywords;
+use crate::parser::visit::{walk, Visitor, Walk};
+use crate::parser::{CSTNode, Context, Spec};
+use crate::tokenizer::{Error, Kind, Tokenizer};
+
+#[derive(Clone)]
+pub struct Options<'a> {
+    pub keyword_case: KeywordCase,
+    /// Remove backticks from names that do not need them.
+    pub requote_names: bool,
+    /// Grammar used to find unreserved keywords that are used as keywords.
+    /// When not set (or the source cannot be parsed), only reserved
+    /// keywords are re-cased.
+    pub spec: Option<&'a Spec>,
+}
+
+impl Default for Options<'_> {
+    fn default() -> Self {
+        Options {
+            keyword_case: KeywordCase::Upper,
+            requote_names: true,
+            spec: None,
+        }
+    }
+}
+
+/// Rewrites the source. Fails if it cannot be tokenized.
+pub fn rewrite(source: &str, options: &Options) -> Result<String, Error> {
+    let tokens = Tokenizer::new(source)
+        .with_trivia()
+        .collect::<Result<Vec<_>, _>>()?;
+    let names = options.spec.and_then(|spec| parse_names(source, spec));
+
+    let mut out = String::with_capacity(source.len());
+    let mut last = 0;
+    for token in &tokens {
+        let replacement = match token.kind {
+            Kind::Keyword(kw) => {
+                let is_keyword = match &names {
+                    Some(names) => !names.contains(&token.span.start),
+                    None => kw.is_reserved(),
+                };
+                if !is_keyword {
+                    continue;
+                }
+                change_case(&token.text, options.keyword_case)
+            }
+            Kind::BacktickName if options.requote_names => match requote(&token.text) {
+                Some(name) => name,
+                None => continue,
+            },
+            _ => continue,
+        };
+
+        let span = token.span;
+        out.push_str(&source[last..span.start as usize]);
+        out.push_str(&replacement);
+        last = span.end as usize;
+    }
+    out.push_str(&source[last..]);
+    Ok(out)
+}
+
+/// Start offsets of names in the source, see [find_names]. Is `None` when
+/// the source cannot be parsed, neither as a block nor as an SDL document.
+fn parse_names(source: &str, spec: &Spec) -> Option<HashSet<u64>> {
+    let ctx = Context::new(spec);
+
+    let result = ctx.parse_block(source);
+    if !result.has_errors() {
+        return Some(find_names(result.cst?, spec));
+    }
+    let result = ctx.parse_sdl_document(source);
+    if !result.has_errors() {
+        return Some(find_names(result.cst?, spec));
+    }
+    None
+}
+
+/// Non-terminals of keywords used as names. These are never inlined, so a
+/// keyword is used as a name exactly when it is a child of one of them.
+const KEYWORD_NAMES: &[&str] = &[
+    "UnreservedKeyword",
+    "PartialReservedKeyword",
+    "ReservedKeyword",
+];
+
+/// Start offsets of terminals which were parsed as names: identifiers and
+/// keywords reduced to one of [KEYWORD_NAMES]. All other keywords in the
+/// tree are used as keywords.
+pub(crate) fn find_names(root: &CSTNode, spec: &Spec) -> HashSet<u64> {
+    let mut finder = NameFinder {
+        spec,
+        names: HashSet::new(),
+    };
+    walk(root, &mut finder);
+    finder.names
+}
+
+struct NameFinder<'s> {
+    spec: &'s Spec,
+    names: HashSet<u64>,
+}
+
+impl<'a> Visitor<'a> for NameFinder<'_> {
+    fn enter(&mut self, node: &'a CSTNode<'a>, parents: &[&'a CSTNode<'a>]) -> Walk {
+        let Some(terminal) = node.as_terminal() else {
+            return Walk::Continue;
+        };
+        let is_name = match terminal.kind {
+            Kind::Ident => true,
+            Kind::Keyword(_) => parents.last().map_or(false, |parent| {
+                KEYWORD_NAMES
+                    .iter()
+                    .any(|non_term| self.spec.is_production(parent, non_term, None))
+            }),
+            _ => false,
+        };
+        if is_name {
+            self.names.insert(terminal.span.start);
+        }
+        Walk::Continue
+    }
+}
+
+pub(crate) fn change_case(text: &str, case: KeywordCase) -> String {
+    match case {
+        KeywordCase::Upper => text.to_uppercase(),
+        KeywordCase::Lower => text.to_lowercase(),
+        KeywordCase::Preserve => text.to_string(),
+    }
+}
+
+/// Name without the backticks, if they are not needed. Names that would be
+/// tokenized as a keyword (even an unreserved one) keep their backticks.
+fn requote(quoted: &str) -> Option<String> {
+    let name = quoted[1..quoted.len() - 1].replace("``", "`");
+
+    let starts_with_digit = name.starts_with(|c: char| c.is_ascii_digit());
+    let is_keyword = keywords::lookup_all(&name.to_lowercase()).is_some();
+    if starts_with_digit || is_keyword || !matches!(quote_name(&name), Cow::Borrowed(_)) {
+        return None;
+    }
+    Some(name)
+}
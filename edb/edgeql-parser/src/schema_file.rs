@@ -1,4 +1,4 @@
-use crate::position::Pos;
+use crate::position::{Pos, Span};
 use crate::tokenizer::Tokenizer;
 use crate::tokenizer;
 
@@ -6,32 +6,79 @@ use crate::tokenizer;
 #[derive(Debug, thiserror::Error)]
 #[non_exhaustive]
 pub enum SchemaFileError {
-    #[error("{}: bracket `{}` has never been closed", pos, kind)]
+    #[error("{}: {}", pos, self.message())]
     MissingBracket {
         pos: Pos,
         kind: char,
     },
-    #[error(
-        "{}: closing bracket mismatch, opened `{}` at {}, encountered `{}`",
-        closing_pos, opened, opened_pos, encountered)]
+    #[error("{}: {}", closing_pos, self.message())]
     BracketMismatch {
         opened: char,
         opened_pos: Pos,
         closing_pos: Pos,
         encountered: char,
     },
-    #[error("{}: extra closing bracket `{}`", pos, kind)]
+    #[error("{}: {}", pos, self.message())]
     ExtraBracket {
         pos: Pos,
         kind: char,
     },
-    #[error("{}: tokenizer error: {}", pos, error)]
+    #[error("{}: {}", pos, self.message())]
     TokenizerError {
         pos: Pos,
         error: String,
     },
 }
 
+impl SchemaFileError {
+    /// Message without the position, which `Display` prepends
+    pub fn message(&self) -> String {
+        use SchemaFileError::*;
+
+        match self {
+            MissingBracket { kind, .. } => {
+                format!("bracket `{}` has never been closed", kind)
+            }
+            BracketMismatch { opened, opened_pos, encountered, .. } => {
+                format!("closing bracket mismatch, opened `{}` at {}, \
+                         encountered `{}`", opened, opened_pos, encountered)
+            }
+            ExtraBracket { kind, .. } => {
+                format!("extra closing bracket `{}`", kind)
+            }
+            TokenizerError { error, .. } => {
+                format!("tokenizer error: {}", error)
+            }
+        }
+    }
+}
+
+impl From<&SchemaFileError> for tokenizer::Error {
+    /// Converts into an error with a span, which can be rendered with
+    /// [crate::render::Renderer].
+    fn from(error: &SchemaFileError) -> tokenizer::Error {
+        use SchemaFileError::*;
+
+        let span = |pos: &Pos, len: u64| Span {
+            start: pos.offset,
+            end: pos.offset + len,
+        };
+        let message = error.message();
+        match error {
+            MissingBracket { pos, .. } | ExtraBracket { pos, .. } => {
+                tokenizer::Error::new(message).with_span(span(pos, 1))
+            }
+            BracketMismatch { opened_pos, closing_pos, .. } => {
+                tokenizer::Error::new(message)
+                .with_span(span(closing_pos, 1))
+                .with_label(span(opened_pos, 1), "opened here")
+            }
+            TokenizerError { pos, .. } => tokenizer::Error::new(message)
+                .with_span(span(pos, 0)),
+        }
+    }
+}
+
 fn match_bracket(open: char, encountered: char, pos: Pos,
     brackets: &mut Vec<(char, char, Pos)>)
     -> Result<(), SchemaFileError>
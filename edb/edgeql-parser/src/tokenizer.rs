@@ -1,3 +1,5 @@
+mod incremental;
+
 use std::borrow::Cow;
 use std::fmt;
 use std::str::CharIndices;
@@ -5,10 +7,13 @@ use std::str::CharIndices;
 use bigdecimal::BigDecimal;
 use memchr::memmem::find;
 
-use crate::keywords::{self, Keyword};
+use crate::diagnostics::{ErrorCode, Label, Suggestion};
+use crate::keywords::{self, Keyword, Version};
 use crate::position::{Pos, Span};
 use crate::validation::Validator;
 
+pub use incremental::Edit;
+
 // Current max keyword length is 10, but we're reserving some space
 pub const MAX_KEYWORD_LENGTH: usize = 16;
 
@@ -43,6 +48,18 @@ pub struct Error {
     pub span: Span,
     pub hint: Option<String>,
     pub details: Option<String>,
+
+    pub code: Option<ErrorCode>,
+
+    /// Labels and suggestions, boxed because most errors have none, and
+    /// errors are passed around by value a lot.
+    annotations: Option<Box<Annotations>>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Annotations {
+    labels: Vec<Label>,
+    suggestions: Vec<Suggestion>,
 }
 
 impl Error {
@@ -52,9 +69,56 @@ impl Error {
             span: Span::default(),
             hint: None,
             details: None,
+            code: None,
+            annotations: None,
         }
     }
 
+    pub fn with_hint<S: ToString>(mut self, hint: S) -> Self {
+        self.hint = Some(hint.to_string());
+        self
+    }
+
+    pub fn with_details<S: ToString>(mut self, details: S) -> Self {
+        self.details = Some(details.to_string());
+        self
+    }
+
+    pub fn with_code(mut self, code: ErrorCode) -> Self {
+        self.code = Some(code);
+        self
+    }
+
+    pub fn default_code_to(mut self, code: ErrorCode) -> Self {
+        self.code.get_or_insert(code);
+        self
+    }
+
+    pub fn with_label<S: ToString>(mut self, span: Span, message: S) -> Self {
+        let label = Label {
+            span,
+            message: message.to_string(),
+        };
+        self.annotations.get_or_insert_with(Default::default).labels.push(label);
+        self
+    }
+
+    pub fn with_suggestion(mut self, suggestion: Suggestion) -> Self {
+        let annotations = self.annotations.get_or_insert_with(Default::default);
+        annotations.suggestions.push(suggestion);
+        self
+    }
+
+    /// Related parts of the source, i.e. the unclosed opening bracket.
+    pub fn labels(&self) -> &[Label] {
+        self.annotations.as_ref().map_or(&[], |a| &a.labels)
+    }
+
+    /// Edits that would fix this error.
+    pub fn suggestions(&self) -> &[Suggestion] {
+        self.annotations.as_ref().map_or(&[], |a| &a.suggestions)
+    }
+
     pub fn with_span(mut self, span: Span) -> Self {
         self.span = span;
         self
@@ -134,6 +198,10 @@ pub enum Kind {
     StartFragment,
     StartMigration,
     StartSDLDocument,
+
+    Whitespace, // only emitted in trivia mode
+    Comment,    // # xx, only emitted in trivia mode
+    Unknown,    // only emitted in error recovery mode
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
@@ -150,6 +218,19 @@ pub struct Tokenizer<'a> {
     dot: bool,
     next_state: Option<(usize, TokenStub<'a>, usize, Pos, Pos)>,
     keyword_buf: String,
+
+    /// Emit whitespace and comments as tokens
+    trivia: bool,
+    /// Start of the whitespace and comments preceding current token
+    trivia_start: usize,
+
+    /// Skip bad input and continue after errors
+    recover: bool,
+    /// Token for the input skipped after the last error
+    pending_unknown: Option<Token<'a>>,
+
+    /// Version of the language, which determines the keywords
+    version: Option<Version>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -157,30 +238,41 @@ pub struct Checkpoint {
     position: Pos,
     off: usize,
     dot: bool,
+    trivia_start: usize,
 }
 
 impl<'a> Iterator for Tokenizer<'a> {
     type Item = Result<Token<'a>, Error>;
 
     fn next(&mut self) -> Option<Self::Item> {
+        if let Some(unknown) = self.pending_unknown.take() {
+            return Some(Ok(unknown));
+        }
+        if self.trivia && self.trivia_start < self.off {
+            return Some(Ok(self.read_trivia()));
+        }
+
         let start = self.current_pos().offset;
 
-        Some(
-            self.read_token()?
-                .map(|(token, end)| {
-                    let end = end.offset;
-                    Token {
-                        kind: token.kind,
-                        text: token.text.into(),
-                        value: None,
-                        span: Span { start, end },
-                    }
-                })
-                .map_err(|e| {
-                    let end = self.position.offset;
-                    e.with_span(Span { start, end })
-                }),
-        )
+        match self.read_token()? {
+            Ok((token, end)) => {
+                let end = end.offset;
+                Some(Ok(Token {
+                    kind: token.kind,
+                    text: token.text.into(),
+                    value: None,
+                    span: Span { start, end },
+                }))
+            }
+            Err(e) => {
+                let end = self.position.offset;
+                if self.recover {
+                    self.pending_unknown = Some(self.skip_unknown());
+                }
+                let e = e.with_span(Span { start, end });
+                Some(Err(e.default_code_to(ErrorCode::InvalidToken)))
+            }
+        }
     }
 }
 
@@ -199,6 +291,11 @@ impl<'a> Tokenizer<'a> {
             // Current max keyword length is 10, but we're reserving some
             // space
             keyword_buf: String::with_capacity(MAX_KEYWORD_LENGTH),
+            trivia: false,
+            trivia_start: 0,
+            recover: false,
+            pending_unknown: None,
+            version: None,
         };
         me.skip_whitespace();
         me
@@ -215,11 +312,48 @@ impl<'a> Tokenizer<'a> {
             dot: false,
             next_state: None,
             keyword_buf: String::with_capacity(MAX_KEYWORD_LENGTH),
+            trivia: false,
+            trivia_start: 0,
+            recover: false,
+            pending_unknown: None,
+            version: None,
         };
         me.skip_whitespace();
         me
     }
 
+    /// Emit whitespace and comments as [Kind::Whitespace] and
+    /// [Kind::Comment] tokens, instead of skipping them.
+    ///
+    /// In this mode, concatenated text of all tokens is equal to the input.
+    /// Note that [Validator] does not combine multi-word keywords
+    /// (i.e. `order by`) when there is a trivia token between the words.
+    pub fn with_trivia(mut self) -> Self {
+        self.trivia = true;
+        self
+    }
+
+    /// Continue tokenizing after an error.
+    ///
+    /// Each error is followed by a [Kind::Unknown] token that spans the
+    /// input skipped to resynchronize: the offending char and any word chars
+    /// (or repetitions of the offending char) after it. This way the stream
+    /// of tokens still covers the whole input.
+    pub fn with_error_recovery(mut self) -> Self {
+        self.recover = true;
+        self
+    }
+
+    /// Tokenize according to keywords of the specified version of the
+    /// language, instead of the current one.
+    ///
+    /// Words that were not keywords in that version are tokenized as
+    /// [Kind::Ident].
+    pub fn with_version(mut self, version: Version) -> Self {
+        self.version = Some(version);
+        self
+    }
+
     pub fn validated_values(self) -> Validator<'a> {
         Validator::new(self)
     }
@@ -229,6 +363,7 @@ impl<'a> Tokenizer<'a> {
             position: self.position,
             off: self.off,
             dot: self.dot,
+            trivia_start: self.trivia_start,
         }
     }
 
@@ -236,6 +371,12 @@ impl<'a> Tokenizer<'a> {
         self.position = checkpoint.position;
         self.off = checkpoint.off;
         self.dot = checkpoint.dot;
+        self.trivia_start = checkpoint.trivia_start;
+        self.pending_unknown = None;
+    }
+
+    pub(crate) fn is_recovering(&self) -> bool {
+        self.recover
     }
 
     pub fn current_pos(&self) -> Pos {
@@ -249,6 +390,7 @@ impl<'a> Tokenizer<'a> {
             if at == self.off {
                 self.off = off;
                 self.position = next;
+                self.trivia_start = off - (next.offset - end.offset) as usize;
                 return Some(Ok((tok, end)));
             }
         }
@@ -273,6 +415,55 @@ impl<'a> Tokenizer<'a> {
         Some(Ok((token, end)))
     }
 
+    /// Reads a single whitespace or comment token from the region skipped
+    /// by the last [Tokenizer::skip_whitespace].
+    fn read_trivia(&mut self) -> Token<'a> {
+        let text = &self.buf[self.trivia_start..self.off];
+        let (kind, len) = if text.starts_with('#') {
+            (Kind::Comment, text.find(['\r', '\n']).unwrap_or(text.len()))
+        } else {
+            (Kind::Whitespace, text.find('#').unwrap_or(text.len()))
+        };
+
+        // position.offset is always ahead of self.off by the same amount
+        let start = self.position.offset - (self.off - self.trivia_start) as u64;
+        self.trivia_start += len;
+        Token {
+            kind,
+            text: text[..len].into(),
+            value: None,
+            span: Span {
+                start,
+                end: start + len as u64,
+            },
+        }
+    }
+
+    /// Consumes the input that could not be tokenized.
+    fn skip_unknown(&mut self) -> Token<'a> {
+        let start = self.position.offset;
+        let tail = &self.buf[self.off..];
+        let mut iter = tail.char_indices();
+        let (_, first) = iter.next().expect("error at end of input");
+        let len = iter
+            .find(|&(_, c)| c != first && c != '_' && !c.is_alphanumeric())
+            .map_or(tail.len(), |(idx, _)| idx);
+
+        self.update_position(len);
+        self.dot = false;
+        let text = &self.buf[self.off - len..self.off];
+        self.skip_whitespace();
+        Token {
+            kind: Kind::Unknown,
+            text: text.into(),
+            value: None,
+            span: Span {
+                start,
+                end: start + len as u64,
+            },
+        }
+    }
+
     fn peek_token(&mut self) -> Option<Result<(Kind, usize), Error>> {
         let tail = &self.buf[self.off..];
         let mut iter = tail.char_indices();
@@ -389,7 +580,7 @@ impl<'a> Tokenizer<'a> {
                     }
                     check_prohibited(c, false)?;
                 }
-                Err(Error::new("unterminated backtick name"))
+                Err(Error::new("unterminated backtick name").with_code(ErrorCode::UnterminatedLiteral))
             }
             '=' => Ok((Eq, 1)),
             ',' => Ok((Comma, 1)),
@@ -494,7 +685,7 @@ impl<'a> Tokenizer<'a> {
                                 }
                                 return Ok((Str, 2 + end + 2));
                             } else {
-                                return Err(Error::new("unterminated string started with $$"));
+                                return Err(Error::new("unterminated string started with $$").with_code(ErrorCode::UnterminatedLiteral));
                             }
                         }
                         '`' => {
@@ -532,7 +723,7 @@ impl<'a> Tokenizer<'a> {
                                 }
                                 check_prohibited(c, false)?;
                             }
-                            return Err(Error::new("unterminated backtick argument"));
+                            return Err(Error::new("unterminated backtick argument").with_code(ErrorCode::UnterminatedLiteral));
                         }
                         '0'..='9' => {}
                         c if c.is_alphabetic() || c == '_' => {
@@ -566,7 +757,8 @@ impl<'a> Tokenizer<'a> {
                                 return Err(Error::new(format_args!(
                                     "unterminated string started with {:?}",
                                     marker
-                                )));
+                                ))
+                                .with_code(ErrorCode::UnterminatedLiteral));
                             }
                         }
                         Some((_, '0'..='9')) => continue,
@@ -605,7 +797,7 @@ impl<'a> Tokenizer<'a> {
                                 ));
                             }
                             None => {
-                                return Err(Error::new("unclosed \\(name) token"));
+                                return Err(Error::new("unclosed \\(name) token").with_code(ErrorCode::UnterminatedLiteral));
                             }
                         }
                     };
@@ -671,7 +863,8 @@ impl<'a> Tokenizer<'a> {
         return Err(Error::new(format_args!(
             "unterminated string, quoted by `{}`",
             open_quote
-        )));
+        ))
+        .with_code(ErrorCode::UnterminatedLiteral));
     }
 
     fn parse_number(&mut self) -> Result<(Kind, usize), Error> {
@@ -854,6 +1047,7 @@ impl<'a> Tokenizer<'a> {
     }
 
     fn skip_whitespace(&mut self) {
+        self.trivia_start = self.off;
         let mut iter = self.buf[self.off..].char_indices();
         let idx = 'outer: loop {
             let (idx, cur_char) = match iter.next() {
@@ -919,7 +1113,10 @@ impl<'a> Tokenizer<'a> {
         self.keyword_buf.clear();
         self.keyword_buf.push_str(s);
         self.keyword_buf.make_ascii_lowercase();
-        keywords::lookup_all(&self.keyword_buf)
+        match self.version {
+            Some(version) => keywords::lookup_all_at(&self.keyword_buf, version),
+            None => keywords::lookup_all(&self.keyword_buf),
+        }
     }
 }
 
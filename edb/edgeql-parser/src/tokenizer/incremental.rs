@@ -0,0 +1,123 @@
+use crate::position::Span;
+
+use super::{Error, Kind, Token, Tokenizer};
+
+/// Replacement of a range of source text
+#[derive(Debug, Clone)]
+pub struct Edit<'a> {
+    /// Replaced range, as byte offsets into the text before the edit
+    pub span: Span,
+    pub replacement: &'a str,
+}
+
+impl<'a> Tokenizer<'a> {
+    /// Tokenizes the text with an edit applied, re-lexing only the region
+    /// that was damaged by the edit.
+    ///
+    /// The tokenizer must be created over the whole text after the edit and
+    /// configured the same way as the tokenizer that produced `old_tokens`
+    /// (the whole output of [Tokenizer], not [Tokenizer::validated_values]).
+    ///
+    /// Tokens in front of the edit are reused as-is. Lexing is restarted
+    /// after them and continues until it produces a token that is equal
+    /// (with span shifted by the edit) to one of the old tokens. From there
+    /// on, the old tokens are reused with their spans shifted.
+    ///
+    /// Returns the tokens and the errors encountered in the re-lexed region.
+    /// Unless the tokenizer is in error recovery mode, it stops at the first
+    /// error.
+    pub fn retokenize(mut self, old_tokens: &[Token], edit: &Edit) -> (Vec<Token<'a>>, Vec<Error>) {
+        let edit_end = edit.span.start + edit.replacement.len() as u64;
+        let delta = edit_end as i64 - edit.span.end as i64;
+
+        // the first token that could be affected by the edit, including
+        // the ones that end just in front of it, since they could be extended
+        let damaged = old_tokens
+            .iter()
+            .position(|t| t.span.end >= edit.span.start)
+            .unwrap_or(old_tokens.len());
+
+        let mut tokens: Vec<_> = old_tokens[..damaged]
+            .iter()
+            .map(|t| self.reuse(t, 0))
+            .collect();
+        if let Some(last) = tokens.last() {
+            let restart = (last.span.end - self.base_offset()) as usize;
+            if restart > self.off {
+                self.dot = old_tokens[..damaged]
+                    .iter()
+                    .rev()
+                    .find(|t| !matches!(t.kind, Kind::Whitespace | Kind::Comment))
+                    .map_or(false, |t| t.kind == Kind::Dot);
+                self.update_position(restart - self.off);
+                self.skip_whitespace();
+            } else {
+                // only trivia was reused, the rest of it is still pending
+                self.trivia_start = restart;
+            }
+        }
+
+        let mut errors = Vec::new();
+        let mut old_idx = damaged;
+        while let Some(res) = self.next() {
+            let token = match res {
+                Ok(token) => token,
+                Err(e) => {
+                    errors.push(e);
+                    if self.recover {
+                        continue;
+                    }
+                    break;
+                }
+            };
+
+            // Whitespace and comments don't reset the tokenizer state, so
+            // the streams can only be synchronized on other tokens.
+            let is_trivia = matches!(token.kind, Kind::Whitespace | Kind::Comment);
+            if token.span.start >= edit_end && !is_trivia {
+                // try to re-synchronize with the old tokens
+                let old_start = (token.span.start as i64 - delta) as u64;
+                while old_tokens
+                    .get(old_idx)
+                    .map_or(false, |t| t.span.start < old_start)
+                {
+                    old_idx += 1;
+                }
+
+                if let Some(old) = old_tokens.get(old_idx) {
+                    if old.span.start == old_start
+                        && old.kind == token.kind
+                        && old.span.end as i64 + delta == token.span.end as i64
+                    {
+                        tokens.push(token);
+                        let rest = &old_tokens[old_idx + 1..];
+                        tokens.extend(rest.iter().map(|t| self.reuse(t, delta)));
+                        break;
+                    }
+                }
+            }
+            tokens.push(token);
+        }
+        (tokens, errors)
+    }
+
+    /// Offset of the start of the buffer, when tokenizer was started with
+    /// [Tokenizer::new_at].
+    fn base_offset(&self) -> u64 {
+        self.position.offset - self.off as u64
+    }
+
+    fn reuse(&self, token: &Token, delta: i64) -> Token<'a> {
+        let start = (token.span.start as i64 + delta) as u64;
+        let end = (token.span.end as i64 + delta) as u64;
+
+        let base = self.base_offset();
+        let text = &self.buf[(start - base) as usize..(end - base) as usize];
+        Token {
+            kind: token.kind,
+            text: text.into(),
+            value: token.value.clone(),
+            span: Span { start, end },
+        }
+    }
+}
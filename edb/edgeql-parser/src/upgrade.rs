@@ -0,0 +1,115 @@
+//! Checks for names that will break on upgrade of the server, because they
+//! collide with keywords that are going to be reserved
+//! ([FUTURE_RESERVED_KEYWORDS]) or that are reserved in some positions only
+//! ([PARTIAL_RESERVED_KEYWORDS]).
+//!
+//! Names are found by parsing the source, as a block of statements or as an
+//! SDL document. The grammar already rejects future reserved keywords as
+//! names, so those the parser fails at are parsed as identifiers instead.
+
+use std::collections::HashSet;
+
+use crate::diagnostics::{ErrorCode, Suggestion};
+use crate::helpers::quote_name;
+use crate::keywords::{Keyword, FUTURE_RESERVED_KEYWORDS, PARTIAL_RESERVED_KEYWORDS};
+use crate::parser::{parse, with_start_token, Context, Spec, Terminal};
+use crate::rewrite::find_names;
+use crate::tokenizer::{Error, Kind, Token, Tokenizer};
+
+/// Reports each unquoted name that collides with a future or partially
+/// reserved keyword. Each of the errors has a suggestion that quotes the name.
+///
+/// Fails if the source cannot be tokenized or parsed.
+pub fn check_reserved_names(source: &str, spec: &Spec) -> Result<Vec<Error>, Error> {
+    let tokens = Tokenizer::new(source)
+        .validated_values()
+        .with_eof()
+        .collect::<Result<Vec<_>, _>>()?;
+    let names = match parse_names(&tokens, Kind::StartBlock, spec) {
+        Ok(names) => names,
+        Err(error) => parse_names(&tokens, Kind::StartSDLDocument, spec).map_err(|_| error)?,
+    };
+
+    let mut errors = Vec::new();
+    for token in &tokens {
+        let Kind::Keyword(Keyword(kw)) = token.kind else {
+            continue;
+        };
+        let is_future = FUTURE_RESERVED_KEYWORDS.contains(kw);
+        if !is_future && !PARTIAL_RESERVED_KEYWORDS.contains(kw) {
+            continue;
+        }
+        if !names.contains(&token.span.start) {
+            continue;
+        }
+
+        let reason = if is_future {
+            "will be reserved in a future version"
+        } else {
+            "is reserved in some positions"
+        };
+        let message = format!("Name '{}' {reason}", token.text);
+        let quoted = quote_name(&token.text);
+        errors.push(
+            Error::new(message)
+                .with_span(token.span)
+                .with_code(ErrorCode::ReservedKeyword)
+                .with_hint(format!("quote the name with backticks: {quoted}"))
+                .with_suggestion(Suggestion::replace(token.span, quoted)),
+        );
+    }
+    Ok(errors)
+}
+
+/// Quotes all names reported by [check_reserved_names].
+pub fn fix_reserved_names(source: &str, spec: &Spec) -> Result<String, Error> {
+    let errors = check_reserved_names(source, spec)?;
+
+    let mut fixed = source.to_string();
+    // spans are sorted, so applying from the end keeps them valid
+    for suggestion in errors.iter().rev().flat_map(|e| e.suggestions()) {
+        fixed = suggestion.apply(&fixed);
+    }
+    Ok(fixed)
+}
+
+/// Start offsets of names in the tokens, parsed from the start symbol.
+///
+/// Future reserved keywords which the parser fails at are parsed again as
+/// identifiers, until the tokens parse or no such keyword is left.
+fn parse_names(tokens: &[Token], start: Kind, spec: &Spec) -> Result<HashSet<u64>, Error> {
+    let mut as_idents = HashSet::new();
+    loop {
+        let terminals = tokens.iter().map(|token| {
+            let mut terminal = Terminal::from_token(token.clone());
+            if as_idents.contains(&token.span.start) {
+                terminal.kind = Kind::Ident;
+            }
+            terminal
+        });
+        let input = with_start_token(Terminal::from_start_kind(start), terminals);
+        let ctx = Context::new(spec);
+        let (cst, errors) = parse(&input, &ctx);
+        if let (Some(cst), true) = (cst, errors.is_empty()) {
+            return Ok(find_names(cst, spec));
+        }
+
+        let retry: Vec<u64> = errors
+            .iter()
+            .map(|e| e.span.start)
+            .filter(|start| !as_idents.contains(start) && is_future_reserved(tokens, *start))
+            .collect();
+        if retry.is_empty() {
+            let error = errors.into_iter().next();
+            return Err(error.unwrap_or_else(|| Error::new("cannot parse the source")));
+        }
+        as_idents.extend(retry);
+    }
+}
+
+fn is_future_reserved(tokens: &[Token], start: u64) -> bool {
+    tokens.iter().any(|t| {
+        t.span.start == start
+            && matches!(t.kind, Kind::Keyword(Keyword(kw)) if FUTURE_RESERVED_KEYWORDS.contains(kw))
+    })
+}
@@ -3,6 +3,7 @@ use std::str::FromStr;
 use bigdecimal::num_bigint::ToBigInt;
 use bigdecimal::BigDecimal;
 
+use crate::diagnostics::ErrorCode;
 use crate::helpers::{unquote_bytes, unquote_string};
 use crate::keywords::Keyword;
 use crate::position::{Pos, Span};
@@ -29,7 +30,15 @@ impl<'a> Iterator for Validator<'a> {
 
         token.value = match parse_value(&token) {
             Ok(x) => x,
-            Err(e) => return Some(Err(Error::new(e).with_span(token.span))),
+            Err(e) => {
+                if self.inner.is_recovering() {
+                    // emit the token after the error, as the tokenizer does
+                    token.kind = Kind::Unknown;
+                    self.peeked = Some(Some(Ok(token.clone())));
+                }
+                let error = Error::new(e).with_span(token.span);
+                return Some(Err(error.with_code(ErrorCode::InvalidLiteral)));
+            }
         };
 
         if let Some(keyword) = self.combine_multi_word_keywords(&token) {
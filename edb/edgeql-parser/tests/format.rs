@@ -0,0 +1,290 @@
+//! Layout is decided by trees parsed with the real grammar, which requires
+//! the spec to be embedded (see `build.rs`).
+
+use edgeql_parser::format::{format, KeywordCase, Options};
+use edgeql_parser::parser::spec;
+
+fn fmt(source: &str) -> String {
+    format(source, &Options::new(spec())).unwrap()
+}
+
+fn fmt_width(source: &str, width: usize) -> String {
+    let options = Options {
+        width,
+        ..Options::new(spec())
+    };
+    format(source, &options).unwrap()
+}
+
+#[test]
+fn single_line() {
+    assert_eq!(
+        fmt("select   User{name,email}filter .name='x' ;"),
+        "SELECT User { name, email } FILTER .name = 'x';\n"
+    );
+    assert_eq!(fmt(""), "");
+}
+
+#[test]
+fn clauses() {
+    assert_eq!(
+        fmt_width(
+            "select User { name } filter .name = 'x' order by .name limit 10",
+            40
+        ),
+        "\
+SELECT User { name }
+FILTER .name = 'x'
+ORDER BY .name
+LIMIT 10
+"
+    );
+}
+
+#[test]
+fn shapes() {
+    assert_eq!(
+        fmt_width(
+            "select User { name, friends: { name, email } filter .active }",
+            40
+        ),
+        "\
+SELECT User {
+    name,
+    friends: { name, email }
+    FILTER .active
+}
+"
+    );
+}
+
+#[test]
+fn set_literals() {
+    assert_eq!(
+        fmt("for x in { 1,2 } union (insert Foo { x := x })"),
+        "FOR x IN {1, 2} UNION (INSERT Foo { x := x })\n"
+    );
+}
+
+#[test]
+fn with_block() {
+    assert_eq!(
+        fmt_width("with a := 1, b := 2 select a + b", 20),
+        "\
+WITH a := 1, b := 2
+SELECT a + b
+"
+    );
+    assert_eq!(
+        fmt_width("with a := (select User), b := 2 select a", 30),
+        "\
+WITH a := (SELECT User),
+    b := 2
+SELECT a
+"
+    );
+}
+
+#[test]
+fn nested_statements() {
+    assert_eq!(
+        fmt_width(
+            "for x in {1, 2} union (insert Foo { y := (select Bar filter .id = x limit 1) })",
+            40
+        ),
+        "\
+FOR x IN {1, 2}
+UNION (
+    INSERT Foo {
+        y := (
+            SELECT Bar
+            FILTER .id = x
+            LIMIT 1
+        )
+    }
+)
+"
+    );
+}
+
+#[test]
+fn comments() {
+    let source = "\
+# leading
+with
+  # the user
+  u := (select User),  # trailing
+select u { name };
+select (
+   1, # one
+   2
+);
+";
+    assert_eq!(
+        fmt(source),
+        "\
+# leading
+WITH
+    # the user
+    u := (SELECT User), # trailing
+SELECT u { name };
+SELECT (
+    1, # one
+    2
+);
+"
+    );
+}
+
+#[test]
+fn comments_before_separators() {
+    assert_eq!(fmt("select 1 # one\n;"), "SELECT 1; # one\n");
+    assert_eq!(
+        fmt("select (a # x\n, b)"),
+        "\
+SELECT (
+    a, # x
+    b
+)
+"
+    );
+    assert_eq!(
+        fmt("with a := 1 # c\n, b := 2 select a"),
+        "\
+WITH a := 1, # c
+    b := 2
+SELECT a
+"
+    );
+    assert_eq!(
+        fmt("select (a\n# x\n, b)"),
+        "\
+SELECT (
+    a,
+    # x
+    b
+)
+"
+    );
+}
+
+#[test]
+fn blank_lines() {
+    let source = "\
+alter type User {
+    create property foo -> str;  # foo
+
+    create property bar -> str;
+};";
+    assert_eq!(
+        fmt(source),
+        "\
+ALTER TYPE User {
+    CREATE PROPERTY foo -> str; # foo
+
+    CREATE PROPERTY bar -> str;
+};
+"
+    );
+}
+
+#[test]
+fn keyword_case() {
+    let source = "Select User Order By .name";
+    let case = |keyword_case| {
+        let options = Options {
+            keyword_case,
+            ..Options::new(spec())
+        };
+        format(source, &options).unwrap()
+    };
+    assert_eq!(case(KeywordCase::Upper), "SELECT User ORDER BY .name\n");
+    assert_eq!(case(KeywordCase::Lower), "select User order by .name\n");
+    assert_eq!(case(KeywordCase::Preserve), "Select User Order By .name\n");
+}
+
+#[test]
+fn operators() {
+    assert_eq!(
+        fmt("select - x [1 : 2] ++ < str > y[0] ++ <array<str>>$z"),
+        "SELECT -x[1:2] ++ <str>y[0] ++ <array<str>>$z\n"
+    );
+    assert_eq!(fmt("select true < false"), "SELECT TRUE < FALSE\n");
+    assert_eq!(fmt("select false-1"), "SELECT FALSE - 1\n");
+}
+
+#[test]
+fn keywords_as_names() {
+    assert_eq!(
+        fmt("select Object { type := .type, abstract } filter .type = 'x'"),
+        "SELECT Object { type := .type, abstract } FILTER .type = 'x'\n"
+    );
+}
+
+#[test]
+fn ddl() {
+    assert_eq!(
+        fmt("create type Foo extending Bar { create link b -> Foo { on target delete allow; } };"),
+        "\
+CREATE TYPE Foo EXTENDING Bar {
+    CREATE LINK b -> Foo {
+        ON TARGET DELETE ALLOW;
+    }
+};
+"
+    );
+}
+
+#[test]
+fn sdl() {
+    let source = "\
+module default { type User extending Named {
+  required property name -> str { constraint exclusive; };
+  # computed
+  property upper := str_upper(.name);
+  index on (.name);
+} scalar type Tag extending str; };";
+    assert_eq!(
+        fmt(source),
+        "\
+MODULE default {
+    TYPE User EXTENDING Named {
+        REQUIRED PROPERTY name -> str {
+            CONSTRAINT exclusive;
+        };
+        # computed
+        PROPERTY upper := str_upper(.name);
+        INDEX ON (.name);
+    }
+    SCALAR TYPE Tag EXTENDING str;
+};
+"
+    );
+}
+
+#[test]
+fn parse_errors() {
+    let options = Options::new(spec());
+    let err = format("select (1, 2", &options).unwrap_err();
+    assert_eq!(err.message, "Missing ')'");
+    let err = format("select 1)", &options).unwrap_err();
+    assert_eq!(err.message, "Unexpected ')'");
+    // the error of SDL, which was parsed further than a block
+    let err = format("type User { property name -> str;", &options).unwrap_err();
+    assert_eq!(err.message, "Missing '}'");
+}
+
+#[test]
+fn idempotent() {
+    let source = "\
+with u := (select User filter .id = <uuid>$id), # trailing
+v := 1 select u { name, friends: { name } filter .name != 'x' } order by .name limit 10;
+select -x[1:2];
+select (a # x
+, b) # y
+;";
+    for width in [20, 40, 80] {
+        let once = fmt_width(source, width);
+        assert_eq!(fmt_width(&once, width), once);
+    }
+}
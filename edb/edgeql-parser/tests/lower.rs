@@ -0,0 +1,680 @@
+//! Lowering of trees parsed with the real grammar, which requires the spec
+//! to be embedded (see `build.rs`).
+
+use edgeql_parser::ast::*;
+use edgeql_parser::parser::lower::{lower_block, lower_expr, Statement};
+use edgeql_parser::parser::{spec, Context};
+use edgeql_parser::position::Span;
+use edgeql_parser::tokenizer::Error;
+
+fn try_lower(text: &str) -> Result<Vec<Statement>, Error> {
+    let ctx = Context::new(spec());
+    let res = ctx.parse_block(text);
+    assert!(!res.has_errors(), "{:?}", res.errors().collect::<Vec<_>>());
+    lower_block(res.cst.unwrap(), spec())
+}
+
+fn lower(text: &str) -> Vec<Statement> {
+    try_lower(text).unwrap()
+}
+
+fn lower_query(text: &str) -> Query {
+    match lower(text).pop() {
+        Some(Statement::Query(query)) => query,
+        other => panic!("expected a query, found {other:?}"),
+    }
+}
+
+fn try_lower_fragment(text: &str) -> Result<Expr, Error> {
+    let ctx = Context::new(spec());
+    let res = ctx.parse_fragment(text);
+    assert!(!res.has_errors(), "{:?}", res.errors().collect::<Vec<_>>());
+    lower_expr(res.cst.unwrap(), spec())
+}
+
+fn lower_fragment(text: &str) -> Expr {
+    try_lower_fragment(text).unwrap()
+}
+
+fn unwrap_select(query: &Query) -> &SelectQuery {
+    let QueryKind::SelectQuery(select) = &query.kind else {
+        panic!("expected a select, found {query:?}");
+    };
+    select
+}
+
+fn unwrap_constant(expr: &Expr) -> &Constant {
+    let ExprKind::BaseConstant(BaseConstant {
+        kind: BaseConstantKind::Constant(constant),
+    }) = &expr.kind
+    else {
+        panic!("expected a constant, found {expr:?}");
+    };
+    constant
+}
+
+fn unwrap_int(expr: &Expr) -> &str {
+    let constant = unwrap_constant(expr);
+    assert!(matches!(constant.kind, ConstantKind::INTEGER));
+    &constant.value
+}
+
+/// Names of steps of a path: object refs and pointers, with `@` for link
+/// properties and `[is T]` for type intersections.
+fn path_names(expr: &Expr) -> Vec<String> {
+    let ExprKind::Path(path) = &expr.kind else {
+        panic!("expected a path, found {expr:?}");
+    };
+    steps_names(path)
+}
+
+fn steps_names(path: &Path) -> Vec<String> {
+    (path.steps.iter())
+        .map(|step| match step {
+            PathSteps::ObjectRef(r) => r.name.clone(),
+            PathSteps::Ptr(Ptr { name, r#type, .. }) if r#type.is_some() => format!("@{name}"),
+            PathSteps::Ptr(Ptr { name, .. }) => name.clone(),
+            PathSteps::TypeIntersection(i) => format!("[is {}]", type_name(&i.r#type)),
+            _ => panic!("unexpected step {step:?}"),
+        })
+        .collect()
+}
+
+fn type_name(ty: &TypeExpr) -> String {
+    match &ty.kind {
+        TypeExprKind::TypeName(TypeName {
+            maintype:
+                BaseObjectRef {
+                    kind: BaseObjectRefKind::ObjectRef(r),
+                },
+            ..
+        }) => match &r.module {
+            Some(module) => format!("{module}::{}", r.name),
+            None => r.name.clone(),
+        },
+        TypeExprKind::TypeOp(op) => {
+            format!("{} {} {}", type_name(&op.left), op.op, type_name(&op.right))
+        }
+        _ => panic!("unexpected type {ty:?}"),
+    }
+}
+
+fn unwrap_ddl(statement: &Statement) -> &DDLOperation {
+    let Statement::DDL(ddl) = statement else {
+        panic!("expected a DDL command, found {statement:?}");
+    };
+    ddl
+}
+
+fn unwrap_named_ddl(ddl: &DDLOperation) -> &NamedDDL {
+    let DDLOperationKind::DDLCommand(DDLCommand {
+        kind: DDLCommandKind::NamedDDL(named),
+    }) = &ddl.kind
+    else {
+        panic!("expected a named DDL command, found {ddl:?}");
+    };
+    named
+}
+
+fn unwrap_create(ddl: &DDLOperation) -> (&ObjectRef, &CreateObject) {
+    let named = unwrap_named_ddl(ddl);
+    let NamedDDLKind::ObjectDDL(ObjectDDL {
+        kind: ObjectDDLKind::CreateObject(create),
+    }) = &named.kind
+    else {
+        panic!("expected CREATE, found {named:?}");
+    };
+    (&named.name, create)
+}
+
+#[test]
+fn block() {
+    let statements = lower("select 1 + 2 filter true; create module foo; drop module bar;");
+    assert_eq!(statements.len(), 3);
+
+    let Statement::Query(query) = &statements[0] else {
+        panic!("expected a query, found {:?}", statements[0]);
+    };
+    let select = unwrap_select(query);
+    assert!(!select.implicit);
+    assert!(select.limit.is_none());
+    let ExprKind::BinOp(bin_op) = &select.result.kind else {
+        panic!("expected a binary operator, found {:?}", select.result);
+    };
+    assert_eq!(bin_op.op, "+");
+    assert_eq!(unwrap_int(&bin_op.left), "1");
+    assert_eq!(unwrap_int(&bin_op.right), "2");
+    let filter = unwrap_constant(select.r#where.as_ref().unwrap());
+    assert!(matches!(filter.kind, ConstantKind::BOOLEAN));
+    assert_eq!(filter.value, "true");
+
+    let (name, create) = unwrap_create(unwrap_ddl(&statements[1]));
+    assert_eq!(name.name, "foo");
+    assert!(!create.create_if_not_exists);
+    assert!(matches!(
+        create.kind,
+        Some(CreateObjectKind::CreateModule(_))
+    ));
+
+    let drop = unwrap_named_ddl(unwrap_ddl(&statements[2]));
+    assert_eq!(drop.name.name, "bar");
+    assert!(matches!(
+        drop.kind,
+        NamedDDLKind::ObjectDDL(ObjectDDL {
+            kind: ObjectDDLKind::DropObject(DropObject {
+                kind: Some(DropObjectKind::DropModule(_))
+            })
+        })
+    ));
+
+    let statements = lower("create module a::b if not exists");
+    let (name, create) = unwrap_create(unwrap_ddl(&statements[0]));
+    assert_eq!(name.name, "a::b");
+    assert!(create.create_if_not_exists);
+
+    assert!(lower(";;").is_empty());
+}
+
+#[test]
+fn select_limit() {
+    let query = lower_query("select x limit 5");
+
+    // LIMIT is applied on an implicit inner query
+    let outer = unwrap_select(&query);
+    assert!(!outer.implicit);
+    assert_eq!(unwrap_int(outer.limit.as_ref().unwrap()), "5");
+    let ExprKind::Query(inner) = &outer.result.kind else {
+        panic!("expected a query, found {outer:?}");
+    };
+    let inner = unwrap_select(inner);
+    assert!(inner.implicit);
+    assert!(inner.limit.is_none());
+    assert_eq!(path_names(&inner.result), ["x"]);
+}
+
+#[test]
+fn operators() {
+    let expr = lower_fragment("-1 and not x.y");
+    let ExprKind::BinOp(bin_op) = &expr.kind else {
+        panic!("expected a binary operator, found {expr:?}");
+    };
+    assert_eq!(bin_op.op, "AND");
+    assert_eq!(unwrap_int(&bin_op.left), "-1");
+
+    let ExprKind::UnaryOp(not) = &bin_op.right.kind else {
+        panic!("expected an unary operator, found {:?}", bin_op.right);
+    };
+    assert_eq!(not.op, "NOT");
+    let ExprKind::Path(path) = &not.operand.kind else {
+        panic!("expected a path, found {:?}", not.operand);
+    };
+    let [PathSteps::ObjectRef(x), PathSteps::Ptr(y)] = &path.steps[..] else {
+        panic!("expected two steps, found {path:?}");
+    };
+    assert_eq!(x.name, "x");
+    assert_eq!(y.name, "y");
+    assert_eq!(y.direction.as_deref(), Some(">"));
+}
+
+#[test]
+fn function_call_args() {
+    let expr = lower_fragment("std::round(1, d := 2)");
+    let ExprKind::FunctionCall(call) = &expr.kind else {
+        panic!("expected a function call, found {expr:?}");
+    };
+    let FunctionCallFunc::Tuple((module, name)) = &call.func else {
+        panic!("expected a qualified name, found {:?}", call.func);
+    };
+    assert_eq!((module.as_str(), name.as_str()), ("std", "round"));
+    assert_eq!(call.args.len(), 1);
+    assert_eq!(unwrap_int(&call.args[0]), "1");
+    assert_eq!(unwrap_int(&call.kwargs["d"]), "2");
+}
+
+#[test]
+fn function_call_errors() {
+    let err = try_lower_fragment("std::round(d := 1, d := 2)").unwrap_err();
+    assert_eq!(err.message, "duplicate named argument `d`");
+    assert_eq!(err.span, Span { start: 19, end: 20 });
+
+    let err = try_lower_fragment("std::round(d := 1, 2)").unwrap_err();
+    assert_eq!(err.message, "positional argument after named argument `d`");
+    assert_eq!(err.span, Span { start: 19, end: 20 });
+}
+
+#[test]
+fn types() {
+    let expr = lower_fragment("<optional str>x is not (A | default::B)");
+    let ExprKind::IsOp(is_op) = &expr.kind else {
+        panic!("expected IS, found {expr:?}");
+    };
+    assert_eq!(is_op.op, "IS NOT");
+    assert_eq!(type_name(&is_op.right), "A | default::B");
+    let ExprKind::TypeCast(cast) = &is_op.left.kind else {
+        panic!("expected a cast, found {:?}", is_op.left);
+    };
+    assert_eq!(type_name(&cast.r#type), "str");
+    assert!(matches!(
+        cast.cardinality_mod,
+        Some(CardinalityModifier::Optional)
+    ));
+
+    let expr = lower_fragment("x[is Foo].y");
+    assert_eq!(path_names(&expr), ["x", "[is Foo]", "y"]);
+
+    let expr = lower_fragment("<tuple<a: array<str>, b: int64>>x");
+    let ExprKind::TypeCast(cast) = &expr.kind else {
+        panic!("expected a cast, found {expr:?}");
+    };
+    assert_eq!(type_name(&cast.r#type), "tuple");
+    let TypeExprKind::TypeName(TypeName {
+        subtypes: Some(subtypes),
+        ..
+    }) = &cast.r#type.kind
+    else {
+        panic!("expected subtypes, found {:?}", cast.r#type);
+    };
+    let names: Vec<_> = subtypes.iter().map(|t| t.name.as_deref()).collect();
+    assert_eq!(names, [Some("a"), Some("b")]);
+    assert_eq!(type_name(&subtypes[0]), "array");
+    assert_eq!(type_name(&subtypes[1]), "int64");
+
+    let err = try_lower_fragment("<tuple<a: int64, str>>x").unwrap_err();
+    assert_eq!(
+        err.message,
+        "mixing named and unnamed subtype declarations is not supported"
+    );
+    assert_eq!(err.span, Span { start: 7, end: 20 });
+    let err = try_lower_fragment("<enum<a, 'b'>>x").unwrap_err();
+    assert_eq!(
+        err.message,
+        "mixing string type literals and type names is not supported"
+    );
+    let err = try_lower_fragment("<array<>>x").unwrap_err();
+    assert_eq!(
+        err.message,
+        "parametrized type must have at least one argument"
+    );
+    assert_eq!(err.span, Span { start: 6, end: 7 });
+}
+
+#[test]
+fn shape() {
+    let query = lower_query(
+        "select User { name, friends: { name } filter .x order by .name limit 3, \
+        required multi f := .x, @p, [is Admin].level, *, Foo[is Bar].** } filter .a",
+    );
+    let select = unwrap_select(&query);
+    assert!(select.r#where.is_some());
+    let ExprKind::Shape(shape) = &select.result.kind else {
+        panic!("expected a shape, found {:?}", select.result);
+    };
+    assert_eq!(path_names(shape.expr.as_ref().unwrap()), ["User"]);
+
+    let [name, friends, f, p, level, splat, typed_splat] = &shape.elements[..] else {
+        panic!("expected 7 elements, found {shape:?}");
+    };
+    assert_eq!(steps_names(&name.expr), ["name"]);
+    assert_eq!(name.elements.as_ref().map(Vec::len), Some(0));
+    assert!(name.compexpr.is_none());
+
+    assert_eq!(steps_names(&friends.expr), ["friends"]);
+    let [friend_name] = &friends.elements.as_ref().unwrap()[..] else {
+        panic!("expected a sub-shape, found {friends:?}");
+    };
+    assert_eq!(steps_names(&friend_name.expr), ["name"]);
+    assert!(friends.r#where.is_some());
+    assert_eq!(friends.orderby.as_ref().map(Vec::len), Some(1));
+    assert_eq!(unwrap_int(friends.limit.as_ref().unwrap()), "3");
+
+    assert_eq!(steps_names(&f.expr), ["f"]);
+    assert_eq!(f.required, Some(true));
+    assert!(matches!(f.cardinality, Some(SchemaCardinality::Many)));
+    assert!(matches!(f.operation.op, ShapeOp::ASSIGN));
+    assert_eq!(path_names(f.compexpr.as_ref().unwrap()), ["x"]);
+
+    assert_eq!(steps_names(&p.expr), ["@p"]);
+    assert_eq!(steps_names(&level.expr), ["[is Admin]", "level"]);
+
+    let [PathSteps::Splat(splat)] = &splat.expr.steps[..] else {
+        panic!("expected a splat, found {splat:?}");
+    };
+    assert_eq!(splat.depth, 1);
+    assert!(splat.r#type.is_none());
+    let [PathSteps::Splat(splat)] = &typed_splat.expr.steps[..] else {
+        panic!("expected a splat, found {typed_splat:?}");
+    };
+    assert_eq!(splat.depth, 2);
+    assert_eq!(type_name(splat.r#type.as_ref().unwrap()), "Foo");
+    assert_eq!(
+        type_name(&splat.intersection.as_ref().unwrap().r#type),
+        "Bar"
+    );
+
+    let expr = lower_fragment("{ a := 1, multi b := 2 }");
+    let ExprKind::Shape(shape) = &expr.kind else {
+        panic!("expected a shape, found {expr:?}");
+    };
+    assert!(shape.expr.is_none());
+    assert_eq!(shape.elements.len(), 2);
+    assert!(matches!(
+        shape.elements[1].cardinality,
+        Some(SchemaCardinality::Many)
+    ));
+}
+
+#[test]
+fn with_block() {
+    let query = lower_query("with module foo, m as module std, x := 1 select x");
+    let [QueryAliases::ModuleAliasDecl(foo), QueryAliases::ModuleAliasDecl(std), QueryAliases::AliasedExpr(x)] =
+        &query.aliases.as_ref().unwrap()[..]
+    else {
+        panic!("expected three aliases, found {:?}", query.aliases);
+    };
+    assert_eq!((foo.module.as_str(), foo.alias.as_deref()), ("foo", None));
+    assert_eq!(
+        (std.module.as_str(), std.alias.as_deref()),
+        ("std", Some("m"))
+    );
+    assert_eq!(x.alias, "x");
+    assert_eq!(unwrap_int(&x.expr), "1");
+    let select = unwrap_select(&query);
+    assert_eq!(path_names(&select.result), ["x"]);
+}
+
+#[test]
+fn insert() {
+    let query = lower_query(
+        "insert default::User { name := 'x' } unless conflict on .name else (select User)",
+    );
+    let QueryKind::InsertQuery(insert) = &query.kind else {
+        panic!("expected INSERT, found {query:?}");
+    };
+    assert_eq!(insert.subject.name, "User");
+    assert_eq!(insert.subject.module.as_deref(), Some("default"));
+    assert_eq!(insert.shape.len(), 1);
+    let Some((Some(on), Some(else_expr))) = &insert.unless_conflict else {
+        panic!("expected UNLESS CONFLICT ON ... ELSE, found {insert:?}");
+    };
+    assert_eq!(path_names(on), ["name"]);
+    assert!(matches!(else_expr.kind, ExprKind::Query(_)));
+
+    let query = lower_query("insert User unless conflict");
+    let QueryKind::InsertQuery(insert) = &query.kind else {
+        panic!("expected INSERT, found {query:?}");
+    };
+    assert!(insert.shape.is_empty());
+    assert!(matches!(insert.unless_conflict, Some((None, None))));
+
+    let query = lower_query("insert User");
+    let QueryKind::InsertQuery(insert) = &query.kind else {
+        panic!("expected INSERT, found {query:?}");
+    };
+    assert!(insert.unless_conflict.is_none());
+}
+
+#[test]
+fn insert_errors() {
+    let err = try_lower("insert User.friends").unwrap_err();
+    assert_eq!(
+        err.message,
+        "INSERT only works with object types, not arbitrary expressions"
+    );
+    assert_eq!(err.span, Span { start: 7, end: 19 });
+
+    let err = try_lower("insert (User if true else Admin)").unwrap_err();
+    assert_eq!(
+        err.message,
+        "INSERT only works with object types, not conditional expressions"
+    );
+    assert!(err.hint.is_some());
+
+    let err = try_lower("insert { name := 'x' }").unwrap_err();
+    assert_eq!(
+        err.message,
+        "insert shape expressions must have a type name"
+    );
+    assert_eq!(err.span, Span { start: 7, end: 22 });
+}
+
+#[test]
+fn update_delete() {
+    let query = lower_query("update User filter .a set { x := 1, y += 2, z -= 3 }");
+    let QueryKind::UpdateQuery(update) = &query.kind else {
+        panic!("expected UPDATE, found {query:?}");
+    };
+    assert_eq!(path_names(&update.subject), ["User"]);
+    assert!(update.r#where.is_some());
+    let ops: Vec<_> = (update.shape.iter())
+        .map(|el| match el.operation.op {
+            ShapeOp::ASSIGN => ":=",
+            ShapeOp::APPEND => "+=",
+            ShapeOp::SUBTRACT => "-=",
+            ShapeOp::MATERIALIZE => "materialize",
+        })
+        .collect();
+    assert_eq!(ops, [":=", "+=", "-="]);
+
+    let query = lower_query("delete User filter .a order by .b limit 2");
+    let QueryKind::DeleteQuery(delete) = &query.kind else {
+        panic!("expected DELETE, found {query:?}");
+    };
+    assert_eq!(path_names(&delete.subject), ["User"]);
+    assert!(delete.r#where.is_some());
+    assert_eq!(delete.orderby.as_ref().map(Vec::len), Some(1));
+    assert!(delete.offset.is_none());
+    assert_eq!(unwrap_int(delete.limit.as_ref().unwrap()), "2");
+}
+
+#[test]
+fn for_query() {
+    for (text, optional, has_union) in [
+        ("for x in {1, 2} union (select x)", false, true),
+        ("for x in {1, 2} select x", false, false),
+        ("for optional x in {1, 2} union (select x)", true, true),
+    ] {
+        let query = lower_query(text);
+        let QueryKind::ForQuery(for_query) = &query.kind else {
+            panic!("expected FOR, found {query:?}");
+        };
+        assert_eq!(for_query.iterator_alias, "x");
+        assert_eq!(for_query.optional, optional);
+        assert_eq!(for_query.has_union, has_union);
+        assert!(matches!(for_query.iterator.kind, ExprKind::Set(_)));
+        assert!(matches!(for_query.result.kind, ExprKind::Query(_)));
+    }
+}
+
+#[test]
+fn group() {
+    let query = lower_query("group u := User using k := .x by .name, (.a, @b), rollup(k), { k }");
+    let QueryKind::GroupQuery(group) = &query.kind else {
+        panic!("expected GROUP, found {query:?}");
+    };
+    assert_eq!(group.subject_alias.as_deref(), Some("u"));
+    assert_eq!(path_names(&group.subject), ["User"]);
+    let using = group.using.as_ref().unwrap();
+    assert_eq!(using.len(), 1);
+    assert_eq!(using[0].alias, "k");
+
+    let [name, list, rollup, sets] = &group.by[..] else {
+        panic!("expected 4 grouping elements, found {:?}", group.by);
+    };
+    let GroupingElementKind::GroupingSimple(GroupingSimple {
+        element: GroupingSimpleElement::Path(name),
+    }) = &name.kind
+    else {
+        panic!("expected a path, found {name:?}");
+    };
+    assert!(name.partial);
+    assert_eq!(steps_names(name), ["name"]);
+
+    let GroupingElementKind::GroupingSimple(GroupingSimple {
+        element: GroupingSimpleElement::GroupingIdentList(list),
+    }) = &list.kind
+    else {
+        panic!("expected an identifier list, found {list:?}");
+    };
+    assert_eq!(list.elements.len(), 2);
+
+    let GroupingElementKind::GroupingOperation(rollup) = &rollup.kind else {
+        panic!("expected ROLLUP, found {rollup:?}");
+    };
+    assert_eq!(rollup.oper, "rollup");
+    assert!(matches!(
+        &rollup.elements[..],
+        [GroupingOperationElements::ObjectRef(k)] if k.name == "k"
+    ));
+
+    let GroupingElementKind::GroupingSets(sets) = &sets.kind else {
+        panic!("expected grouping sets, found {sets:?}");
+    };
+    assert_eq!(sets.sets.len(), 1);
+}
+
+#[test]
+fn ddl() {
+    let statements = lower(
+        "create abstract type Foo extending Bar, Baz {
+            create required property name: str {
+                create constraint exclusive;
+            };
+            create multi link friends -> Foo {
+                on target delete allow;
+            };
+            create property upper := str_upper(.name);
+            create index on (.name);
+        }",
+    );
+    let ddl = unwrap_ddl(&statements[0]);
+    let (name, create) = unwrap_create(ddl);
+    assert_eq!(name.name, "Foo");
+    assert!(create.r#abstract);
+    let Some(CreateObjectKind::CreateExtendingObject(extending)) = &create.kind else {
+        panic!("expected CREATE TYPE, found {create:?}");
+    };
+    assert!(matches!(
+        extending.kind,
+        Some(CreateExtendingObjectKind::CreateObjectType(_))
+    ));
+    let bases: Vec<_> = extending.bases.iter().map(type_name).collect();
+    assert_eq!(bases, ["Bar", "Baz"]);
+    assert_eq!(ddl.commands.len(), 4);
+
+    let (name, create) = unwrap_create(&ddl.commands[0]);
+    assert_eq!(name.name, "name");
+    let Some(CreateObjectKind::CreateConcretePointer(pointer)) = &create.kind else {
+        panic!("expected a pointer, found {create:?}");
+    };
+    assert_eq!(pointer.is_required, Some(true));
+    assert!(pointer.cardinality.is_none());
+    let CreateConcretePointerTarget::TypeExpr(target) = &pointer.target else {
+        panic!("expected a type, found {:?}", pointer.target);
+    };
+    assert_eq!(type_name(target), "str");
+    let (name, create) = unwrap_create(&ddl.commands[0].commands[0]);
+    assert_eq!(name.name, "exclusive");
+    assert!(matches!(
+        create.kind,
+        Some(CreateObjectKind::CreateConcreteConstraint(_))
+    ));
+
+    let (name, create) = unwrap_create(&ddl.commands[1]);
+    assert_eq!(name.name, "friends");
+    let Some(CreateObjectKind::CreateConcretePointer(pointer)) = &create.kind else {
+        panic!("expected a pointer, found {create:?}");
+    };
+    assert!(matches!(
+        pointer.kind,
+        Some(CreateConcretePointerKind::CreateConcreteLink(_))
+    ));
+    assert!(matches!(pointer.cardinality, Some(SchemaCardinality::Many)));
+    assert!(matches!(
+        ddl.commands[1].commands[0].kind,
+        DDLOperationKind::OnTargetDelete(OnTargetDelete {
+            cascade: Some(LinkTargetDeleteAction::Allow)
+        })
+    ));
+
+    let (_, create) = unwrap_create(&ddl.commands[2]);
+    let Some(CreateObjectKind::CreateConcretePointer(pointer)) = &create.kind else {
+        panic!("expected a pointer, found {create:?}");
+    };
+    assert!(matches!(
+        pointer.target,
+        CreateConcretePointerTarget::Expr(_)
+    ));
+
+    let (name, create) = unwrap_create(&ddl.commands[3]);
+    assert_eq!(
+        (name.module.as_deref(), name.name.as_str()),
+        (Some("__"), "idx")
+    );
+    let Some(CreateObjectKind::CreateConcreteIndex(index)) = &create.kind else {
+        panic!("expected an index, found {create:?}");
+    };
+    assert_eq!(path_names(&index.expr), ["name"]);
+
+    let statements = lower(
+        "alter type Foo {
+            rename to Bar;
+            alter property name {
+                set required using ('x');
+                set type str using (<str>.name);
+            };
+            drop link friends;
+        }",
+    );
+    let ddl = unwrap_ddl(&statements[0]);
+    let NamedDDLKind::Rename(rename) = &unwrap_named_ddl(&ddl.commands[0]).kind else {
+        panic!("expected RENAME, found {:?}", ddl.commands[0]);
+    };
+    assert_eq!(rename.new_name.name, "Bar");
+    let alter = &ddl.commands[1].commands;
+    let DDLOperationKind::SetField(SetField {
+        kind: Some(SetFieldKind::SetPointerOptionality(optionality)),
+        ..
+    }) = &alter[0].kind
+    else {
+        panic!("expected SET REQUIRED, found {:?}", alter[0]);
+    };
+    assert!(optionality.fill_expr.is_some());
+    assert!(matches!(
+        alter[1].kind,
+        DDLOperationKind::SetField(SetField {
+            kind: Some(SetFieldKind::SetPointerType(_)),
+            ..
+        })
+    ));
+}
+
+#[test]
+fn ddl_errors() {
+    let err =
+        try_lower("create type Foo { create property a { create constraint x; } }").unwrap_err();
+    assert_eq!(err.message, "computed property without expression");
+    assert_eq!(err.span, Span { start: 34, end: 35 });
+
+    let err = try_lower("create abstract link a extending b { extending c }").unwrap_err();
+    assert_eq!(err.message, "specifying EXTENDING twice is not allowed");
+
+    let err = try_lower("create type Foo { create property a::b -> str }").unwrap_err();
+    assert_eq!(err.message, "unexpected fully-qualified name");
+
+    let err = try_lower("create abstract constraint c(named only a: str, b: str)").unwrap_err();
+    assert_eq!(
+        err.message,
+        "positional argument `b` follows NAMED ONLY argument `a`"
+    );
+}
+
+#[test]
+fn unsupported() {
+    let err = try_lower("create function foo() -> str using ('foo')").unwrap_err();
+    assert!(
+        err.message.starts_with("cannot lower CreateFunctionStmt."),
+        "{}",
+        err.message
+    );
+    assert_eq!(err.span, Span { start: 0, end: 41 });
+}
@@ -0,0 +1,512 @@
+//! Normalization finds contexts of literals in trees parsed with the real
+//! grammar, which requires the spec to be embedded (see `build.rs`).
+
+use edgeql_parser::normalize::literals::{LiteralContext, LiteralPolicy};
+use edgeql_parser::normalize::{self, normalize_batch, normalize_with, Entry, Error};
+use edgeql_parser::normalize::{encode_variables, Encoder, Options, Value, Variable};
+use edgeql_parser::parser::spec;
+use num_bigint::BigInt;
+
+fn normalize(text: &str) -> Result<Entry, Error> {
+    normalize::normalize(text, spec())
+}
+
+fn with_policy(policy: LiteralPolicy) -> Options<'static> {
+    Options {
+        policy,
+        ..Options::new(spec())
+    }
+}
+
+
+#[test]
+fn test_verbatim() {
+    let entry = normalize(r###"
+        SELECT $1 + $2
+    "###.trim()).unwrap();
+    assert_eq!(entry.processed_source, "SELECT $1 + $2");
+    assert_eq!(entry.variables, vec![vec![]]);
+}
+
+#[test]
+fn test_configure() {
+    let entry = normalize(r###"
+        CONFIGURE INSTANCE SET some_setting := 7
+    "###.trim()).unwrap();
+    assert_eq!(entry.processed_source, "CONFIGURE INSTANCE SET some_setting := 7");
+    assert_eq!(entry.variables, vec![] as Vec<Vec<Variable>>);
+}
+
+#[test]
+fn test_int() {
+    let entry = normalize(r###"
+        SELECT 1 + 2
+    "###).unwrap();
+    assert_eq!(entry.processed_source,
+        "SELECT <lit int64>$0+<lit int64>$1");
+    assert_eq!(entry.variables, vec![vec![
+        Variable {
+            value: Value::Int(1),
+        },
+        Variable {
+            value: Value::Int(2),
+        }
+    ]]);
+}
+
+#[test]
+fn test_str() {
+    let entry = normalize(r#"
+        SELECT "x" + "yy"
+    "#).unwrap();
+    assert_eq!(entry.processed_source,
+        "SELECT <lit str>$0+<lit str>$1");
+    assert_eq!(entry.variables, vec![vec![
+        Variable {
+            value: Value::String("x".into()),
+        },
+        Variable {
+            value: Value::String("yy".into()),
+        }
+    ]]);
+}
+
+#[test]
+fn test_float() {
+    let entry = normalize(r###"
+        SELECT 1.5 + 23.25
+    "###).unwrap();
+    assert_eq!(entry.processed_source,
+        "SELECT <lit float64>$0+<lit float64>$1");
+    assert_eq!(entry.variables, vec![vec![
+        Variable {
+            value: Value::Float(1.5),
+        },
+        Variable {
+            value: Value::Float(23.25),
+        }
+    ]]);
+}
+
+#[test]
+fn test_bigint() {
+    let entry = normalize(r###"
+        SELECT 1n + 23n
+    "###).unwrap();
+    assert_eq!(entry.processed_source,
+        "SELECT <lit bigint>$0+<lit bigint>$1");
+    assert_eq!(entry.variables, vec![vec![
+        Variable {
+            value: Value::BigInt("1".into()),
+        },
+        Variable {
+            value: Value::BigInt(BigInt::from(23).to_str_radix(16)),
+        }
+    ]]);
+}
+
+#[test]
+fn test_bigint_exponent() {
+    let entry = normalize(r###"
+        SELECT 1e10n + 23e13n
+    "###).unwrap();
+    assert_eq!(entry.processed_source,
+        "SELECT <lit bigint>$0+<lit bigint>$1");
+    assert_eq!(entry.variables, vec![vec![
+        Variable {
+            value: Value::BigInt(BigInt::from(10000000000u64).to_str_radix(16)),
+        },
+        Variable {
+            value: Value::BigInt(BigInt::from(230000000000000u64).to_str_radix(16)),
+        }
+    ]]);
+}
+
+#[test]
+fn test_decimal() {
+    let entry = normalize(r###"
+        SELECT 1.33n + 23.77n
+    "###).unwrap();
+    assert_eq!(entry.processed_source,
+        "SELECT <lit decimal>$0+<lit decimal>$1");
+    assert_eq!(entry.variables, vec![vec![
+        Variable {
+            value: Value::Decimal("1.33".parse().unwrap()),
+        },
+        Variable {
+            value: Value::Decimal("23.77".parse().unwrap()),
+        }
+    ]]);
+}
+
+#[test]
+fn test_positional() {
+    let entry = normalize(r###"
+        SELECT <int64>$0 + 2
+    "###).unwrap();
+    assert_eq!(entry.processed_source, "SELECT<int64>$0+<lit int64>$1");
+    assert_eq!(entry.variables, vec![vec![
+        Variable {
+            value: Value::Int(2),
+        }
+    ]]);
+}
+
+#[test]
+fn test_named() {
+    let entry = normalize(r###"
+        SELECT <int64>$test_var + 2
+    "###).unwrap();
+    assert_eq!(entry.processed_source,
+        "SELECT<int64>$test_var+<lit int64>$__edb_arg_1");
+    assert_eq!(entry.variables, vec![vec![
+        Variable {
+            value: Value::Int(2),
+        }
+    ]]);
+}
+
+#[test]
+fn test_limit_1() {
+    let entry = normalize(r###"
+        SELECT User { one := 1 } LIMIT 1
+    "###).unwrap();
+    assert_eq!(entry.processed_source,
+        "SELECT User{one:=<lit int64>$0}LIMIT 1");
+    assert_eq!(entry.variables, vec![vec![
+        Variable {
+            value: Value::Int(1),
+        },
+    ]]);
+}
+
+#[test]
+fn test_tuple_access() {
+    let entry = normalize(r###"
+        SELECT User { one := 2, two := .field.2, three := .field  . 3 }
+    "###).unwrap();
+    assert_eq!(entry.processed_source,
+        "SELECT User{one:=<lit int64>$0,\
+                     two:=.field.2,three:=.field.3}");
+    assert_eq!(entry.variables, vec![vec![
+        Variable {
+            value: Value::Int(2),
+        },
+    ]]);
+}
+
+#[test]
+fn test_script() {
+    let entry = normalize(r###"
+        SELECT 1 + 2;
+        SELECT 2;
+    "###).unwrap();
+    assert_eq!(
+        entry.processed_source,
+        "SELECT <lit int64>$0+<lit int64>$1;\
+        SELECT <lit int64>$2;",
+    );
+    assert_eq!(entry.variables, vec![
+        vec![
+            Variable {
+                value: Value::Int(1),
+            },
+            Variable {
+                value: Value::Int(2),
+            }
+        ],
+        vec![
+            Variable {
+                value: Value::Int(2),
+            }
+        ],
+    ]);
+}
+
+#[test]
+fn test_script_with_args() {
+    let entry = normalize(r###"
+        SELECT 2 + $1;
+        SELECT $1 + 2;
+    "###).unwrap();
+    assert_eq!(
+        entry.processed_source,
+        "SELECT <lit int64>$2+$1;SELECT$1+<lit int64>$3;",
+    );
+    assert_eq!(entry.variables, vec![
+        vec![
+            Variable {
+                value: Value::Int(2),
+            }
+        ],
+        vec![
+            Variable {
+                value: Value::Int(2),
+            }
+        ],
+    ]);
+}
+
+#[test]
+fn test_batch() {
+    let texts = [
+        "SELECT 1",
+        "SELECT 'x'",
+        "SELECT 2",
+        "SELECT 'unterminated",
+        "SELECT 'y'",
+        "SELECT $a",
+    ];
+    let options = Options::new(spec());
+    for threads in [0, 1, 4] {
+        let results = normalize_batch(&texts, threads, &options);
+        let indexes: Vec<_> = results.iter().map(|(idx, _)| *idx).collect();
+        assert_eq!(indexes, [0, 1, 3, 5]);
+
+        for (idx, result) in results {
+            match (idx, result) {
+                (3, Err(_)) => {}
+                (idx, Ok(entry)) => {
+                    let expected = normalize(texts[idx]).unwrap();
+                    assert_eq!(entry.processed_source, expected.processed_source);
+                    assert_eq!(entry.variables, expected.variables);
+                }
+                (idx, Err(e)) => panic!("{idx}: {e:?}"),
+            }
+        }
+    }
+    assert!(normalize_batch::<&str>(&[], 0, &options).is_empty());
+}
+
+#[test]
+fn test_bool_and_bytes() {
+    let entry = normalize(r###"
+        SELECT true AND NOT false ++ b'\x01'
+    "###).unwrap();
+    assert_eq!(entry.processed_source,
+        "SELECT <lit bool>$0 AND NOT <lit bool>$1++<lit bytes>$2");
+    assert_eq!(entry.variables, vec![vec![
+        Variable {
+            value: Value::Bool(true),
+        },
+        Variable {
+            value: Value::Bool(false),
+        },
+        Variable {
+            value: Value::Bytes(vec![1]),
+        },
+    ]]);
+}
+
+#[test]
+fn test_negative() {
+    let entry = normalize(r###"
+        SELECT (-1, - 2.5, -9223372036854775808, x - 3, -2 ^ 2)
+    "###).unwrap();
+    assert_eq!(entry.processed_source,
+        "SELECT(<lit int64>$0,<lit float64>$1,<lit int64>$2,\
+        x-<lit int64>$3,-<lit int64>$4^<lit int64>$5)");
+    assert_eq!(entry.variables, vec![vec![
+        Variable {
+            value: Value::Int(-1),
+        },
+        Variable {
+            value: Value::Float(-2.5),
+        },
+        Variable {
+            value: Value::Int(i64::MIN),
+        },
+        Variable {
+            value: Value::Int(3),
+        },
+        Variable {
+            value: Value::Int(2),
+        },
+        Variable {
+            value: Value::Int(2),
+        },
+    ]]);
+}
+
+#[test]
+fn test_int64_range() {
+    let entry = normalize("SELECT 9223372036854775807").unwrap();
+    assert_eq!(entry.processed_source, "SELECT <lit int64>$0");
+
+    // fit into int64 only with a minus
+    for text in [
+        "SELECT 9223372036854775808",
+        "SELECT 18446744073709551615",
+        "SELECT -9223372036854775809",
+        "SELECT (- 18446744073709551615)",
+    ] {
+        let entry = normalize(text).unwrap();
+        assert_eq!(entry.processed_source, text);
+        assert_eq!(entry.variables, vec![vec![]]);
+    }
+}
+
+#[test]
+fn test_array() {
+    let entry = normalize(r###"
+        SELECT ['a', 'b'] ++ x[1] ++ [1, -2] ++ [1, 'a']
+    "###).unwrap();
+    assert_eq!(entry.processed_source,
+        "SELECT <lit array<str>>$0++x[<lit int64>$1]\
+        ++<lit array<int64>>$2++[<lit int64>$3,<lit str>$4]");
+    assert_eq!(entry.variables[0][0], Variable {
+        value: Value::Array(vec![
+            Value::String("a".into()),
+            Value::String("b".into()),
+        ]),
+    });
+    assert_eq!(entry.variables[0][2], Variable {
+        value: Value::Array(vec![Value::Int(1), Value::Int(-2)]),
+    });
+
+    // arrays of any length share the cache entry
+    let one = normalize("SELECT [true]").unwrap();
+    let three = normalize("SELECT [true, false, true]").unwrap();
+    assert_eq!(one.processed_source, "SELECT <lit array<bool>>$0");
+    assert_eq!(one.hash, three.hash);
+
+    let entry = normalize("SELECT [] ++ [[1]]").unwrap();
+    assert_eq!(entry.processed_source, "SELECT[]++[<lit array<int64>>$0]");
+}
+
+#[test]
+fn test_script_with_ddl() {
+    let entry = normalize(r###"
+        SET GLOBAL foo := 1;
+        CREATE TYPE Foo { CREATE PROPERTY bar -> str { SET default := 'x'; }; };
+        SELECT 2;
+        CONFIGURE SESSION SET x := 3;
+        SELECT 'a'
+    "###).unwrap();
+    assert_eq!(
+        entry.processed_source,
+        "SET GLOBAL foo:=1;\
+        CREATE TYPE Foo{CREATE PROPERTY bar->str{SET default:='x';};};\
+        SELECT <lit int64>$0;\
+        CONFIGURE SESSION SET x:=3;\
+        SELECT <lit str>$1",
+    );
+    assert_eq!(entry.first_arg, Some(0));
+    assert_eq!(entry.variables, vec![
+        vec![],
+        vec![],
+        vec![
+            Variable {
+                value: Value::Int(2),
+            }
+        ],
+        vec![],
+        vec![
+            Variable {
+                value: Value::String("a".into()),
+            }
+        ],
+    ]);
+}
+
+#[test]
+fn test_script_statements() {
+    // no statement after the last semicolon
+    let entry = normalize("SELECT 1; CREATE TYPE Foo;").unwrap();
+    assert_eq!(entry.processed_source, "SELECT <lit int64>$0;CREATE TYPE Foo;");
+    assert_eq!(entry.variables, vec![
+        vec![Variable { value: Value::Int(1) }],
+        vec![],
+    ]);
+
+    let text = "WITH MODULE foo CREATE TYPE Bar { CREATE PROPERTY x -> int64 { \
+        SET default := 1; }; };";
+    let entry = normalize(text).unwrap();
+    assert_eq!(entry.processed_source, text);
+    assert_eq!(entry.variables, Vec::<Vec<_>>::new());
+}
+
+#[test]
+fn test_literal_policy() {
+    let entry = normalize("SELECT (1, 2).0 LIMIT 1").unwrap();
+    assert_eq!(entry.processed_source,
+        "SELECT(<lit int64>$0,<lit int64>$1).0 LIMIT 1");
+
+    let entry = normalize("SELECT 1 OFFSET 2 LIMIT 5").unwrap();
+    assert_eq!(entry.processed_source,
+        "SELECT <lit int64>$0 OFFSET <lit int64>$1 LIMIT <lit int64>$2");
+
+    let options = with_policy(LiteralPolicy::new([LiteralContext::Limit])
+        .with_limit_one(false));
+    let entry = normalize_with("SELECT 1 OFFSET 2 LIMIT 5", &options)
+        .unwrap();
+    assert_eq!(entry.processed_source,
+        "SELECT <lit int64>$0 OFFSET <lit int64>$1 LIMIT 5");
+
+    let options = with_policy(LiteralPolicy::default().with_limit_one(false));
+    let entry = normalize_with("SELECT 1 LIMIT 1", &options).unwrap();
+    assert_eq!(entry.processed_source,
+        "SELECT <lit int64>$0 LIMIT <lit int64>$1");
+}
+
+#[test]
+fn test_literal_context_from_grammar() {
+    let entry = normalize("SELECT Foo LIMIT (1)").unwrap();
+    assert_eq!(entry.processed_source, "SELECT Foo LIMIT (1)");
+    assert_eq!(entry.variables, vec![vec![]]);
+
+    let entry = normalize("SELECT Foo LIMIT (7)").unwrap();
+    assert_eq!(entry.processed_source, "SELECT Foo LIMIT(<lit int64>$0)");
+
+    // the sign belongs to the literal
+    let entry = normalize("SELECT Foo LIMIT -1").unwrap();
+    assert_eq!(entry.processed_source, "SELECT Foo LIMIT -1");
+
+    let options = with_policy(LiteralPolicy::new([LiteralContext::Limit]));
+    let entry = normalize_with("SELECT Foo LIMIT (7)", &options).unwrap();
+    assert_eq!(entry.processed_source, "SELECT Foo LIMIT (7)");
+}
+
+#[test]
+fn test_literal_context_guessed() {
+    // the query cannot be parsed
+    let entry = normalize("SELECT (1, 2).0 LIMIT 1 +").unwrap();
+    assert_eq!(entry.processed_source,
+        "SELECT(<lit int64>$0,<lit int64>$1).0 LIMIT 1+");
+
+    let entry = normalize("SELECT 'a' OFFSET 2 LIMIT -1 +").unwrap();
+    assert_eq!(entry.processed_source,
+        "SELECT <lit str>$0 OFFSET <lit int64>$1 LIMIT-1+");
+
+    let options = with_policy(LiteralPolicy::new([LiteralContext::Offset]));
+    let entry = normalize_with("SELECT 'a' OFFSET 2 LIMIT -3 +", &options)
+        .unwrap();
+    assert_eq!(entry.processed_source,
+        "SELECT <lit str>$0 OFFSET 2 LIMIT <lit int64>$1+");
+}
+
+/// Encodes integers as decimal text
+struct TextEncoder;
+
+impl Encoder for TextEncoder {
+    fn encode_value(&self, buf: &mut Vec<u8>, value: &Value)
+        -> Result<(), String>
+    {
+        match value {
+            Value::Int(v) => buf.extend_from_slice(v.to_string().as_bytes()),
+            _ => return Err("not an int".into()),
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_encoder() {
+    let entry = normalize("SELECT 1 + 23").unwrap();
+    let encoded = encode_variables(&TextEncoder, &entry.variables[0]);
+    assert_eq!(encoded.unwrap(), b"\0\0\0\x011\0\0\0\x0223");
+
+    let entry = normalize("SELECT 'x'").unwrap();
+    let encoded = encode_variables(&TextEncoder, &entry.variables[0]);
+    assert_eq!(encoded.unwrap_err(), "not an int");
+}
@@ -0,0 +1,539 @@
+use indexmap::IndexMap;
+
+use edgeql_parser::diagnostics::{ErrorCode, Label, Suggestion};
+use edgeql_parser::parser::visit::{find_all, walk, Visitor, Walk};
+use edgeql_parser::parser::{
+    Action, CSTNode, Context, Event, EventLog, OwnedCSTNode, Production, RecoveryConfig, Reduce,
+    Skipped, Spec,
+};
+use edgeql_parser::position::Span;
+use edgeql_parser::tokenizer::Kind;
+
+/// Grammar with a single production: `S := STARTBLOCK IDENT EOF`
+fn spec() -> Spec {
+    sequence_spec(
+        &[Kind::StartBlock, Kind::Ident, Kind::EOF],
+        "reduce_STARTBLOCK_IDENT_EOF",
+    )
+}
+
+/// Grammar with a single production `S`, which is a sequence of tokens.
+fn sequence_spec(kinds: &[Kind], production: &str) -> Spec {
+    let action = |kind, action| IndexMap::from_iter([(kind, action)]);
+
+    let mut actions: Vec<_> = (kinds.iter().enumerate())
+        .map(|(i, kind)| action(*kind, Action::Shift(i + 1)))
+        .collect();
+    let reduce = Reduce {
+        production_id: 0,
+        non_term: "S".to_string(),
+        cnt: kinds.len(),
+    };
+    actions.push(action(Kind::EOI, Action::Reduce(reduce)));
+    actions.push(action(Kind::EOI, Action::Shift(kinds.len() + 2)));
+    actions.push(IndexMap::new());
+
+    let mut goto = vec![IndexMap::new(); actions.len()];
+    goto[0].insert("S".to_string(), kinds.len() + 1);
+
+    Spec {
+        actions,
+        goto,
+        start: "S".to_string(),
+        inlines: IndexMap::new(),
+        production_names: vec![("S".to_string(), production.to_string())],
+    }
+}
+
+#[test]
+fn parse_block_ok() {
+    let spec = spec();
+    let ctx = Context::new(&spec);
+
+    let res = ctx.parse_block("foo");
+    assert!(!res.has_errors(), "{:?}", res.errors().collect::<Vec<_>>());
+
+    let Some(CSTNode::Production(prod)) = res.cst else {
+        panic!("expected a production, got {:?}", res.cst);
+    };
+    assert_eq!(prod.id, 0);
+    assert_eq!(prod.args.len(), 3);
+    let CSTNode::Terminal(ident) = &prod.args[1] else {
+        panic!("expected a terminal");
+    };
+    assert_eq!(ident.text, "foo");
+}
+
+#[test]
+fn parser_errors() {
+    let spec = spec();
+    let ctx = Context::new(&spec);
+
+    let res = ctx.parse_block("foo bar");
+    assert!(res.tokenizer_errors.is_empty());
+    assert_eq!(res.parser_errors.len(), 1);
+    assert_eq!(res.parser_errors[0].message, "Unexpected 'bar'");
+}
+
+#[test]
+fn tokenizer_errors() {
+    let spec = spec();
+    let ctx = Context::new(&spec);
+
+    let res = ctx.parse_block("foo ~");
+    assert_eq!(res.tokenizer_errors.len(), 1);
+    assert_eq!(res.errors().count(), res.parser_errors.len() + 1);
+    assert!(res.has_errors());
+}
+
+#[test]
+fn start_token_span() {
+    let spec = spec();
+    let ctx = Context::new(&spec);
+
+    // start token is moved to the first token, which is EOF
+    let res = ctx.parse_block("  ");
+    assert_eq!(res.parser_errors.len(), 1);
+    let span = res.parser_errors[0].span;
+    assert_eq!((span.start, span.end), (2, 2));
+}
+
+#[test]
+fn visit_parents() {
+    struct Depths(Vec<(usize, Option<Kind>)>);
+
+    impl<'a> Visitor<'a> for Depths {
+        fn enter(&mut self, node: &'a CSTNode<'a>, parents: &[&'a CSTNode<'a>]) -> Walk {
+            self.0
+                .push((parents.len(), node.as_terminal().map(|t| t.kind)));
+            Walk::Continue
+        }
+    }
+
+    let spec = spec();
+    let ctx = Context::new(&spec);
+    let res = ctx.parse_block("  foo");
+    let cst = res.cst.unwrap();
+
+    let mut depths = Depths(Vec::new());
+    walk(cst, &mut depths);
+    assert_eq!(
+        depths.0,
+        vec![
+            (0, None),
+            (1, Some(Kind::StartBlock)),
+            (1, Some(Kind::Ident)),
+            (1, Some(Kind::EOF)),
+        ]
+    );
+
+    let span = cst.span().unwrap();
+    assert_eq!((span.start, span.end), (2, 5));
+}
+
+#[test]
+fn find_by_name() {
+    let spec = spec();
+    let ctx = Context::new(&spec);
+    let res = ctx.parse_block("foo");
+    let cst = res.cst.unwrap();
+
+    let found = find_all(cst, &spec, "S", None);
+    assert_eq!(found.len(), 1);
+    assert!(found[0].parent().is_none());
+
+    let found = find_all(cst, &spec, "S", Some("reduce_STARTBLOCK_IDENT_EOF"));
+    assert_eq!(found.len(), 1);
+    assert!(find_all(cst, &spec, "S", Some("reduce_empty")).is_empty());
+    assert!(find_all(cst, &spec, "Expr", None).is_empty());
+}
+
+#[test]
+fn find_inlined() {
+    let mut spec = spec();
+    spec.production_names
+        .push(("Expr".to_string(), "reduce_S".to_string()));
+    spec.production_names
+        .push(("Wrapper".to_string(), "reduce_Expr".to_string()));
+
+    // S, which replaced inlined Expr, wrapped into Wrapper
+    let inner = CSTNode::Production(Production {
+        id: 0,
+        args: &[],
+        inlined_ids: Some(&[1]),
+    });
+    let args = [inner];
+    let root = CSTNode::Production(Production {
+        id: 2,
+        args: &args,
+        inlined_ids: None,
+    });
+
+    for non_term in ["S", "Expr"] {
+        let found = find_all(&root, &spec, non_term, None);
+        assert_eq!(found.len(), 1, "{non_term}");
+        assert!(spec.is_production(found[0].parent().unwrap(), "Wrapper", None));
+    }
+    assert_eq!(find_all(&root, &spec, "Expr", Some("reduce_S")).len(), 1);
+
+    // there are no terminals
+    assert!(root.span().is_none());
+}
+
+#[test]
+fn owned_cst() {
+    let spec = spec();
+
+    let owned = {
+        let ctx = Context::new(&spec);
+        let res = ctx.parse_block("foo");
+        OwnedCSTNode::from(res.cst.unwrap())
+    };
+
+    let OwnedCSTNode::Production(prod) = &owned else {
+        panic!("expected a production, got {owned:?}");
+    };
+    assert_eq!(prod.args.len(), 3);
+
+    // allocate back into a new context
+    let ctx = Context::new(&spec);
+    let cst = ctx.alloc_cst(&owned);
+    assert!(spec.is_production(&cst, "S", None));
+    assert_eq!(cst.children()[1].as_terminal().unwrap().text, "foo");
+    assert_eq!(OwnedCSTNode::from(&cst), owned);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn owned_cst_serde() {
+    let spec = spec();
+    let ctx = Context::new(&spec);
+    let res = ctx.parse_block("foo");
+    let owned = OwnedCSTNode::from(res.cst.unwrap());
+
+    let json = serde_json::to_string(&owned).unwrap();
+    let back: OwnedCSTNode = serde_json::from_str(&json).unwrap();
+    assert_eq!(back, owned);
+}
+
+#[test]
+fn diagnostics_missing_bracket() {
+    use Kind::*;
+
+    let spec = sequence_spec(
+        &[StartBlock, OpenParen, Ident, CloseParen, EOF],
+        "reduce_STARTBLOCK_LPAREN_IDENT_RPAREN_EOF",
+    );
+    let ctx = Context::new(&spec);
+
+    let res = ctx.parse_block("(foo");
+    assert_eq!(res.parser_errors.len(), 1);
+    let error = &res.parser_errors[0];
+    assert_eq!(error.message, "Missing ')'");
+    assert_eq!(error.code, Some(ErrorCode::MissingToken));
+    assert_eq!(
+        error.labels(),
+        &[Label {
+            span: Span { start: 0, end: 1 },
+            message: "unclosed '('".to_string(),
+        }]
+    );
+    assert_eq!(error.suggestions(), &[Suggestion::insert(4, ")")]);
+    assert_eq!(error.suggestions()[0].apply("(foo"), "(foo)");
+}
+
+#[test]
+fn diagnostics_reserved_keyword() {
+    let spec = spec();
+    let ctx = Context::new(&spec);
+
+    let res = ctx.parse_block("select foo");
+    assert_eq!(res.parser_errors.len(), 1);
+    let error = &res.parser_errors[0];
+    assert_eq!(error.message, "Unexpected keyword 'SELECT'");
+    assert_eq!(error.code, Some(ErrorCode::ReservedKeyword));
+    assert_eq!(error.suggestions()[0].apply("select foo"), "`select` foo");
+}
+
+#[test]
+fn diagnostics_unexpected() {
+    let spec = spec();
+    let ctx = Context::new(&spec);
+
+    let res = ctx.parse_block("foo bar");
+    let error = &res.parser_errors[0];
+    assert_eq!(error.code, Some(ErrorCode::UnexpectedToken));
+    assert_eq!(error.suggestions()[0].apply("foo bar"), "foo ");
+    assert!(error.labels().is_empty());
+}
+
+#[test]
+fn diagnostics_sql_keyword() {
+    let spec = spec();
+    let ctx = Context::new(&spec);
+
+    let res = ctx.parse_block("foo WHERE");
+    let error = &res.parser_errors[0];
+    assert_eq!(error.message, "Unexpected 'WHERE'");
+    assert_eq!(error.code, Some(ErrorCode::UnsupportedKeyword));
+    assert!(error.hint.as_deref().unwrap().contains("filter"));
+    assert_eq!(error.suggestions()[0].apply("foo WHERE"), "foo filter");
+}
+
+#[test]
+fn diagnostics_trailing_comma() {
+    use Kind::*;
+
+    let spec = sequence_spec(
+        &[StartBlock, OpenParen, Ident, CloseParen, EOF],
+        "reduce_STARTBLOCK_LPAREN_IDENT_RPAREN_EOF",
+    );
+    let ctx = Context::new(&spec);
+
+    let res = ctx.parse_block("(foo,)");
+    let error = &res.parser_errors[0];
+    assert_eq!(error.message, "Unexpected ','");
+
+    let spec = sequence_spec(
+        &[StartBlock, OpenParen, Ident, Comma, Ident, CloseParen, EOF],
+        "reduce_STARTBLOCK_LPAREN_IDENT_COMMA_IDENT_RPAREN_EOF",
+    );
+    let ctx = Context::new(&spec);
+
+    let res = ctx.parse_block("(foo,)");
+    let error = &res.parser_errors[0];
+    assert_eq!(error.message, "Unexpected trailing comma");
+    assert_eq!(error.span, Span { start: 4, end: 5 });
+    assert_eq!(error.suggestions()[0].apply("(foo,)"), "(foo)");
+}
+
+#[test]
+fn diagnostics_tokenizer() {
+    let spec = spec();
+    let ctx = Context::new(&spec);
+
+    let res = ctx.parse_block("foo ~");
+    assert_eq!(res.tokenizer_errors[0].code, Some(ErrorCode::InvalidToken));
+
+    let res = ctx.parse_block("foo 'bar");
+    assert_eq!(
+        res.tokenizer_errors[0].code,
+        Some(ErrorCode::UnterminatedLiteral)
+    );
+
+    let res = ctx.parse_block(r"foo '\q'");
+    assert_eq!(
+        res.tokenizer_errors[0].code,
+        Some(ErrorCode::InvalidLiteral)
+    );
+}
+
+#[test]
+fn recovery_disabled() {
+    let spec = spec();
+    let ctx = Context::new(&spec).with_recovery(RecoveryConfig::disabled());
+
+    let res = ctx.parse_block("foo bar baz");
+    assert!(res.cst.is_none());
+    assert_eq!(res.parser_errors.len(), 1);
+    assert_eq!(res.parser_errors[0].message, "Unexpected 'bar'");
+    assert_eq!(res.parser_errors[0].code, Some(ErrorCode::UnexpectedToken));
+
+    let res = ctx.parse_block("foo");
+    assert!(!res.has_errors());
+    assert!(res.cst.is_some());
+}
+
+#[test]
+fn recovery_injection_cost() {
+    use Kind::*;
+
+    let spec = sequence_spec(
+        &[StartBlock, OpenParen, Ident, CloseParen, EOF],
+        "reduce_STARTBLOCK_LPAREN_IDENT_RPAREN_EOF",
+    );
+
+    let ctx = Context::new(&spec);
+    let res = ctx.parse_block("(foo");
+    assert_eq!(res.parser_errors[0].message, "Missing ')'");
+
+    // injecting ')' costs more than the parser is allowed to spend
+    let recovery = RecoveryConfig::default().with_injection_cost(CloseParen, 20);
+    let ctx = Context::new(&spec).with_recovery(recovery);
+    let res = ctx.parse_block("(foo");
+    assert!(res.cst.is_none());
+    assert_eq!(res.parser_errors[0].message, "Unexpected end of file");
+}
+
+#[test]
+fn recovered_missing_node() {
+    use Kind::*;
+
+    let spec = sequence_spec(
+        &[StartBlock, OpenParen, Ident, CloseParen, EOF],
+        "reduce_STARTBLOCK_LPAREN_IDENT_RPAREN_EOF",
+    );
+    let ctx = Context::new(&spec);
+
+    let res = ctx.parse_block("(foo");
+    let cst = res.cst.unwrap();
+    assert!(cst.is_recovered());
+    let CSTNode::Missing(paren) = &cst.children()[3] else {
+        panic!("expected a missing node, got {:?}", cst.children()[3]);
+    };
+    assert_eq!(paren.kind, CloseParen);
+    assert_eq!(paren.span, Span { start: 4, end: 4 });
+    assert!(cst.children()[2].as_terminal().is_some());
+
+    let owned = OwnedCSTNode::from(cst);
+    assert_eq!(OwnedCSTNode::from(&ctx.alloc_cst(&owned)), owned);
+}
+
+#[test]
+fn recovered_skipped_node() {
+    let spec = spec();
+    let ctx = Context::new(&spec);
+
+    let res = ctx.parse_block("foo bar baz");
+    let cst = res.cst.unwrap();
+    assert!(cst.is_recovered());
+    assert_eq!(cst.span(), Some(Span { start: 0, end: 11 }));
+
+    let CSTNode::Skipped(Skipped { tokens, node }) = &cst.children()[2] else {
+        panic!("expected a skipped node, got {:?}", cst.children()[2]);
+    };
+    let skipped: Vec<_> = tokens.iter().map(|t| t.text.as_str()).collect();
+    assert_eq!(skipped, ["bar", "baz"]);
+    assert_eq!(node.as_terminal().unwrap().kind, Kind::EOF);
+
+    let owned = OwnedCSTNode::from(cst);
+    assert_eq!(OwnedCSTNode::from(&ctx.alloc_cst(&owned)), owned);
+
+    let res = ctx.parse_block("foo");
+    assert!(!res.cst.unwrap().is_recovered());
+}
+
+#[test]
+fn trace_events() {
+    let spec = spec();
+    let log = EventLog::new();
+    let ctx = Context::new(&spec).with_tracer(&log);
+
+    ctx.parse_block("foo");
+    assert_eq!(
+        log.lines(),
+        [
+            "token StartBlock",
+            "  shift StartBlock -> 1",
+            "token 'foo'",
+            "  shift 'foo' -> 2",
+            "token end of file",
+            "  shift end of file -> 3",
+            "token end of input",
+            "  reduce S.reduce_STARTBLOCK_IDENT_EOF (3) -> 4",
+            "  shift end of input -> 5",
+        ]
+    );
+}
+
+#[test]
+fn trace_recovery() {
+    let spec = spec();
+    let events = std::cell::RefCell::new(Vec::new());
+    let tracer = |event: &Event| {
+        if !matches!(event, Event::Token { .. } | Event::Shift { .. }) {
+            events.borrow_mut().push(event.to_string());
+        }
+    };
+    let ctx = Context::new(&spec).with_tracer(&tracer);
+
+    ctx.parse_block("foo bar");
+    assert_eq!(
+        events.into_inner(),
+        [
+            "  unexpected 'bar' in 2",
+            "  skip 'bar' (cost 3)",
+            "  reduce S.reduce_STARTBLOCK_IDENT_EOF (3) -> 4",
+        ]
+    );
+}
+
+/// Grammar with two productions:
+/// `S := STARTBLOCK P EOF` and `P := ( IDENT )`
+fn nested_spec() -> Spec {
+    use Kind::*;
+
+    let action = |kind, action| IndexMap::from_iter([(kind, action)]);
+    let reduce = |production_id: usize, non_term: &str, cnt| {
+        Action::Reduce(Reduce {
+            production_id,
+            non_term: non_term.to_string(),
+            cnt,
+        })
+    };
+
+    let actions = vec![
+        action(StartBlock, Action::Shift(1)),
+        action(OpenParen, Action::Shift(2)),
+        action(Ident, Action::Shift(3)),
+        action(CloseParen, Action::Shift(4)),
+        action(EOF, reduce(1, "P", 3)),
+        action(EOF, Action::Shift(6)),
+        action(EOI, reduce(0, "S", 3)),
+        action(EOI, Action::Shift(8)),
+        IndexMap::new(),
+    ];
+    let mut goto = vec![IndexMap::new(); actions.len()];
+    goto[0].insert("S".to_string(), 7);
+    goto[1].insert("P".to_string(), 5);
+
+    Spec {
+        actions,
+        goto,
+        start: "S".to_string(),
+        inlines: IndexMap::new(),
+        production_names: vec![
+            ("S".to_string(), "reduce_STARTBLOCK_P_EOF".to_string()),
+            ("P".to_string(), "reduce_LPAREN_IDENT_RPAREN".to_string()),
+        ],
+    }
+}
+
+#[test]
+fn complete_expected_tokens() {
+    let spec = nested_spec();
+    let ctx = Context::new(&spec);
+
+    let completion = ctx.complete_block("", 0);
+    assert_eq!(completion.expected, [Kind::OpenParen]);
+    assert_eq!(completion.non_term, Some("S"));
+    assert_eq!(completion.word, None);
+
+    let completion = ctx.complete_block("(foo)", 1);
+    assert_eq!(completion.expected, [Kind::Ident]);
+    assert_eq!(completion.non_term, Some("P"));
+
+    let completion = ctx.complete_block("( foo ", 6);
+    assert_eq!(completion.expected, [Kind::CloseParen]);
+    assert_eq!(completion.non_term, Some("P"));
+
+    let completion = ctx.complete_block("(foo)", 5);
+    assert_eq!(completion.expected, []);
+}
+
+#[test]
+fn complete_word() {
+    let spec = nested_spec();
+    let ctx = Context::new(&spec);
+
+    // the word being typed is ignored
+    let completion = ctx.complete_block("(fo", 3);
+    assert_eq!(completion.expected, [Kind::Ident]);
+    assert_eq!(completion.non_term, Some("P"));
+    assert_eq!(completion.word, Some(Span { start: 1, end: 3 }));
+    assert_eq!(completion.keywords().count(), 0);
+
+    // errors before the cursor are recovered from
+    let completion = ctx.complete_block("(foo bar ", 9);
+    assert_eq!(completion.expected, [Kind::CloseParen]);
+}
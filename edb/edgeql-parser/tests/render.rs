@@ -0,0 +1,254 @@
+use edgeql_parser::diagnostics::{ErrorCode, Suggestion};
+use edgeql_parser::expr;
+use edgeql_parser::position::{InflatedPos, Span};
+use edgeql_parser::render::{Format, Renderer};
+use edgeql_parser::schema_file;
+use edgeql_parser::tokenizer::Error;
+
+fn span(start: u64, end: u64) -> Span {
+    Span { start, end }
+}
+
+fn render(source: &str, error: Error) -> String {
+    Renderer::new(source).render(&[error])
+}
+
+#[test]
+fn snippet() {
+    let error = Error::new("Missing ')'")
+        .with_span(span(4, 4))
+        .with_code(ErrorCode::MissingToken)
+        .with_label(span(0, 1), "unclosed '('")
+        .with_suggestion(Suggestion::insert(4, ")"));
+
+    assert_eq!(
+        render("(foo", error),
+        "\
+error[E0102]: Missing ')'
+ --> 1:5
+  |
+1 | (foo
+  |     ^
+  | - unclosed '('
+  = help: insert ')'
+"
+    );
+}
+
+#[test]
+fn hint_and_details() {
+    let error = Error::new("Unexpected keyword 'SELECT'")
+        .with_span(span(15, 21))
+        .with_hint("quote it")
+        .with_details("it is reserved");
+
+    assert_eq!(
+        Renderer::new("select 1;\nwith select := 1")
+            .with_file_name("q.edgeql")
+            .render(&[error]),
+        "\
+error: Unexpected keyword 'SELECT'
+ --> q.edgeql:2:6
+  |
+2 | with select := 1
+  |      ^^^^^^
+  = hint: quote it
+  = note: it is reserved
+"
+    );
+}
+
+#[test]
+fn tabs_and_wide_chars() {
+    let source = "\tselect '💣', x";
+    let error = Error::new("Unexpected 'x'").with_span(span(16, 17));
+
+    assert_eq!(
+        Renderer::new(source).with_tab_width(2).render(&[error]),
+        "\
+error: Unexpected 'x'
+ --> 1:15
+  |
+1 |   select '💣', x
+  |                ^
+"
+    );
+}
+
+#[test]
+fn json_position() {
+    let source = "select 1;\n\tselect '💣', x";
+    let error = Error::new("Unexpected 'x'").with_span(span(26, 27));
+    let out = Renderer::new(source)
+        .with_format(Format::Json)
+        .render(&[error]);
+    let out: serde_json::Value = serde_json::from_str(&out).unwrap();
+
+    // same as positions in other errors
+    let pos = InflatedPos::from_offset(source.as_bytes(), 26)
+        .unwrap()
+        .deflate();
+    assert_eq!((pos.line, pos.column), (2, 15));
+    assert_eq!(
+        out[0]["start"],
+        serde_json::json!({"line": 2, "column": 15})
+    );
+}
+
+#[test]
+fn multi_line_span() {
+    let source = "select {\n  a,\n  b,\n  c,\n  d,\n  e,\n};";
+    let error = Error::new("bad shape").with_span(span(7, 35));
+
+    assert_eq!(
+        render(source, error),
+        "\
+error: bad shape
+ --> 1:8
+  |
+1 | select {
+  |        ^
+...
+7 | };
+  | ^
+"
+    );
+}
+
+#[test]
+fn crlf() {
+    // span within the line ending
+    let error = Error::new("bad").with_span(span(2, 3));
+    assert_eq!(
+        render("a\r\nb", error),
+        "\
+error: bad
+ --> 1:2
+  |
+1 | a
+  |  ^
+"
+    );
+
+    // span ending within the line ending
+    let error = Error::new("bad").with_span(span(0, 2));
+    assert_eq!(
+        render("a\r\nb", error),
+        "\
+error: bad
+ --> 1:1
+  |
+1 | a
+  | ^
+"
+    );
+
+    let error = Error::new("bad").with_span(span(0, 4));
+    assert_eq!(
+        render("a\r\nb\r\n", error),
+        "\
+error: bad
+ --> 1:1
+  |
+1 | a
+  | ^
+2 | b
+  | ^
+"
+    );
+
+    let error = Error::new("bad").with_span(span(3, 3));
+    let out = Renderer::new("a\r\nb")
+        .with_format(Format::Json)
+        .render(&[error]);
+    let out: serde_json::Value = serde_json::from_str(&out).unwrap();
+    assert_eq!(out[0]["start"], serde_json::json!({"line": 2, "column": 1}));
+}
+
+#[test]
+fn multiple_errors() {
+    let errors = [
+        Error::new("first").with_span(span(0, 1)),
+        Error::new("second").with_span(span(2, 3)),
+    ];
+    let out = Renderer::new("a b").render(&errors);
+    assert!(out.starts_with("error: first\n"));
+    assert!(out.contains("\n\nerror: second\n"));
+}
+
+#[test]
+fn ansi() {
+    let error = Error::new("Unexpected 'x'").with_span(span(0, 1));
+    let out = Renderer::new("x")
+        .with_format(Format::Ansi)
+        .render(&[error]);
+    assert!(out.starts_with("\x1b[1;31merror\x1b[0m"));
+    assert!(out.contains("\x1b[1;31m^\x1b[0m"));
+}
+
+#[test]
+fn json() {
+    let error = Error::new("Missing ')'")
+        .with_span(span(100, 100)) // out of range
+        .with_code(ErrorCode::MissingToken)
+        .with_label(span(0, 1), "unclosed '('")
+        .with_suggestion(Suggestion::insert(4, ")"));
+
+    let out = Renderer::new("(foo")
+        .with_format(Format::Json)
+        .render(&[error]);
+    let out: serde_json::Value = serde_json::from_str(&out).unwrap();
+    assert_eq!(
+        out,
+        serde_json::json!([{
+            "code": "E0102",
+            "message": "Missing ')'",
+            "span": {"start": 4, "end": 4},
+            "start": {"line": 1, "column": 5},
+            "end": {"line": 1, "column": 5},
+            "hint": null,
+            "details": null,
+            "labels": [{"span": {"start": 0, "end": 1}, "message": "unclosed '('"}],
+            "suggestions": [{
+                "message": "insert ')'",
+                "span": {"start": 4, "end": 4},
+                "replacement": ")",
+            }],
+        }])
+    );
+}
+
+#[test]
+fn expr_error() {
+    let source = "(a, [b)";
+    let error = expr::check(source).unwrap_err();
+
+    assert_eq!(
+        render(source, Error::from(&error)),
+        "\
+error: closing bracket mismatch, opened \"[\" at 1:5, encountered \")\"
+ --> 1:7
+  |
+1 | (a, [b)
+  |       ^
+  |     - opened here
+"
+    );
+}
+
+#[test]
+fn schema_file_error() {
+    let source = "type Foo {";
+    let error = schema_file::validate(source).unwrap_err();
+
+    assert_eq!(
+        render(source, Error::from(&error)),
+        "\
+error: bracket `{` has never been closed
+ --> 1:10
+  |
+1 | type Foo {
+  |          ^
+"
+    );
+}
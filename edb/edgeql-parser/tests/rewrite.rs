@@ -0,0 +1,142 @@
+use edgeql_parser::format::KeywordCase;
+#[cfg(feature = "embedded-spec")]
+use edgeql_parser::parser::spec;
+use edgeql_parser::rewrite::{rewrite, Options};
+
+fn upper(source: &str) -> String {
+    rewrite(source, &Options::default()).unwrap()
+}
+
+fn lower(source: &str) -> String {
+    let options = Options {
+        keyword_case: KeywordCase::Lower,
+        ..Options::default()
+    };
+    rewrite(source, &options).unwrap()
+}
+
+#[cfg(feature = "embedded-spec")]
+fn upper_with_spec(source: &str) -> String {
+    let options = Options {
+        spec: Some(spec()),
+        ..Options::default()
+    };
+    rewrite(source, &options).unwrap()
+}
+
+#[test]
+fn keywords() {
+    assert_eq!(
+        upper("select User { name } filter .name = 'x' order by .name;"),
+        "SELECT User { name } FILTER .name = 'x' order BY .name;"
+    );
+    assert_eq!(
+        lower("SELECT User FILTER .name = 'x'  ORDER\n  BY .name;"),
+        "select User filter .name = 'x'  ORDER\n  by .name;"
+    );
+}
+
+#[cfg(feature = "embedded-spec")]
+#[test]
+fn keywords_with_spec() {
+    assert_eq!(
+        upper_with_spec("select User { name } filter .name = 'x' order by .name;"),
+        "SELECT User { name } FILTER .name = 'x' ORDER BY .name;"
+    );
+    assert_eq!(
+        upper_with_spec("create abstract type Foo { create property type -> str; }"),
+        "CREATE ABSTRACT TYPE Foo { CREATE PROPERTY type -> str; }"
+    );
+    assert_eq!(
+        upper_with_spec("alter type Foo { create annotation title := 'x'; }"),
+        "ALTER TYPE Foo { CREATE ANNOTATION title := 'x'; }"
+    );
+    assert_eq!(
+        upper_with_spec("module default { abstract type Foo { required property name -> str; } }"),
+        "MODULE default { ABSTRACT TYPE Foo { REQUIRED PROPERTY name -> str; } }"
+    );
+}
+
+#[test]
+fn layout_is_kept() {
+    let source = "select  # Select all\n\t'select' ++ r\"filter\"\n  limit $$ limit $$;";
+    assert_eq!(
+        upper(source),
+        "SELECT  # Select all\n\t'select' ++ r\"filter\"\n  LIMIT $$ limit $$;"
+    );
+}
+
+#[test]
+fn unreserved_keywords_without_spec() {
+    assert_eq!(
+        upper("select count(Object) { name, abstract, required } filter .type"),
+        "SELECT count(Object) { name, abstract, required } FILTER .type"
+    );
+}
+
+#[cfg(feature = "embedded-spec")]
+#[test]
+fn unreserved_keywords_as_names() {
+    assert_eq!(
+        upper_with_spec("select count(Object)"),
+        "SELECT count(Object)"
+    );
+    assert_eq!(
+        upper_with_spec("select schema::ObjectType { name, abstract, required }"),
+        "SELECT schema::ObjectType { name, abstract, required }"
+    );
+    assert_eq!(
+        upper_with_spec("select Foo { multi, link, order: { type } }"),
+        "SELECT Foo { multi, link, order: { type } }"
+    );
+    assert_eq!(
+        upper_with_spec("select Obj { type := .type } filter .<link[is Foo]"),
+        "SELECT Obj { type := .type } FILTER .<link[IS Foo]"
+    );
+}
+
+#[cfg(feature = "embedded-spec")]
+#[test]
+fn parser_error_with_spec() {
+    // only reserved keywords are re-cased
+    assert_eq!(
+        upper_with_spec("select Foo { abstract } order by"),
+        "SELECT Foo { abstract } order BY"
+    );
+}
+
+#[test]
+fn requote_names() {
+    assert_eq!(
+        upper("select `User` { `name`, `select`, `type`, `1x`, `a b`, `a``b` }"),
+        "SELECT User { name, `select`, `type`, `1x`, `a b`, `a``b` }"
+    );
+
+    let options = Options {
+        requote_names: false,
+        ..Options::default()
+    };
+    assert_eq!(rewrite("select `User`", &options).unwrap(), "SELECT `User`");
+}
+
+#[test]
+fn preserve_case() {
+    let options = Options {
+        keyword_case: KeywordCase::Preserve,
+        ..Options::default()
+    };
+    assert_eq!(
+        rewrite("Select `x` Filter true", &options).unwrap(),
+        "Select x Filter true"
+    );
+}
+
+#[test]
+fn tokenizer_error() {
+    let err = rewrite("select 'abc", &Options::default()).unwrap_err();
+    assert!(
+        err.message.starts_with("unterminated string"),
+        "{}",
+        err.message
+    );
+}
@@ -1,4 +1,5 @@
-use edgeql_parser::tokenizer::{Kind, Tokenizer};
+use edgeql_parser::position::Span;
+use edgeql_parser::tokenizer::{Edit, Kind, Tokenizer};
 use edgeql_parser::tokenizer::Kind::*;
 
 fn tok_str(s: &str) -> Vec<String> {
@@ -851,3 +852,238 @@ fn test_substitution() {
     assert_eq!(tok_err("SELECT \\(some_name"),
         "unclosed \\(name) token");
 }
+
+fn tok_trivia(s: &str) -> Vec<(Kind, String)> {
+    let mut r = Vec::new();
+    let mut total = String::new();
+    for token in Tokenizer::new(s).with_trivia() {
+        let token = token.unwrap();
+        assert_eq!(&s[token.span.start as usize..token.span.end as usize],
+                   token.text);
+        total.push_str(&token.text);
+        r.push((token.kind, token.text.to_string()));
+    }
+    assert_eq!(total, s);
+    r
+}
+
+#[test]
+fn trivia() {
+    assert_eq!(tok_trivia(""), []);
+    assert_eq!(tok_trivia("  "), [(Whitespace, "  ".into())]);
+    assert_eq!(tok_trivia("# x"), [(Comment, "# x".into())]);
+    assert_eq!(tok_trivia("  SELECT# one\n\t1 +2;\r\n# two\n"), [
+        (Whitespace, "  ".into()),
+        (keyword("select"), "SELECT".into()),
+        (Comment, "# one".into()),
+        (Whitespace, "\n\t".into()),
+        (IntConst, "1".into()),
+        (Whitespace, " ".into()),
+        (Add, "+".into()),
+        (IntConst, "2".into()),
+        (Semicolon, ";".into()),
+        (Whitespace, "\r\n".into()),
+        (Comment, "# two".into()),
+        (Whitespace, "\n".into()),
+    ]);
+    assert_eq!(tok_trivia("a # b\n# c\nd"), [
+        (Ident, "a".into()),
+        (Whitespace, " ".into()),
+        (Comment, "# b".into()),
+        (Whitespace, "\n".into()),
+        (Comment, "# c".into()),
+        (Whitespace, "\n".into()),
+        (Ident, "d".into()),
+    ]);
+}
+
+#[test]
+fn trivia_checkpoint() {
+    let mut tokenizer = Tokenizer::new("a  b").with_trivia();
+    assert_eq!(tokenizer.next().unwrap().unwrap().text, "a");
+    let checkpoint = tokenizer.checkpoint();
+    assert_eq!(tokenizer.next().unwrap().unwrap().kind, Whitespace);
+    assert_eq!(tokenizer.next().unwrap().unwrap().text, "b");
+    tokenizer.reset(checkpoint);
+    assert_eq!(tokenizer.next().unwrap().unwrap().text, "  ");
+    assert_eq!(tokenizer.next().unwrap().unwrap().text, "b");
+    assert!(tokenizer.next().is_none());
+}
+
+fn tok_recover(s: &str) -> (Vec<(Kind, String)>, Vec<String>) {
+    let mut tokens = Vec::new();
+    let mut errors = Vec::new();
+    for res in Tokenizer::new(s).with_error_recovery().validated_values() {
+        match res {
+            Ok(t) => tokens.push((t.kind, t.text.to_string())),
+            Err(e) => errors.push(e.message),
+        }
+    }
+    (tokens, errors)
+}
+
+#[test]
+fn error_recovery() {
+    assert_eq!(tok_recover("SELECT 'abc def"), (
+        vec![
+            (keyword("select"), "SELECT".into()),
+            (Unknown, "'abc".into()),
+            (Ident, "def".into()),
+        ],
+        vec!["unterminated string, quoted by `'`".into()],
+    ));
+    assert_eq!(tok_recover("a ? b ! c"), (
+        vec![
+            (Ident, "a".into()),
+            (Unknown, "?".into()),
+            (Ident, "b".into()),
+            (Unknown, "!".into()),
+            (Ident, "c".into()),
+        ],
+        vec![
+            "Bare `?` is not an operator, did you mean `?=` or `??` ?".into(),
+            "Bare `!` is not an operator, did you mean `!=`?".into(),
+        ],
+    ));
+    assert_eq!(tok_recover("1d + 2; $$ x"), (
+        vec![
+            (Unknown, "1d".into()),
+            (Add, "+".into()),
+            (IntConst, "2".into()),
+            (Semicolon, ";".into()),
+            (Unknown, "$$".into()),
+            (Ident, "x".into()),
+        ],
+        vec![
+            "suffix \"d\" is invalid for numbers, \
+             perhaps you wanted `1n` (bigint)?".into(),
+            "unterminated string started with $$".into(),
+        ],
+    ));
+    // errors from value validation
+    assert_eq!(tok_recover("99999999999999999999 + 1"), (
+        vec![
+            (Unknown, "99999999999999999999".into()),
+            (Add, "+".into()),
+            (IntConst, "1".into()),
+        ],
+        vec!["error reading int: number too large to fit in target type"
+             .into()],
+    ));
+}
+
+fn tokenizer_in_mode(s: &str, mode: usize) -> Tokenizer<'_> {
+    match mode {
+        0 => Tokenizer::new(s),
+        1 => Tokenizer::new(s).with_trivia(),
+        _ => Tokenizer::new(s).with_trivia().with_error_recovery(),
+    }
+}
+
+fn check_retokenize(mode: usize, old: &str, start: usize, end: usize,
+                    replacement: &str)
+{
+    let tokenizer = |s| tokenizer_in_mode(s, mode);
+    let recovering = mode == 2;
+    let new = format!("{}{}{}", &old[..start], replacement, &old[end..]);
+    let old_tokens: Vec<_> = tokenizer(old).map_while(Result::ok).collect();
+    let expected: Vec<_> = if recovering {
+        tokenizer(&new).filter_map(Result::ok).collect()
+    } else {
+        tokenizer(&new).map_while(Result::ok).collect()
+    };
+
+    let edit = Edit {
+        span: Span { start: start as u64, end: end as u64 },
+        replacement,
+    };
+    let (tokens, _) = tokenizer(&new).retokenize(&old_tokens, &edit);
+    let tokens: Vec<_> = tokens.iter()
+        .map(|t| (t.kind, t.text.to_string(), t.span.start, t.span.end))
+        .collect();
+    let expected: Vec<_> = expected.iter()
+        .map(|t| (t.kind, t.text.to_string(), t.span.start, t.span.end))
+        .collect();
+    assert_eq!(tokens, expected, "{:?} -> {:?}", old, new);
+}
+
+#[test]
+fn retokenize() {
+    let sources = [
+        "SELECT User { name, friends: { name } } FILTER .id = <uuid>$id;",
+        "  # comment\nselect (a.1, 'str', 12.5e3, $$dollar$$) ; ",
+    ];
+    let edits = ["", "x", " ", "1", ".", "'", "#", "\n", "ab cd"];
+    for source in sources {
+        for mode in 0..3 {
+            for start in 0..=source.len() {
+                for edit in edits {
+                    check_retokenize(mode, source, start, start, edit);
+                    if start < source.len() {
+                        check_retokenize(mode, source, start, start + 1, edit);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn retokenize_reuses_tokens() {
+    let old = "select a; select b";
+    let new = "select abc; select b";
+    let old_tokens: Vec<_> = Tokenizer::new(old).map(Result::unwrap).collect();
+    let edit = Edit {
+        span: Span { start: 8, end: 8 },
+        replacement: "bc",
+    };
+    let (tokens, errors) = Tokenizer::new(new).retokenize(&old_tokens, &edit);
+    assert!(errors.is_empty());
+    assert_eq!(
+        tokens.iter().map(|t| &t.text[..]).collect::<Vec<_>>(),
+        ["select", "abc", ";", "select", "b"]);
+    assert_eq!(tokens[4].span, Span { start: 19, end: 20 });
+}
+
+#[test]
+fn version() {
+    use edgeql_parser::keywords::Version;
+
+    let kinds = |s, version| {
+        Tokenizer::new(s).with_version(version)
+            .map(|t| t.unwrap().kind)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(kinds("create branch Foo", Version::new(5, 0)),
+        [keyword("create"), keyword("branch"), Ident]);
+    assert_eq!(kinds("create branch Foo", Version::new(4, 8)),
+        [keyword("create"), Ident, Ident]);
+    assert_eq!(kinds("ACCESS Policy", Version::new(1, 4)), [Ident, Ident]);
+    assert_eq!(kinds("select union", Version::new(1, 0)),
+        [keyword("select"), keyword("union")]);
+}
+
+#[test]
+fn keyword_classes() {
+    use edgeql_parser::keywords::{self, Keyword, KeywordClass, Version};
+
+    let v2 = Version::new(2, 0);
+    assert_eq!(keywords::class_at("trigger", v2), None);
+    assert_eq!(keywords::class_at("trigger", Version::new(3, 0)),
+        Some(KeywordClass::Unreserved));
+    assert_eq!(keywords::class_at("order by", v2), Some(KeywordClass::Combined));
+    assert_eq!(keywords::lookup_at("select", v2), Some(Keyword("select")));
+    assert_eq!(keywords::lookup_at("trigger", Version::new(4, 0)), None);
+    assert!(Keyword("window").is_reserved_at(v2));
+    assert!(!Keyword("policy").is_reserved_at(v2));
+    assert_eq!(keywords::class_at("rewrite", Version::new(2, 12)), None);
+    assert_eq!(keywords::class_at("rewrite", Version::new(2, 13)),
+        Some(KeywordClass::Unreserved));
+    assert_eq!(keywords::class_at("except", v2),
+        Some(KeywordClass::FutureReserved));
+    assert_eq!(keywords::class_at("except", Version::new(3, 0)),
+        Some(KeywordClass::PartialReserved));
+    assert!(Keyword("analyze").is_reserved_at(v2));
+    assert_eq!(keywords::lookup_all_at("then", Version::new(3, 6)), None);
+    assert_eq!(v2.to_string(), "2.0");
+}
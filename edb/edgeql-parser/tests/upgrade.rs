@@ -0,0 +1,107 @@
+//! Finding names in trees parsed with the real grammar, which requires the
+//! spec to be embedded (see `build.rs`).
+
+use edgeql_parser::diagnostics::ErrorCode;
+use edgeql_parser::parser::spec;
+use edgeql_parser::position::Span;
+use edgeql_parser::tokenizer::Error;
+use edgeql_parser::upgrade;
+
+fn check_reserved_names(source: &str) -> Result<Vec<Error>, Error> {
+    upgrade::check_reserved_names(source, spec())
+}
+
+fn fix_reserved_names(source: &str) -> Result<String, Error> {
+    upgrade::fix_reserved_names(source, spec())
+}
+
+#[test]
+fn future_reserved() {
+    let source = "select User { window := .match } filter .Single = 1";
+    let errors = check_reserved_names(source).unwrap();
+    let messages: Vec<_> = errors.iter().map(|e| e.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        [
+            "Name 'window' will be reserved in a future version",
+            "Name 'match' will be reserved in a future version",
+            "Name 'Single' will be reserved in a future version",
+        ]
+    );
+
+    let error = &errors[0];
+    assert_eq!(error.code, Some(ErrorCode::ReservedKeyword));
+    assert_eq!(error.span, Span { start: 14, end: 20 });
+    assert_eq!(
+        error.hint.as_deref(),
+        Some("quote the name with backticks: `window`")
+    );
+    assert_eq!(error.suggestions()[0].replacement, "`window`");
+}
+
+#[test]
+fn partially_reserved() {
+    let errors = check_reserved_names("select Foo { union: { name } }").unwrap();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(
+        errors[0].message,
+        "Name 'union' is reserved in some positions"
+    );
+}
+
+#[test]
+fn definitions() {
+    let source = "\
+module default {
+    type Check {
+        property begin -> str;
+        multi link over: Foo;
+        index on (.begin);
+    }
+}";
+    assert_eq!(
+        fix_reserved_names(source).unwrap(),
+        "\
+module default {
+    type `Check` {
+        property `begin` -> str;
+        multi link `over`: Foo;
+        index on (.`begin`);
+    }
+}"
+    );
+}
+
+#[test]
+fn shape_elements() {
+    assert_eq!(
+        fix_reserved_names("select Foo { global, union, name } filter .window").unwrap(),
+        "select Foo { `global`, `union`, name } filter .`window`"
+    );
+    assert_eq!(
+        fix_reserved_names("select count(window) union match").unwrap(),
+        "select count(`window`) union `match`"
+    );
+}
+
+#[test]
+fn keywords_are_not_reported() {
+    let source = "select User filter .name = 'window' union (select Foo);\
+        alter type Foo { create index on (.x) }; with module foo select 1;\
+        create global foo -> str; select global foo;\
+        select Foo { single x := 1 } except Bar";
+    assert!(check_reserved_names(source).unwrap().is_empty());
+    assert_eq!(fix_reserved_names(source).unwrap(), source);
+}
+
+#[test]
+fn parser_error() {
+    let err = check_reserved_names("select Foo { window } filter").unwrap_err();
+    assert!(err.message.starts_with("Missing"), "{}", err.message);
+}
+
+#[test]
+fn tokenizer_error() {
+    let err = check_reserved_names("select `").unwrap_err();
+    assert_eq!(err.message, "unterminated backtick name");
+}
@@ -1054,7 +1054,7 @@ class CreateConcretePointer(CreateObject):
     is_required: typing.Optional[bool] = None
     declared_overloaded: bool = False
     target: typing.Optional[typing.Union[Expr, TypeExpr]]
-    cardinality: qltypes.SchemaCardinality
+    cardinality: typing.Optional[qltypes.SchemaCardinality]
     bases: typing.List[TypeName]
 
 
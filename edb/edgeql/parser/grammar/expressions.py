@@ -1540,13 +1540,28 @@ class Constant(Nonterm):
     def reduce_PARAMETERANDTYPE(self, param):
         assert param.val.startswith('<lit ')
         type_name, param_name = param.val.removeprefix('<lit ').split('>$')
-        self.val = qlast.TypeCast(
-            type=qlast.TypeName(
+        if type_name.startswith('array<'):
+            element_name = type_name.removeprefix('array<').removesuffix('>')
+            typename = qlast.TypeName(
+                maintype=qlast.ObjectRef(name='array'),
+                subtypes=[
+                    qlast.TypeName(
+                        maintype=qlast.ObjectRef(
+                            name=element_name,
+                            module='__std__'
+                        )
+                    ),
+                ],
+            )
+        else:
+            typename = qlast.TypeName(
                 maintype=qlast.ObjectRef(
                     name=type_name,
                     module='__std__'
                 )
-            ),
+            )
+        self.val = qlast.TypeCast(
+            type=typename,
             expr=qlast.Parameter(name=param_name),
         )
 
@@ -182,6 +182,11 @@ def _tokenize(eql: str) -> ql_parser.ParserResult:
 
 
 def _normalize(eql: str) -> ql_parser.Entry:
+    # literals to extract are found with the grammar
+    from edb.edgeql import parser as qlparser
+    if not qlparser.SPEC_LOADED:
+        qlparser.preload_spec()
+
     try:
         return ql_parser.normalize(eql)
     except ql_parser.SyntaxError as e:
@@ -1,6 +1,7 @@
 import itertools
 import typing
 import dataclasses
+import enum
 import textwrap
 from itertools import chain
 
@@ -46,6 +47,7 @@ def main() -> None:
 
             //! Abstract Syntax Tree for EdgeQL
             #![allow(non_camel_case_types)]
+            #![allow(clippy::large_enum_variant, clippy::type_complexity)]
 
             use indexmap::IndexMap;
 
@@ -85,7 +87,9 @@ def main() -> None:
     # generate enums
     for name, typ in chain(qlast.__dict__.items(), qltypes.__dict__.items()):
 
-        if not isinstance(typ, type) or not issubclass(typ, s_enum.StrEnum):
+        if not isinstance(typ, type) or not issubclass(
+            typ, (s_enum.StrEnum, enum.IntEnum)
+        ):
             continue
 
         f.write(codegen_enum(name, typ))
@@ -96,7 +100,7 @@ def codegen_struct(cls: ASTClass) -> str:
     fields = ''
     doc_comment = ''
 
-    for f in typing.cast(typing.List[ast._Field], cls.typ._direct_fields):
+    for f in collect_fields(cls.typ):
 
         if f.hidden:
             continue
@@ -147,6 +151,32 @@ def codegen_struct(cls: ASTClass) -> str:
     ).replace('{\n}', r'{}')
 
 
+def collect_fields(typ: typing.Type) -> typing.List[ast._Field]:
+    # Fields of mixins that are not generated themselves (such as
+    # ConcreteConstraintOp) are inlined into the classes that use them.
+    # object_class is skipped, as the mixins only use it to give each
+    # command class its own default.
+    fields = list(typing.cast(typing.List[ast._Field], typ._direct_fields))
+    names = {f.name for f in fields}
+    skipped = {'object_class'}
+    for base in ignored_mixins(typ):
+        for f in base._direct_fields:
+            if f.name not in names and f.name not in skipped:
+                names.add(f.name)
+                fields.append(f)
+    return fields
+
+
+def ignored_mixins(typ: typing.Type) -> typing.List[typing.Type]:
+    mixins = []
+    for base in typ.__bases__:
+        if base.__name__ in ast_classes or not hasattr(base, '_direct_fields'):
+            continue
+        mixins.extend(ignored_mixins(base))
+        mixins.append(base)
+    return mixins
+
+
 def codegen_enum(name: str, cls: typing.Type) -> str:
     fields = ''
     for member in cls._member_names_:
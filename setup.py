@@ -889,6 +889,12 @@ class build_parsers(setuptools.Command):
         import edb._edgeql_parser as rust_parser
         rust_parser.save_spec(spec_json, str(dst))
 
+        # refresh the copy embedded into the Rust crate (with the
+        # `embedded-spec` feature), which is checked in
+        if self.inplace:
+            shutil.copyfile(
+                dst, ROOT_PATH / 'edb' / 'edgeql-parser' / 'grammar.bc')
+
 
 class build_rust(setuptools_rust.build.build_rust):
     def run(self):
@@ -0,0 +1,166 @@
+#
+# This source file is part of the EdgeDB open source project.
+#
+# Copyright 2024-present MagicStack Inc. and the EdgeDB authors.
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#     http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+#
+
+
+import unittest
+
+from edb import _edgeql_parser as rust_parser
+from edb.edgeql import ast as qlast
+from edb.edgeql import codegen as qlcodegen
+from edb.edgeql import parser as qlparser
+from edb.edgeql import tokenizer
+
+
+class TestEdgeQLRustAST(unittest.TestCase):
+    """Tests of the AST lowered by the Rust parser."""
+
+    @classmethod
+    def setUpClass(cls):
+        qlparser.preload_spec()
+
+    def parse_ast(self, start, text):
+        source = tokenizer.Source.from_string(text)
+        return rust_parser.parse_ast(start, source.tokens())
+
+    def assert_same_ast(self, text):
+        expected = qlparser.parse_block(text)
+        actual = self.parse_ast('STARTBLOCK', text)
+
+        self.assertEqual(len(actual), len(expected))
+        for act, exp in zip(actual, expected):
+            self.assertIs(type(act), type(exp))
+            self.assertEqual(
+                qlcodegen.generate_source(act),
+                qlcodegen.generate_source(exp),
+            )
+
+    def test_edgeql_rust_ast_select_01(self):
+        [stmt] = self.parse_ast('STARTBLOCK', 'select 1;')
+
+        self.assertIsInstance(stmt, qlast.SelectQuery)
+        self.assertIsInstance(stmt.result, qlast.Constant)
+        self.assertEqual(stmt.result.kind, qlast.ConstantKind.INTEGER)
+        self.assertEqual(stmt.result.value, '1')
+
+    def test_edgeql_rust_ast_select_02(self):
+        self.assert_same_ast('''
+            select User {
+                name,
+                friends: { name } filter .active order by .name limit 3,
+                required multi emails := .email ++ '@example.com',
+            }
+            filter .name like 'A%'
+            order by .name desc empty last
+            offset 2 limit 10;
+        ''')
+
+    def test_edgeql_rust_ast_select_03(self):
+        self.assert_same_ast('''
+            select a if b else c;
+            select Foo.<bar[is Baz]@p;
+            select (a := 1, b := 2).a;
+            select <optional str>$x ?? [1, 2][0:1];
+            select count(User filter .age > 18) is not Object;
+        ''')
+
+    def test_edgeql_rust_ast_statements_01(self):
+        self.assert_same_ast('''
+            with module std, x := -1
+            insert User { name := <str>x }
+            unless conflict on .name else (select User);
+
+            update User filter .name = 'a' set { tags += 'b' };
+            delete User filter not exists .name limit 1;
+            for optional x in {1, 2} union (select x);
+            group User using k := .name[0] by k, rollup(.age);
+            create module foo;
+            drop module foo;
+        ''')
+
+    def test_edgeql_rust_ast_ddl_01(self):
+        self.assert_same_ast('''
+            create module foo if not exists;
+            create scalar type Color extending enum<Red, Green>;
+            create abstract type foo::Named {
+                create required single property name: str {
+                    create constraint exclusive;
+                    create annotation title := 'Name';
+                };
+                create index on (.name) except (.name = '');
+            };
+            create type Foo extending foo::Named {
+                create multi link friends -> Foo {
+                    create property since: datetime;
+                    on target delete allow;
+                };
+                create property tags := array_agg(.friends.name);
+                create property upper {
+                    using (str_upper(.name));
+                };
+            };
+            create alias Foos := (select Foo);
+            create global current -> uuid;
+            create abstract constraint max_len(max: int64) on (len(__subject__))
+                extending std::max_value {
+                set errmessage := 'too long';
+            };
+        ''')
+
+    def test_edgeql_rust_ast_ddl_02(self):
+        self.assert_same_ast('''
+            alter type Foo {
+                rename to Bar;
+                extending Baz first;
+                alter property name {
+                    set required using ('x');
+                    set type str using (<str>.name);
+                    reset cardinality;
+                    alter annotation title := 'N';
+                };
+                alter link friends {
+                    drop property since;
+                    reset on target delete;
+                };
+                drop constraint exclusive on (.name);
+            };
+            alter scalar type Color {
+                create constraint one_of('Red', 'Green');
+            };
+            drop alias Foos;
+            drop type Foo;
+            drop module foo;
+        ''')
+
+    def test_edgeql_rust_ast_types_01(self):
+        self.assert_same_ast('''
+            select <array<str>>x;
+            select <tuple<a: int64, b: tuple<str, bool>>>x;
+            select <optional array<int64>>[];
+        ''')
+
+    def test_edgeql_rust_ast_fragment_01(self):
+        expr = self.parse_ast('STARTFRAGMENT', '1 + x.y')
+
+        self.assertIsInstance(expr, qlast.BinOp)
+        self.assertEqual(qlcodegen.generate_source(expr), '(1 + x.y)')
+
+    def test_edgeql_rust_ast_error_01(self):
+        with self.assertRaisesRegex(
+            rust_parser.SyntaxError, 'cannot lower .* into AST'
+        ):
+            self.parse_ast('STARTBLOCK', 'create function foo() -> str using (1);')
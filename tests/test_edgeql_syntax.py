@@ -1837,6 +1837,15 @@ aa';
         };
         """
 
+    @tb.must_fail(errors.EdgeQLSyntaxError,
+                  r"Unexpected '='",
+                  hint="Use ':=' to assign a value to a shape element",
+                  line=2, col=28)
+    def test_edgeql_syntax_shape_73(self):
+        """
+        SELECT User { name = 'x' };
+        """
+
     def test_edgeql_syntax_shape_splat_01(self):
         """
         select Foo {
@@ -2831,6 +2840,32 @@ aa';
         default::Movie.name;
         """
 
+    @tb.must_fail(errors.EdgeQLSyntaxError,
+                  r"Unexpected 'WHERE'",
+                  hint="Use `filter` to select elements that satisfy a "
+                       "condition",
+                  line=2, col=21)
+    def test_edgeql_syntax_select_14(self):
+        """
+        SELECT User WHERE .name = 'x';
+        """
+
+    @tb.must_fail(errors.EdgeQLSyntaxError,
+                  r"Unexpected 'JOIN'", line=2, col=21)
+    def test_edgeql_syntax_select_15(self):
+        """
+        SELECT User JOIN Friend;
+        """
+
+    @tb.must_fail(errors.EdgeQLSyntaxError,
+                  r"Unexpected keyword 'AS'",
+                  hint="Use `alias := <expr>` to name an expression",
+                  line=2, col=26)
+    def test_edgeql_syntax_select_16(self):
+        """
+        SELECT User.name AS n;
+        """
+
     def test_edgeql_syntax_group_01(self):
         """
         GROUP User
@@ -3422,6 +3457,17 @@ aa';
         SELECT x
         """
 
+    @tb.must_fail(errors.EdgeQLSyntaxError,
+                  r"Unexpected '='",
+                  hint="Use ':=' to assign a value to a shape element",
+                  line=3, col=18)
+    def test_edgeql_syntax_update_09(self):
+        """
+        UPDATE User SET {
+            name = 'x'
+        };
+        """
+
     def test_edgeql_syntax_insertfor_01(self):
         """
         FOR name IN {'a', 'b', 'c'}
@@ -6392,6 +6438,13 @@ aa';
         START TRANSACTION ISOLATION SERIALIZABLE, READ WRITE, NOT DEFERRABLE;
         """
 
+    @tb.must_fail(errors.EdgeQLSyntaxError,
+                  r"Unexpected trailing comma", line=2, col=49)
+    def test_edgeql_syntax_transaction_07(self):
+        """
+        START TRANSACTION READ WRITE, DEFERRABLE,;
+        """
+
     def test_edgeql_syntax_describe_01(self):
         """
         DESCRIBE SCHEMA AS DDL;
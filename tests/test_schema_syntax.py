@@ -706,6 +706,17 @@ class TestEdgeSchemaParser(SchemaSyntaxTest):
         };
         """
 
+    @tb.must_fail(errors.EdgeQLSyntaxError,
+                  r"Unexpected trailing comma", line=3, col=40)
+    def test_eschema_syntax_type_37(self):
+        """
+        module test {
+            type Foo extending Bar, Baz, {
+                property name: str;
+            };
+        };
+        """
+
     def test_eschema_syntax_link_target_type_01(self):
         """
         module test {
@@ -1799,6 +1810,17 @@ abstract property test::foo {
         };
         """
 
+    @tb.must_fail(errors.EdgeQLSyntaxError,
+                  r"Unexpected ':'",
+                  hint="Use '->' to declare the return type of a function",
+                  line=3, col=33)
+    def test_eschema_syntax_function_23(self):
+        """
+        module test {
+            function foo(a: str): str using (a);
+        };
+        """
+
     def test_eschema_syntax_alias_01(self):
         """
         module test {